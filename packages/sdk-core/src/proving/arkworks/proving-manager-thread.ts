@@ -76,6 +76,7 @@ export class ArkworksProvingManagerThread {
     if (protocol === 'mixer') {
       const input = pmSetupInput as MixerPMSetupInput;
       const { note } = await Note.deserialize(input.note);
+      const wasm = await this.wasmBlob;
 
       pm.setLeaves(input.leaves);
       pm.setRelayer(input.relayer);
@@ -83,7 +84,7 @@ export class ArkworksProvingManagerThread {
       pm.setLeafIndex(String(input.leafIndex));
       pm.setRefund(String(input.refund));
       pm.setFee(String(input.fee));
-      pm.setPk(u8aToHex(input.provingKey).replace('0x', ''));
+      pm.setPk(new wasm.JsProvingKey(input.provingKey, note.curve));
       pm.setNote(note);
 
       const proofInput = pm.build_js();
@@ -161,7 +162,7 @@ export class ArkworksProvingManagerThread {
 
       pm.setInputUtxos(inputUtxos.map((utxo) => utxo.inner));
       pm.setIndices(leafIds.map((i) => i.index.toString()) as any);
-      pm.setPk(u8aToHex(input.provingKey).replace('0x', ''));
+      pm.setPk(new wasm.JsProvingKey(input.provingKey));
       pm.setRoots(input.roots);
       pm.chain_id(input.chainId);
       pm.public_amount(input.publicAmount);
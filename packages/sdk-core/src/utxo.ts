@@ -52,8 +52,13 @@ export class Utxo {
     // The wasmUtxo is a string representation of the utxo. It has all of the information
     // required of the parts - except for the Keypair information used for encryption.
     const wasmUtxoString = this.inner.serialize();
+    // Drop the `v1:` version prefix and the trailing checksum of the wasm encoding,
+    // the unversioned form is still accepted by `JsUtxo.deserialize`.
+    const unversioned = wasmUtxoString.startsWith('v1:')
+      ? wasmUtxoString.slice('v1:'.length, wasmUtxoString.lastIndexOf(':'))
+      : wasmUtxoString;
 
-    const parts = wasmUtxoString.split('&');
+    const parts = unversioned.split('&');
 
     const encryptionKey = this.keypair.getEncryptionKey()?.slice(2) ?? '';
 
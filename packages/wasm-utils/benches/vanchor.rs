@@ -0,0 +1,92 @@
+//! Single and multi-threaded proving of the 2 and 16 inputs VAnchor circuits
+//!
+//! `cargo bench --no-default-features --features parallel`
+use std::collections::BTreeMap;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::rngs::OsRng;
+use rayon::ThreadPoolBuilder;
+use wasm_utils::amount::Amount;
+use wasm_utils::proof::{setup_keys_with_height, MTBn254X5, ProofInputBuilder, ProvingKey};
+use wasm_utils::types::{Backend, Curve, NoteProtocol};
+use wasm_utils::{create_proof, ProofInput, Utxo, TREE_HEIGHT};
+
+const CHAIN_ID: u64 = 0;
+const AMOUNT: u128 = 10;
+
+fn vanchor_input(ins: usize) -> ProofInput {
+	let (pk, _) = setup_keys_with_height(NoteProtocol::VAnchor, Curve::Bn254, TREE_HEIGHT, 2, ins as u32, 2).unwrap();
+
+	let inputs: Vec<Utxo> = (0..ins as u64)
+		.map(|index| {
+			Utxo::new(
+				Curve::Bn254,
+				Backend::Arkworks,
+				AMOUNT,
+				CHAIN_ID,
+				None,
+				None,
+				None,
+				Some(index),
+			)
+			.unwrap()
+		})
+		.collect();
+	let outputs = [AMOUNT * ins as u128, 0].map(|amount| {
+		Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			amount,
+			CHAIN_ID,
+			None,
+			None,
+			None,
+			None,
+		)
+		.unwrap()
+	});
+	let leaves: Vec<Vec<u8>> = inputs.iter().map(|utxo| utxo.get_commitment()).collect();
+	let root = MTBn254X5::from_leaves(&leaves, 0).unwrap().get_root();
+
+	let mut builder = ProofInputBuilder::new(NoteProtocol::VAnchor);
+	builder.exponentiation(5).unwrap();
+	builder.width(5).unwrap();
+	builder.curve(Curve::Bn254).unwrap();
+	builder.backend(Backend::Arkworks).unwrap();
+	builder.chain_id(CHAIN_ID.into()).unwrap();
+	builder.public_amount(Amount::from(0i128)).unwrap();
+	builder.ext_data_hash([1u8; 32].to_vec()).unwrap();
+	builder.roots(vec![root.clone(), root]).unwrap();
+	builder.leaf_indices((0..ins as u64).collect()).unwrap();
+	builder.leaves_map(BTreeMap::from([(CHAIN_ID, leaves)])).unwrap();
+	builder.set_input_utxos(inputs).unwrap();
+	builder.set_output_utxos(outputs).unwrap();
+	builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+	builder.build().unwrap()
+}
+
+fn bench_vanchor(c: &mut Criterion, ins: usize) {
+	let input = vanchor_input(ins);
+	let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+	let mut group = c.benchmark_group(format!("vanchor_{}_inputs", ins));
+	group.sample_size(10);
+	for threads in [1, cores] {
+		let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+		group.bench_with_input(BenchmarkId::new("threads", threads), &input, |b, input| {
+			b.iter(|| pool.install(|| create_proof(input.clone(), &mut OsRng).unwrap()))
+		});
+	}
+	group.finish();
+}
+
+fn vanchor_2_inputs(c: &mut Criterion) {
+	bench_vanchor(c, 2);
+}
+
+fn vanchor_16_inputs(c: &mut Criterion) {
+	bench_vanchor(c, 16);
+}
+
+criterion_group!(benches, vanchor_2_inputs, vanchor_16_inputs);
+criterion_main!(benches);
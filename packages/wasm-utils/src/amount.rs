@@ -0,0 +1,397 @@
+//! Signed amounts of VAnchor transactions and their encoding in the scalar
+//! field of the proof curve, and the conversion of token amounts from and to
+//! decimals
+use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::ops::{Add, AddAssign, Neg, Sub};
+use core::str::FromStr;
+
+use ark_bls12_381::Fr as Bls381Fr;
+use ark_bn254::Fr as Bn254Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ethabi::Uint as U256;
+#[cfg(feature = "js")]
+use js_sys::{Array, JsString};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::types::{Curve, OpStatusCode, OperationError};
+
+/// Largest public amount (largest deposit) the VAnchor prover accepts
+///
+/// Half of the BN254 scalar field, the smaller of the two curves: a
+/// withdrawal `-x` is encoded as `FIELD_SIZE - x`, so larger magnitudes would
+/// collide with the encoding of the opposite sign.
+pub const MAX_PUBLIC_AMOUNT: Amount = Amount {
+	negative: false,
+	magnitude: U256([
+		0xa1f0_fac9_f800_0000,
+		0x9419_f424_3cdc_b848,
+		0xdc28_22db_40c0_ac2e,
+		0x1832_2739_7098_d014,
+	]),
+};
+/// Smallest public amount (largest withdrawal) the VAnchor prover accepts
+pub const MIN_PUBLIC_AMOUNT: Amount = Amount {
+	negative: true,
+	magnitude: MAX_PUBLIC_AMOUNT.magnitude,
+};
+/// Largest amount a single UTXO can hold
+pub const MAX_UTXO_AMOUNT: u128 = u128::MAX;
+
+/// Signed 256 bit amount
+///
+/// Sums of UTXO amounts and public amounts are accounted with it, as they can
+/// leave the `i128` range for tokens with 18 or more decimals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Amount {
+	negative: bool,
+	magnitude: U256,
+}
+
+impl Amount {
+	fn new(negative: bool, magnitude: U256) -> Self {
+		// Zero is never negative, keeps the equality structural
+		Self {
+			negative: negative && !magnitude.is_zero(),
+			magnitude,
+		}
+	}
+
+	pub fn is_negative(&self) -> bool {
+		self.negative
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.magnitude.is_zero()
+	}
+
+	/// Whether the amount is in `[MIN_PUBLIC_AMOUNT, MAX_PUBLIC_AMOUNT]`
+	pub fn is_public_amount(&self) -> bool {
+		self.magnitude <= MAX_PUBLIC_AMOUNT.magnitude
+	}
+
+	/// The amount as an element of the field `F`, a negative amount `-x` is
+	/// encoded as `FIELD_SIZE - x` like the solidity contracts and sdk-core do
+	pub fn to_field<F: PrimeField>(&self) -> F {
+		let mut bytes = [0u8; 32];
+		self.magnitude.to_big_endian(&mut bytes);
+		let magnitude = F::from_be_bytes_mod_order(&bytes);
+		if self.negative {
+			-magnitude
+		} else {
+			magnitude
+		}
+	}
+
+	/// Big-endian bytes of `to_field` in the scalar field of `curve`
+	pub fn to_field_bytes(&self, curve: Curve) -> [u8; 32] {
+		let bytes = match curve {
+			Curve::Bn254 => self.to_field::<Bn254Fr>().into_repr().to_bytes_be(),
+			Curve::Bls381 => self.to_field::<Bls381Fr>().into_repr().to_bytes_be(),
+		};
+		bytes.try_into().expect("amount: a field element is 32 bytes")
+	}
+
+	pub fn to_i128(&self) -> Option<i128> {
+		if self.magnitude > U256::from(u128::MAX) {
+			return None;
+		}
+		let magnitude = self.magnitude.as_u128();
+		match (self.negative, i128::try_from(magnitude)) {
+			(false, Ok(magnitude)) => Some(magnitude),
+			(true, Ok(magnitude)) => Some(-magnitude),
+			// `i128::MIN` has no positive counterpart
+			(true, Err(_)) if magnitude == i128::MIN.unsigned_abs() => Some(i128::MIN),
+			_ => None,
+		}
+	}
+
+	pub fn to_u128(&self) -> Option<u128> {
+		if self.negative || self.magnitude > U256::from(u128::MAX) {
+			return None;
+		}
+		Some(self.magnitude.as_u128())
+	}
+}
+
+impl From<u128> for Amount {
+	fn from(amount: u128) -> Self {
+		Self::new(false, U256::from(amount))
+	}
+}
+
+impl From<U256> for Amount {
+	fn from(amount: U256) -> Self {
+		Self::new(false, amount)
+	}
+}
+
+impl From<i128> for Amount {
+	fn from(amount: i128) -> Self {
+		Self::new(amount < 0, U256::from(amount.unsigned_abs()))
+	}
+}
+
+impl Neg for Amount {
+	type Output = Amount;
+
+	fn neg(self) -> Self::Output {
+		Self::new(!self.negative, self.magnitude)
+	}
+}
+
+impl Add for Amount {
+	type Output = Amount;
+
+	fn add(self, rhs: Self) -> Self::Output {
+		if self.negative == rhs.negative {
+			return Self::new(self.negative, self.magnitude + rhs.magnitude);
+		}
+		// Opposite signs, the sign of the larger magnitude wins
+		if self.magnitude >= rhs.magnitude {
+			Self::new(self.negative, self.magnitude - rhs.magnitude)
+		} else {
+			Self::new(rhs.negative, rhs.magnitude - self.magnitude)
+		}
+	}
+}
+
+impl AddAssign for Amount {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs;
+	}
+}
+
+impl Sub for Amount {
+	type Output = Amount;
+
+	fn sub(self, rhs: Self) -> Self::Output {
+		self + -rhs
+	}
+}
+
+impl Ord for Amount {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.negative, other.negative) {
+			(false, false) => self.magnitude.cmp(&other.magnitude),
+			(true, true) => other.magnitude.cmp(&self.magnitude),
+			(false, true) => Ordering::Greater,
+			(true, false) => Ordering::Less,
+		}
+	}
+}
+
+impl PartialOrd for Amount {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Decimal amount with an optional `-` sign
+impl FromStr for Amount {
+	type Err = OperationError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (negative, digits) = match s.strip_prefix('-') {
+			Some(digits) => (true, digits),
+			None => (false, s),
+		};
+		if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+			let message = format!("`{}` isn't a decimal amount", s);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidAmount, message));
+		}
+		let magnitude = U256::from_dec_str(digits).map_err(|_| {
+			let message = format!("`{}` doesn't fit in 256 bits", s);
+			OperationError::new_with_message(OpStatusCode::InvalidAmount, message)
+		})?;
+		Ok(Self::new(negative, magnitude))
+	}
+}
+
+impl fmt::Display for Amount {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.negative {
+			write!(f, "-")?;
+		}
+		write!(f, "{}", self.magnitude)
+	}
+}
+
+/// Parse a decimal amount such as `1.5` into the base units of a token with
+/// `decimals`, `parse_units("1.5", 18)` is `1500000000000000000`
+pub fn parse_units(amount: &str, decimals: u8) -> Result<u128, OperationError> {
+	let invalid = || {
+		let message = format!("`{}` isn't an amount with at most {} decimals", amount, decimals);
+		OperationError::new_with_message(OpStatusCode::InvalidAmount, message)
+	};
+	let (whole, fraction) = match amount.split_once('.') {
+		Some((whole, fraction)) => (whole, fraction),
+		None => (amount, ""),
+	};
+	let is_digits = |part: &str| part.bytes().all(|c| c.is_ascii_digit());
+	if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+		return Err(invalid());
+	}
+	if fraction.len() > decimals as usize {
+		return Err(invalid());
+	}
+	let digits = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+	digits.parse().map_err(|_| invalid())
+}
+
+/// Format base units of a token with `decimals` as a decimal amount, without
+/// trailing zeros
+pub fn format_units(amount: u128, decimals: u8) -> String {
+	let digits = format!("{:0>width$}", amount, width = decimals as usize + 1);
+	let (whole, fraction) = digits.split_at(digits.len() - decimals as usize);
+	let fraction = fraction.trim_end_matches('0');
+	if fraction.is_empty() {
+		whole.to_string()
+	} else {
+		format!("{}.{}", whole, fraction)
+	}
+}
+
+/// `[min, max]` of the public amount as decimal strings
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = publicAmountRange)]
+pub fn public_amount_range_js() -> Array {
+	[MIN_PUBLIC_AMOUNT, MAX_PUBLIC_AMOUNT]
+		.iter()
+		.map(|amount| JsString::from(amount.to_string()))
+		.collect()
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = maxUtxoAmount)]
+pub fn max_utxo_amount_js() -> JsString {
+	JsString::from(MAX_UTXO_AMOUNT.to_string())
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = parseUnits)]
+pub fn parse_units_js(amount: JsString, decimals: u8) -> Result<JsString, JsValue> {
+	let amount: String = amount.into();
+	Ok(JsString::from(parse_units(&amount, decimals)?.to_string()))
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = formatUnits)]
+pub fn format_units_js(amount: JsString, decimals: u8) -> Result<JsString, JsValue> {
+	let amount: String = amount.into();
+	let amount = parse_units(&amount, 0)?;
+	Ok(JsString::from(format_units(amount, decimals)))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// BN254 scalar field size, `FIELD_SIZE` in sdk-core
+	const FIELD_SIZE: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+	// BLS12-381 scalar field size
+	const BLS381_FIELD_SIZE: &str = "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+	#[test]
+	fn should_encode_withdrawals_as_field_size_minus_amount() {
+		let mut expected = [0u8; 32];
+		(U256::from_dec_str(FIELD_SIZE).unwrap() - U256::from(5)).to_big_endian(&mut expected);
+		assert_eq!(Amount::from(-5i128).to_field_bytes(Curve::Bn254), expected);
+		assert_eq!(Amount::from(-5i128).to_field::<Bn254Fr>(), Bn254Fr::from(-5i128));
+
+		let mut expected = [0u8; 32];
+		(U256::from_dec_str(FIELD_SIZE).unwrap() - U256::from(i128::MIN.unsigned_abs())).to_big_endian(&mut expected);
+		assert_eq!(Amount::from(i128::MIN).to_field_bytes(Curve::Bn254), expected);
+
+		let mut expected = [0u8; 32];
+		expected[31] = 5;
+		assert_eq!(Amount::from(5i128).to_field_bytes(Curve::Bn254), expected);
+		assert_eq!(Amount::from(5i128).to_field_bytes(Curve::Bls381), expected);
+		assert_eq!(Amount::from(0i128).to_field_bytes(Curve::Bn254), [0u8; 32]);
+	}
+
+	#[test]
+	fn should_bound_public_amounts_by_half_the_field() {
+		let half_field = (U256::from_dec_str(FIELD_SIZE).unwrap() - U256::from(1)) / U256::from(2);
+		assert_eq!(MAX_PUBLIC_AMOUNT, Amount::from(half_field));
+		assert_eq!(MIN_PUBLIC_AMOUNT, -Amount::from(half_field));
+		assert!(MAX_PUBLIC_AMOUNT.is_public_amount());
+		assert!(MIN_PUBLIC_AMOUNT.is_public_amount());
+		assert!(!(MAX_PUBLIC_AMOUNT + Amount::from(1u128)).is_public_amount());
+		assert!(!(MIN_PUBLIC_AMOUNT - Amount::from(1u128)).is_public_amount());
+		// Beyond `i128`, the extremes still encode as distinct field elements
+		assert!(Amount::from(u128::MAX) + Amount::from(u128::MAX) < MAX_PUBLIC_AMOUNT);
+		assert_ne!(
+			MAX_PUBLIC_AMOUNT.to_field_bytes(Curve::Bn254),
+			MIN_PUBLIC_AMOUNT.to_field_bytes(Curve::Bn254)
+		);
+
+		assert_eq!("-12".parse::<Amount>().unwrap(), Amount::from(-12i128));
+		assert_eq!(
+			MAX_PUBLIC_AMOUNT.to_string().parse::<Amount>().unwrap(),
+			MAX_PUBLIC_AMOUNT
+		);
+		for invalid in ["", "-", "1.5", "+1", "1e3", " 1"] {
+			assert_eq!(invalid.parse::<Amount>().unwrap_err().code, OpStatusCode::InvalidAmount);
+		}
+	}
+
+	#[test]
+	fn should_encode_bls381_withdrawals_in_the_bls381_field() {
+		let mut expected = [0u8; 32];
+		(U256::from_dec_str(BLS381_FIELD_SIZE).unwrap() - U256::from(5)).to_big_endian(&mut expected);
+		assert_eq!(Amount::from(-5i128).to_field_bytes(Curve::Bls381), expected);
+		assert_eq!(Amount::from(-5i128).to_field::<Bls381Fr>(), Bls381Fr::from(-5i128));
+		assert_ne!(
+			Amount::from(-5i128).to_field_bytes(Curve::Bls381),
+			Amount::from(-5i128).to_field_bytes(Curve::Bn254)
+		);
+	}
+
+	#[test]
+	fn should_sum_beyond_i128() {
+		let sum = Amount::from(u128::MAX) + Amount::from(u128::MAX);
+		assert_eq!(sum.to_string(), "680564733841876926926749214863536422910");
+		assert_eq!(sum.to_u128(), None);
+		assert_eq!(sum.to_i128(), None);
+
+		let change = sum - Amount::from(u128::MAX) - Amount::from(1u128);
+		assert_eq!(change.to_u128(), Some(u128::MAX - 1));
+		assert!(Amount::from(u128::MAX) > Amount::from(i128::MAX));
+
+		let withdrawal = Amount::from(1u128) - Amount::from(u128::MAX);
+		assert!(withdrawal.is_negative());
+		assert!(withdrawal < Amount::from(i128::MIN));
+		assert_eq!(withdrawal.to_i128(), None);
+		assert_eq!(
+			(Amount::from(i128::MIN) + Amount::from(0u128)).to_i128(),
+			Some(i128::MIN)
+		);
+		assert_eq!(Amount::from(3u128) - Amount::from(3u128), Amount::default());
+	}
+
+	#[test]
+	fn should_convert_decimal_amounts_to_base_units() {
+		assert_eq!(parse_units("1.5", 18).unwrap(), 1_500_000_000_000_000_000);
+		assert_eq!(parse_units("10", 2).unwrap(), 1000);
+		assert_eq!(parse_units(".25", 2).unwrap(), 25);
+		assert_eq!(parse_units("3.", 0).unwrap(), 3);
+		for invalid in ["", ".", "1.234", "-1", "1e3", "1.5.0", " 1"] {
+			assert_eq!(parse_units(invalid, 2).unwrap_err().code, OpStatusCode::InvalidAmount);
+		}
+		assert_eq!(
+			parse_units("340282366920938463463.374607431768211456", 18)
+				.unwrap_err()
+				.code,
+			OpStatusCode::InvalidAmount
+		);
+
+		assert_eq!(format_units(1_500_000_000_000_000_000, 18), "1.5");
+		assert_eq!(format_units(25, 2), "0.25");
+		assert_eq!(format_units(1000, 2), "10");
+		assert_eq!(format_units(7, 0), "7");
+		assert_eq!(format_units(u128::MAX, 18), "340282366920938463463.374607431768211455");
+	}
+}
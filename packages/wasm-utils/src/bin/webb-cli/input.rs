@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use wasm_utils::note::Note;
+use wasm_utils::proof::{ProofInput, ProofInputBuilder, ProvingKey};
+use wasm_utils::types::{Backend, Curve, NoteProtocol, OpStatusCode, OperationError};
+use wasm_utils::{Utxo, TREE_HEIGHT};
+
+/// Content of the file passed to `proof generate --input`
+///
+/// Byte values are hex encoded and amounts are decimal strings, the proving
+/// key is a path to the raw key bytes relative to the input file.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "protocol", rename_all = "lowercase")]
+pub enum ProofInputFile {
+	Mixer(MixerInputFile),
+	Anchor(AnchorInputFile),
+	VAnchor(VAnchorInputFile),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MixerInputFile {
+	pub note: String,
+	pub leaves: Vec<String>,
+	pub leaf_index: u64,
+	pub relayer: String,
+	pub recipient: String,
+	#[serde(default = "zero")]
+	pub fee: String,
+	#[serde(default = "zero")]
+	pub refund: String,
+	pub pk: PathBuf,
+}
+
+/// The note gives the chain id, `roots` has one root per linked anchor
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorInputFile {
+	pub note: String,
+	pub leaves: Vec<String>,
+	pub leaf_index: u64,
+	pub relayer: String,
+	pub recipient: String,
+	#[serde(default = "zero")]
+	pub fee: String,
+	#[serde(default = "zero")]
+	pub refund: String,
+	pub roots: Vec<String>,
+	/// Defaults to 32 zero bytes, no refreshed commitment
+	#[serde(default = "zero_commitment")]
+	pub refresh_commitment: String,
+	pub pk: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VAnchorInputFile {
+	#[serde(default = "bn254")]
+	pub curve: String,
+	#[serde(default = "tree_height")]
+	pub height: usize,
+	#[serde(default = "width")]
+	pub width: usize,
+	#[serde(default = "exponentiation")]
+	pub exponentiation: i8,
+	pub chain_id: u64,
+	pub public_amount: String,
+	pub ext_data_hash: String,
+	pub roots: Vec<String>,
+	/// Leaves of every tree keyed by chain id
+	pub leaves: BTreeMap<u64, Vec<String>>,
+	pub indices: Vec<u64>,
+	/// Serialized input UTXOs
+	pub inputs: Vec<String>,
+	/// Serialized output UTXOs
+	pub outputs: [String; 2],
+	pub pk: PathBuf,
+}
+
+fn zero() -> String {
+	"0".to_string()
+}
+
+fn zero_commitment() -> String {
+	hex::encode([0u8; 32])
+}
+
+fn bn254() -> String {
+	Curve::Bn254.to_string()
+}
+
+fn tree_height() -> usize {
+	TREE_HEIGHT
+}
+
+fn width() -> usize {
+	5
+}
+
+fn exponentiation() -> i8 {
+	5
+}
+
+pub fn decode_hex(value: &str, code: OpStatusCode) -> Result<Vec<u8>, OperationError> {
+	hex::decode(value.trim_start_matches("0x"))
+		.map_err(|e| OperationError::new_with_message(code, format!("{}: {}", value, e)))
+}
+
+pub fn decode_hex_list(values: &[String], code: OpStatusCode) -> Result<Vec<Vec<u8>>, OperationError> {
+	values.iter().map(|value| decode_hex(value, code.clone())).collect()
+}
+
+pub fn read_file(path: &Path) -> Result<Vec<u8>, OperationError> {
+	fs::read(path).map_err(|e| {
+		OperationError::new_with_message(
+			OpStatusCode::Unknown,
+			format!("Failed to read {}: {}", path.display(), e),
+		)
+	})
+}
+
+pub fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, OperationError> {
+	let content = read_file(path)?;
+	serde_json::from_slice(&content).map_err(|e| {
+		OperationError::new_with_message(
+			OpStatusCode::DeserializationFailed,
+			format!("Failed to parse {}: {}", path.display(), e),
+		)
+	})
+}
+
+impl ProofInputFile {
+	/// Turn the file content into a proof input, `base` is the directory the
+	/// proving key path is resolved against
+	pub fn into_proof_input(self, base: &Path) -> Result<ProofInput, OperationError> {
+		match self {
+			ProofInputFile::Mixer(input) => {
+				let note: Note = input.note.parse()?;
+				let leaf = note.get_leaf_and_nullifier()?.mixer_leaf()?;
+
+				let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+				builder.exponentiation(note.exponentiation.unwrap_or(5))?;
+				builder.width(note.width.unwrap_or(3))?;
+				builder.height(note.height.unwrap_or(TREE_HEIGHT))?;
+				builder.curve(note.curve.unwrap_or(Curve::Bn254))?;
+				builder.backend(note.backend.unwrap_or(Backend::Arkworks))?;
+				builder.secrets(leaf)?;
+				builder.leaves_list(decode_hex_list(&input.leaves, OpStatusCode::InvalidLeaves)?)?;
+				builder.leaf_index(input.leaf_index)?;
+				builder.relayer(decode_hex(&input.relayer, OpStatusCode::InvalidRelayer)?)?;
+				builder.recipient(decode_hex(&input.recipient, OpStatusCode::InvalidRecipient)?)?;
+				builder.fee(input.fee.parse().map_err(|_| OpStatusCode::InvalidFee)?)?;
+				builder.refund(input.refund.parse().map_err(|_| OpStatusCode::InvalidRefund)?)?;
+				let curve = note.curve.unwrap_or(Curve::Bn254);
+				builder.pk(ProvingKey::new(curve, &read_file(&base.join(&input.pk))?)?)?;
+				builder.build()
+			}
+			ProofInputFile::Anchor(input) => {
+				let note: Note = input.note.parse()?;
+				let leaf = note.get_leaf_and_nullifier()?.anchor_leaf()?;
+				let curve = note.curve.unwrap_or(Curve::Bn254);
+
+				let mut builder = ProofInputBuilder::new(NoteProtocol::Anchor);
+				builder.exponentiation(note.exponentiation.unwrap_or(5))?;
+				builder.width(note.width.unwrap_or(4))?;
+				builder.height(note.height.unwrap_or(TREE_HEIGHT))?;
+				builder.curve(curve)?;
+				builder.backend(note.backend.unwrap_or(Backend::Arkworks))?;
+				builder.chain_id(
+					note.target_chain_id
+						.parse()
+						.map_err(|_| OpStatusCode::InvalidTargetChain)?,
+				)?;
+				builder.secrets(leaf)?;
+				builder.leaves_list(decode_hex_list(&input.leaves, OpStatusCode::InvalidLeaves)?)?;
+				builder.leaf_index(input.leaf_index)?;
+				builder.relayer(decode_hex(&input.relayer, OpStatusCode::InvalidRelayer)?)?;
+				builder.recipient(decode_hex(&input.recipient, OpStatusCode::InvalidRecipient)?)?;
+				builder.fee(input.fee.parse().map_err(|_| OpStatusCode::InvalidFee)?)?;
+				builder.refund(input.refund.parse().map_err(|_| OpStatusCode::InvalidRefund)?)?;
+				builder.roots(decode_hex_list(&input.roots, OpStatusCode::InvalidRoots)?)?;
+				builder.refresh_commitment(decode_hex(&input.refresh_commitment, OpStatusCode::CommitmentNotSet)?)?;
+				builder.pk(ProvingKey::new(curve, &read_file(&base.join(&input.pk))?)?)?;
+				builder.build()
+			}
+			ProofInputFile::VAnchor(input) => {
+				let curve: Curve = input.curve.parse().map_err(|_| {
+					OperationError::new_with_message(
+						OpStatusCode::InvalidCurve,
+						format!("Unexpected value {}", input.curve),
+					)
+				})?;
+				let inputs = input
+					.inputs
+					.iter()
+					.map(|utxo| Utxo::deserialize(utxo))
+					.collect::<Result<Vec<_>, _>>()?;
+				let outputs = [
+					Utxo::deserialize(&input.outputs[0])?,
+					Utxo::deserialize(&input.outputs[1])?,
+				];
+				let leaves = input
+					.leaves
+					.iter()
+					.map(|(chain_id, leaves)| Ok((*chain_id, decode_hex_list(leaves, OpStatusCode::InvalidLeaves)?)))
+					.collect::<Result<BTreeMap<_, _>, OperationError>>()?;
+
+				let mut builder = ProofInputBuilder::new(NoteProtocol::VAnchor);
+				builder.exponentiation(input.exponentiation)?;
+				builder.width(input.width)?;
+				builder.height(input.height)?;
+				builder.curve(curve)?;
+				builder.backend(Backend::Arkworks)?;
+				builder.chain_id(input.chain_id.into())?;
+				builder.public_amount(
+					input
+						.public_amount
+						.parse()
+						.map_err(|_| OpStatusCode::InvalidPublicAmount)?,
+				)?;
+				builder.ext_data_hash(decode_hex(&input.ext_data_hash, OpStatusCode::InvalidExtDataHash)?)?;
+				builder.roots(decode_hex_list(&input.roots, OpStatusCode::InvalidRoots)?)?;
+				builder.leaves_map(leaves)?;
+				builder.leaf_indices(input.indices)?;
+				builder.set_input_utxos(inputs)?;
+				builder.set_output_utxos(outputs)?;
+				builder.pk(ProvingKey::new(curve, &read_file(&base.join(&input.pk))?)?)?;
+				builder.build()
+			}
+		}
+	}
+}
@@ -0,0 +1,645 @@
+//! Command line tool over the native wasm-utils API.
+//!
+//! Every command runs offline and prints a single JSON document to stdout.
+//! Failures are printed as `{ "error": { "code", "message", "data" } }` to
+//! stderr with a non-zero exit code.
+mod input;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Parser, Subcommand};
+use rand::rngs::OsRng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use wasm_utils::note::{Note, NoteBuilder};
+use wasm_utils::proof::{
+	create_proof, render_solidity_verifier, setup_keys_with_height, verify, MTBn254X5, ProofOutput,
+};
+use wasm_utils::types::{Curve, NoteProtocol, NoteVersion, OpStatusCode, OperationError};
+use wasm_utils::Utxo;
+
+use crate::input::{decode_hex, decode_hex_list, read_file, read_json, ProofInputFile};
+
+#[derive(Parser)]
+#[clap(
+	name = "webb-cli",
+	version,
+	about = "Inspect notes, generate keys and proofs offline"
+)]
+struct Cli {
+	#[clap(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Create, parse and validate notes
+	#[clap(subcommand)]
+	Note(NoteCommand),
+	/// Create UTXOs and compute their nullifier/commitment
+	#[clap(subcommand)]
+	Utxo(UtxoCommand),
+	/// Generate proving and verifying keys
+	#[clap(subcommand)]
+	Keys(KeysCommand),
+	/// Merkle tree helpers
+	#[clap(subcommand)]
+	Tree(TreeCommand),
+	/// Generate and verify proofs
+	#[clap(subcommand)]
+	Proof(ProofCommand),
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum NoteCommand {
+	/// Generate a note with fresh secrets
+	New {
+		#[clap(long, default_value = "mixer")]
+		protocol: String,
+		#[clap(long)]
+		source_chain_id: u64,
+		#[clap(long)]
+		target_chain_id: u64,
+		/// Defaults to the source chain id
+		#[clap(long)]
+		source_identifying_data: Option<String>,
+		/// Defaults to the target chain id
+		#[clap(long)]
+		target_identifying_data: Option<String>,
+		#[clap(long, default_value = "0")]
+		amount: String,
+		#[clap(long, default_value = "18")]
+		denomination: u8,
+		#[clap(long)]
+		token_symbol: Option<String>,
+		#[clap(long, default_value = "Bn254")]
+		curve: String,
+		#[clap(long, default_value = "Arkworks")]
+		backend: String,
+		#[clap(long, default_value = "Poseidon")]
+		hash_function: String,
+		/// Defaults to 3 for the mixer and 5 for the vanchor
+		#[clap(long)]
+		width: Option<usize>,
+		#[clap(long, default_value = "5")]
+		exponentiation: i8,
+		#[clap(long)]
+		index: Option<u64>,
+	},
+	/// Print the fields of a serialized note
+	Parse { note: String },
+	/// Check that the note metadata matches its secrets, and the leaf
+	/// commitment when given
+	Validate {
+		note: String,
+		/// Hex encoded leaf commitment the note should have
+		#[clap(long)]
+		commitment: Option<String>,
+	},
+}
+
+#[derive(Subcommand)]
+enum UtxoCommand {
+	/// Generate a UTXO, missing keys and blinding are generated randomly
+	New {
+		#[clap(long, default_value = "Bn254")]
+		curve: String,
+		#[clap(long, default_value = "Arkworks")]
+		backend: String,
+		#[clap(long)]
+		amount: u128,
+		#[clap(long)]
+		chain_id: u64,
+		#[clap(long)]
+		index: Option<u64>,
+		#[clap(long)]
+		blinding: Option<String>,
+		#[clap(long)]
+		public_key: Option<String>,
+		#[clap(long)]
+		private_key: Option<String>,
+	},
+	/// Compute the nullifier of a serialized UTXO
+	Nullifier { utxo: String },
+	/// Compute the commitment of a serialized UTXO
+	Commitment { utxo: String },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+	/// Generate a random key pair for a circuit configuration
+	Setup {
+		#[clap(long, default_value = "mixer")]
+		protocol: String,
+		#[clap(long, default_value = "Bn254")]
+		curve: String,
+		#[clap(long, default_value = "2")]
+		anchors: u32,
+		#[clap(long, default_value = "2")]
+		ins: u32,
+		#[clap(long, default_value = "2")]
+		outs: u32,
+		/// Height of the Merkle tree, one of 16, 20 or 30
+		#[clap(long, default_value = "30")]
+		height: usize,
+		/// Write the raw proving key to this file instead of printing it as hex
+		#[clap(long)]
+		pk_out: Option<PathBuf>,
+		/// Write the raw verifying key to this file instead of printing it as hex
+		#[clap(long)]
+		vk_out: Option<PathBuf>,
+	},
+	/// Render the Solidity verifier contract of a raw Bn254 verifying key
+	Verifier {
+		#[clap(long)]
+		vk: PathBuf,
+		/// Name of the contract, e.g. `Verifier2_16` for a VAnchor selector
+		#[clap(long, default_value = "Verifier")]
+		name: String,
+		/// Write the contract to this file instead of printing it in the JSON
+		#[clap(long)]
+		out: Option<PathBuf>,
+	},
+}
+
+#[derive(Subcommand)]
+enum TreeCommand {
+	/// Compute the root of a tree, the file holds a JSON array of hex leaves
+	Root {
+		#[clap(long)]
+		leaves: PathBuf,
+		#[clap(long, default_value = "30")]
+		height: usize,
+	},
+}
+
+#[derive(Subcommand)]
+enum ProofCommand {
+	/// Generate a proof from a JSON input file
+	Generate {
+		#[clap(long)]
+		input: PathBuf,
+	},
+	/// Verify the output of `proof generate`
+	Verify {
+		/// File with the JSON printed by `proof generate`
+		#[clap(long)]
+		proof: PathBuf,
+		/// File with the raw verifying key
+		#[clap(long)]
+		vk: PathBuf,
+		#[clap(long, default_value = "Bn254")]
+		curve: String,
+	},
+}
+
+/// The fields of a generated proof needed for the verification
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofFile {
+	proof: String,
+	public_inputs: Vec<String>,
+}
+
+fn parse<T: std::str::FromStr>(value: &str, code: OpStatusCode) -> Result<T, OperationError> {
+	value
+		.parse()
+		.map_err(|_| OperationError::new_with_message(code, format!("Unexpected value {}", value)))
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<(), OperationError> {
+	fs::write(path, content).map_err(|e| {
+		OperationError::new_with_message(
+			OpStatusCode::Unknown,
+			format!("Failed to write {}: {}", path.display(), e),
+		)
+	})
+}
+
+fn note_json(note: &Note) -> Result<Value, OperationError> {
+	Ok(json!({
+		"note": note.to_string(),
+		"protocol": note.protocol.to_string(),
+		"version": note.version.to_string(),
+		"sourceChainId": note.source_chain_id,
+		"targetChainId": note.target_chain_id,
+		"sourceIdentifyingData": note.source_identifying_data,
+		"targetIdentifyingData": note.target_identifying_data,
+		"curve": note.curve.map(|v| v.to_string()),
+		"width": note.width,
+		"exponentiation": note.exponentiation,
+		"hashFunction": note.hash_function.map(|v| v.to_string()),
+		"backend": note.backend.map(|v| v.to_string()),
+		"tokenSymbol": note.token_symbol,
+		"amount": note.amount,
+		"denomination": note.denomination,
+		"index": note.index,
+		"secrets": note.secrets.iter().map(hex::encode).collect::<Vec<_>>(),
+		"commitment": hex::encode(note.get_leaf_commitment()?),
+	}))
+}
+
+fn utxo_json(utxo: &Utxo) -> Value {
+	json!({
+		"utxo": utxo.to_string(),
+		"chainId": utxo.get_chain_id_raw(),
+		"amount": utxo.get_amount_value().to_string(),
+		"index": utxo.get_index(),
+		"commitment": hex::encode(utxo.get_commitment()),
+		"nullifier": utxo.get_nullifier().ok().map(hex::encode),
+	})
+}
+
+fn run_note(command: NoteCommand) -> Result<Value, OperationError> {
+	match command {
+		NoteCommand::New {
+			protocol,
+			source_chain_id,
+			target_chain_id,
+			source_identifying_data,
+			target_identifying_data,
+			amount,
+			denomination,
+			token_symbol,
+			curve,
+			backend,
+			hash_function,
+			width,
+			exponentiation,
+			index,
+		} => {
+			let protocol: NoteProtocol = parse(&protocol, OpStatusCode::InvalidNoteProtocol)?;
+			let width = width.unwrap_or(match protocol {
+				NoteProtocol::Mixer => 3,
+				NoteProtocol::Anchor => 4,
+				NoteProtocol::VAnchor => 5,
+			});
+			let builder = NoteBuilder {
+				protocol: Some(protocol),
+				version: Some(NoteVersion::V1),
+				source_chain_id: Some(source_chain_id.to_string()),
+				target_chain_id: Some(target_chain_id.to_string()),
+				source_identifying_data: Some(source_identifying_data.unwrap_or_else(|| source_chain_id.to_string())),
+				target_identifying_data: Some(target_identifying_data.unwrap_or_else(|| target_chain_id.to_string())),
+				amount: Some(amount),
+				denomination: Some(denomination),
+				token_symbol,
+				curve: Some(parse(&curve, OpStatusCode::InvalidCurve)?),
+				backend: Some(parse(&backend, OpStatusCode::InvalidBackend)?),
+				hash_function: Some(parse(&hash_function, OpStatusCode::InvalidHasFunction)?),
+				width: Some(width),
+				exponentiation: Some(exponentiation),
+				index,
+				..Default::default()
+			};
+			note_json(&builder.build()?)
+		}
+		NoteCommand::Parse { note } => note_json(&note.parse()?),
+		NoteCommand::Validate { note, commitment } => {
+			let note = Note::deserialize(&note)?;
+			let commitment = commitment
+				.map(|v| decode_hex(&v, OpStatusCode::InvalidLeaves))
+				.transpose()?;
+			note.validate(commitment.as_deref())?;
+			Ok(json!({
+				"valid": true,
+				"commitment": hex::encode(note.get_leaf_commitment()?),
+			}))
+		}
+	}
+}
+
+fn run_utxo(command: UtxoCommand) -> Result<Value, OperationError> {
+	match command {
+		UtxoCommand::New {
+			curve,
+			backend,
+			amount,
+			chain_id,
+			index,
+			blinding,
+			public_key,
+			private_key,
+		} => {
+			let blinding = blinding
+				.map(|v| decode_hex(&v, OpStatusCode::InvalidNoteSecrets))
+				.transpose()?;
+			let public_key = public_key
+				.map(|v| decode_hex(&v, OpStatusCode::InvalidNoteSecrets))
+				.transpose()?;
+			let private_key = private_key
+				.map(|v| decode_hex(&v, OpStatusCode::InvalidNoteSecrets))
+				.transpose()?;
+			let utxo = Utxo::new(
+				parse(&curve, OpStatusCode::InvalidCurve)?,
+				parse(&backend, OpStatusCode::InvalidBackend)?,
+				amount,
+				chain_id,
+				blinding,
+				public_key,
+				private_key,
+				index,
+			)?;
+			Ok(utxo_json(&utxo))
+		}
+		UtxoCommand::Nullifier { utxo } => {
+			let utxo = Utxo::deserialize(&utxo)?;
+			let nullifier = utxo.get_nullifier()?;
+			Ok(json!({ "nullifier": hex::encode(nullifier) }))
+		}
+		UtxoCommand::Commitment { utxo } => {
+			let utxo = Utxo::deserialize(&utxo)?;
+			Ok(json!({ "commitment": hex::encode(utxo.get_commitment()) }))
+		}
+	}
+}
+
+fn run_keys(command: KeysCommand) -> Result<Value, OperationError> {
+	match command {
+		KeysCommand::Setup {
+			protocol,
+			curve,
+			anchors,
+			ins,
+			outs,
+			height,
+			pk_out,
+			vk_out,
+		} => {
+			let protocol: NoteProtocol = parse(&protocol, OpStatusCode::InvalidNoteProtocol)?;
+			let curve: Curve = parse(&curve, OpStatusCode::InvalidCurve)?;
+			let (pk, vk) = setup_keys_with_height(protocol, curve, height, anchors, ins, outs)?;
+			let pk = match pk_out {
+				Some(path) => {
+					write_file(&path, &pk)?;
+					json!({ "path": path, "size": pk.len() })
+				}
+				None => json!(hex::encode(&pk)),
+			};
+			let vk = match vk_out {
+				Some(path) => {
+					write_file(&path, &vk)?;
+					json!({ "path": path, "size": vk.len() })
+				}
+				None => json!(hex::encode(&vk)),
+			};
+			Ok(json!({
+				"protocol": protocol.to_string(),
+				"curve": curve.to_string(),
+				"anchors": anchors,
+				"ins": ins,
+				"outs": outs,
+				"height": height,
+				"pk": pk,
+				"vk": vk,
+			}))
+		}
+		KeysCommand::Verifier { vk, name, out } => {
+			let source = render_solidity_verifier(&read_file(&vk)?, &name)?;
+			let contract = match out {
+				Some(path) => {
+					write_file(&path, source.as_bytes())?;
+					json!({ "path": path })
+				}
+				None => json!(source),
+			};
+			Ok(json!({ "name": name, "contract": contract }))
+		}
+	}
+}
+
+fn run_tree(command: TreeCommand) -> Result<Value, OperationError> {
+	match command {
+		TreeCommand::Root { leaves, height } => {
+			let leaves: Vec<String> = read_json(&leaves)?;
+			let leaves = decode_hex_list(&leaves, OpStatusCode::InvalidLeaves)?;
+			let tree = MTBn254X5::from_leaves_with_height(&leaves, 0, height)?;
+			Ok(json!({
+				"root": hex::encode(tree.get_root()),
+				"height": tree.height(),
+				"leafCount": leaves.len(),
+			}))
+		}
+	}
+}
+
+fn run_proof(command: ProofCommand) -> Result<Value, OperationError> {
+	match command {
+		ProofCommand::Generate { input } => {
+			let base = input.parent().unwrap_or_else(|| Path::new("."));
+			let input_file: ProofInputFile = read_json(&input)?;
+			let proof_input = input_file.into_proof_input(base)?;
+			let output = match create_proof(proof_input, &mut OsRng)? {
+				ProofOutput::Mixer(proof) => json!({
+					"protocol": NoteProtocol::Mixer.to_string(),
+					"proof": hex::encode(proof.get_proof()),
+					"publicInputs": proof.get_public_inputs().iter().map(hex::encode).collect::<Vec<_>>(),
+					"root": hex::encode(proof.get_root()),
+					"nullifierHash": hex::encode(proof.get_nullifier_hash()),
+					"leaf": hex::encode(proof.get_leaf()),
+				}),
+				ProofOutput::Anchor(proof) => json!({
+					"protocol": NoteProtocol::Anchor.to_string(),
+					"proof": hex::encode(proof.get_proof()),
+					"publicInputs": proof.get_public_inputs().iter().map(hex::encode).collect::<Vec<_>>(),
+					"roots": proof.get_roots().iter().map(hex::encode).collect::<Vec<_>>(),
+					"nullifierHash": hex::encode(proof.get_nullifier_hash()),
+					"leaf": hex::encode(proof.get_leaf()),
+				}),
+				ProofOutput::VAnchor(proof) => json!({
+					"protocol": NoteProtocol::VAnchor.to_string(),
+					"proof": hex::encode(proof.get_proof()),
+					"publicInputs": proof.get_public_inputs().iter().map(hex::encode).collect::<Vec<_>>(),
+					"publicAmount": hex::encode(proof.get_public_amount()),
+					"inputUtxos": proof.get_input_utxos().iter().map(Utxo::to_string).collect::<Vec<_>>(),
+					"outputUtxos": proof.get_output_utxos().iter().map(Utxo::to_string).collect::<Vec<_>>(),
+				}),
+			};
+			Ok(output)
+		}
+		ProofCommand::Verify { proof, vk, curve } => {
+			let proof_file: ProofFile = read_json(&proof)?;
+			let proof = decode_hex(&proof_file.proof, OpStatusCode::InvalidProofParameters)?;
+			let public_inputs = decode_hex_list(&proof_file.public_inputs, OpStatusCode::InvalidProofParameters)?;
+			let vk = read_file(&vk)?;
+			let curve: Curve = parse(&curve, OpStatusCode::InvalidCurve)?;
+			let valid = verify(curve, &public_inputs, &vk, &proof)?;
+			Ok(json!({ "valid": valid }))
+		}
+	}
+}
+
+fn run(command: Command) -> Result<Value, OperationError> {
+	match command {
+		Command::Note(command) => run_note(command),
+		Command::Utxo(command) => run_utxo(command),
+		Command::Keys(command) => run_keys(command),
+		Command::Tree(command) => run_tree(command),
+		Command::Proof(command) => run_proof(command),
+	}
+}
+
+fn main() {
+	let cli = Cli::parse();
+	match run(cli.command) {
+		Ok(output) => println!(
+			"{}",
+			serde_json::to_string_pretty(&output).expect("JSON values always serialize")
+		),
+		Err(e) => {
+			// Data holding JSON, like the mismatches of `note validate`, is
+			// printed as JSON
+			let data = e
+				.data
+				.map(|data| serde_json::from_str(&data).unwrap_or(Value::String(data)));
+			let error = json!({
+				"error": {
+					"code": e.code.clone() as u32,
+					"message": e.error_message,
+					"data": data,
+				}
+			});
+			eprintln!("{}", error);
+			process::exit(1);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	const MIXER_NOTE: &str = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+
+	#[test]
+	fn should_parse_note_to_json() {
+		let output = run(Command::Note(NoteCommand::Parse {
+			note: MIXER_NOTE.to_string(),
+		}))
+		.unwrap();
+		assert_eq!(output["note"], MIXER_NOTE);
+		assert_eq!(output["protocol"], "mixer");
+		assert_eq!(output["secrets"].as_array().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn should_generate_a_note_that_validates() {
+		let output = run(Command::Note(NoteCommand::New {
+			protocol: "vanchor".to_string(),
+			source_chain_id: 1,
+			target_chain_id: 2,
+			source_identifying_data: None,
+			target_identifying_data: None,
+			amount: "10".to_string(),
+			denomination: 18,
+			token_symbol: Some("WEBB".to_string()),
+			curve: "Bn254".to_string(),
+			backend: "Arkworks".to_string(),
+			hash_function: "Poseidon".to_string(),
+			width: None,
+			exponentiation: 5,
+			index: Some(0),
+		}))
+		.unwrap();
+		let note = output["note"].as_str().unwrap().to_string();
+		let validated = run(Command::Note(NoteCommand::Validate {
+			note: note.clone(),
+			commitment: None,
+		}))
+		.unwrap();
+		assert_eq!(validated["commitment"], output["commitment"]);
+
+		// The amount of the metadata doesn't match the UTXO anymore
+		let error = run(Command::Note(NoteCommand::Validate {
+			note: note.replace("amount=10", "amount=11"),
+			commitment: None,
+		}))
+		.unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidNoteIntegrity);
+		let mismatches: Value = serde_json::from_str(&error.data.unwrap()).unwrap();
+		assert_eq!(mismatches[0]["field"], "amount");
+	}
+
+	#[test]
+	fn should_validate_a_note_against_its_commitment() {
+		let error = run(Command::Note(NoteCommand::Validate {
+			note: MIXER_NOTE.to_string(),
+			commitment: Some(hex::encode([0u8; 32])),
+		}))
+		.unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidNoteIntegrity);
+	}
+
+	#[test]
+	fn should_read_proof_input_file() {
+		let input: ProofInputFile = serde_json::from_str(
+			r#"{ "protocol": "mixer", "note": "n", "leaves": ["00"], "leafIndex": 0, "relayer": "01", "recipient": "02", "pk": "pk.bin" }"#,
+		)
+		.unwrap();
+		match input {
+			ProofInputFile::Mixer(input) => {
+				assert_eq!(input.fee, "0");
+				assert_eq!(input.pk, PathBuf::from("pk.bin"));
+			}
+			_ => panic!("expected a mixer input"),
+		}
+	}
+
+	#[test]
+	fn should_read_anchor_and_vanchor_input_files() {
+		let input: ProofInputFile = serde_json::from_str(
+			r#"{ "protocol": "anchor", "note": "n", "leaves": ["00"], "leafIndex": 0, "relayer": "01", "recipient": "02", "roots": ["03", "04"], "pk": "pk.bin" }"#,
+		)
+		.unwrap();
+		match input {
+			ProofInputFile::Anchor(input) => {
+				assert_eq!(input.roots.len(), 2);
+				assert_eq!(input.refresh_commitment, hex::encode([0u8; 32]));
+			}
+			_ => panic!("expected an anchor input"),
+		}
+
+		let input: ProofInputFile = serde_json::from_str(
+			r#"{ "protocol": "vanchor", "curve": "Bls381", "height": 16, "chainId": 1, "publicAmount": "0", "extDataHash": "00", "roots": [], "leaves": {}, "indices": [], "inputs": [], "outputs": ["", ""], "pk": "pk.bin" }"#,
+		)
+		.unwrap();
+		match input {
+			ProofInputFile::VAnchor(input) => {
+				assert_eq!(input.curve, "Bls381");
+				assert_eq!(input.height, 16);
+				assert_eq!((input.width, input.exponentiation), (5, 5));
+			}
+			_ => panic!("expected a vanchor input"),
+		}
+	}
+
+	#[test]
+	fn should_render_the_verifier_of_generated_keys() {
+		let vk = std::env::temp_dir().join(format!("webb-cli-vk-{}.bin", process::id()));
+		run(Command::Keys(KeysCommand::Setup {
+			protocol: "mixer".to_string(),
+			curve: "Bn254".to_string(),
+			anchors: 2,
+			ins: 2,
+			outs: 2,
+			height: 16,
+			pk_out: None,
+			vk_out: Some(vk.clone()),
+		}))
+		.unwrap();
+		let output = run(Command::Keys(KeysCommand::Verifier {
+			vk: vk.clone(),
+			name: "MixerVerifier".to_string(),
+			out: None,
+		}))
+		.unwrap();
+		fs::remove_file(vk).unwrap();
+		assert!(output["contract"]
+			.as_str()
+			.unwrap()
+			.contains("contract MixerVerifier {"));
+	}
+}
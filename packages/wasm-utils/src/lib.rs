@@ -3,20 +3,37 @@
 
 extern crate ark_ff;
 extern crate core;
+#[cfg(feature = "js")]
 extern crate wasm_bindgen;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+pub mod amount;
 pub mod note;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod proof;
+pub mod secret;
+pub mod serialization;
+pub mod token;
 pub mod types;
+#[cfg(feature = "js")]
 mod utils;
-mod utxo;
+pub mod utxo;
+
+// Native API, usable without the `js` feature
+pub use note::{Note, NoteBuilder};
+pub use proof::{create_proof, create_proofs, verify, ProofInput, ProofOutput};
+pub use serialization::Serializable;
+use types::OpStatusCode;
+pub use types::OperationError;
+pub use utxo::Utxo;
 
 use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
+use arkworks_setups::r1cs::anchor::AnchorR1CSProver;
 use arkworks_setups::r1cs::mixer::MixerR1CSProver;
 use arkworks_setups::r1cs::vanchor::VAnchorR1CSProver;
 
@@ -26,6 +43,9 @@ pub const DEFAULT_LEAF: [u8; 32] = [
 	150, 133, 237, 72, 153, 175, 108,
 ];
 pub const TREE_HEIGHT: usize = 30;
+/// Merkle tree heights the provers and trees are compiled for, `TREE_HEIGHT`
+/// is the default
+pub const SUPPORTED_TREE_HEIGHTS: [usize; 3] = [16, 20, TREE_HEIGHT];
 pub const ANCHOR_COUNT: usize = 2;
 
 pub const ANCHOR_COUNT_2: usize = 2;
@@ -39,7 +59,56 @@ const OUTS_2: usize = 2;
 pub type MixerR1CSProverBn254_30 = MixerR1CSProver<Bn254, TREE_HEIGHT>;
 pub type MixerR1CSProverBls381_30 = MixerR1CSProver<Bls12_381, TREE_HEIGHT>;
 
+pub type AnchorR1CSProverBn254_30_2 = AnchorR1CSProver<Bn254, TREE_HEIGHT, ANCHOR_COUNT>;
+
 pub type VAnchorR1CSProverBn254_30_2_2_2 = VAnchorR1CSProver<Bn254, TREE_HEIGHT, ANCHOR_COUNT_2, INS_2, OUTS_2>;
 pub type VAnchorR1CSProverBn254_30_2_16_2 = VAnchorR1CSProver<Bn254, TREE_HEIGHT, ANCHOR_COUNT_2, INS_16, OUTS_2>;
 pub type VAnchorR1CSProverBn254_30_16_2_2 = VAnchorR1CSProver<Bn254, TREE_HEIGHT, ANCHOR_COUNT_16, INS_2, OUTS_2>;
 pub type VAnchorR1CSProverBn254_30_16_16_2 = VAnchorR1CSProver<Bn254, TREE_HEIGHT, ANCHOR_COUNT_16, INS_16, OUTS_2>;
+
+pub type VAnchorR1CSProverBls381_30_2_2_2 = VAnchorR1CSProver<Bls12_381, TREE_HEIGHT, ANCHOR_COUNT_2, INS_2, OUTS_2>;
+pub type VAnchorR1CSProverBls381_30_2_16_2 = VAnchorR1CSProver<Bls12_381, TREE_HEIGHT, ANCHOR_COUNT_2, INS_16, OUTS_2>;
+pub type VAnchorR1CSProverBls381_30_16_2_2 = VAnchorR1CSProver<Bls12_381, TREE_HEIGHT, ANCHOR_COUNT_16, INS_2, OUTS_2>;
+pub type VAnchorR1CSProverBls381_30_16_16_2 =
+	VAnchorR1CSProver<Bls12_381, TREE_HEIGHT, ANCHOR_COUNT_16, INS_16, OUTS_2>;
+
+/// Fails with `InvalidTreeHeight` for a height outside of
+/// `SUPPORTED_TREE_HEIGHTS`
+pub fn check_tree_height(height: usize) -> Result<usize, OperationError> {
+	if SUPPORTED_TREE_HEIGHTS.contains(&height) {
+		return Ok(height);
+	}
+	let message = format!(
+		"Tree height {} isn't supported, use one of {:?}",
+		height, SUPPORTED_TREE_HEIGHTS
+	);
+	Err(OperationError::new_with_message(
+		OpStatusCode::InvalidTreeHeight,
+		message,
+	))
+}
+
+/// Evaluates `$body` with `$h` bound to the runtime `$height` as a const, so
+/// it can be passed as the height of the provers and trees
+///
+/// Returns `None` for heights outside of `SUPPORTED_TREE_HEIGHTS`.
+macro_rules! with_tree_height {
+	($height:expr, |$h:ident| $body:expr) => {
+		match $height {
+			16 => {
+				const $h: usize = 16;
+				Some($body)
+			}
+			20 => {
+				const $h: usize = 20;
+				Some($body)
+			}
+			30 => {
+				const $h: usize = 30;
+				Some($body)
+			}
+			_ => None,
+		}
+	};
+}
+pub(crate) use with_tree_height;
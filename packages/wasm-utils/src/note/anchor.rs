@@ -0,0 +1,65 @@
+use ark_std::rand::rngs::OsRng;
+use arkworks_setups::common::Leaf;
+
+use crate::AnchorR1CSProverBn254_30_2;
+use arkworks_setups::{AnchorProver, Curve as ArkCurve};
+
+use crate::types::{Curve, OpStatusCode, OperationError};
+
+/// Generate the `[secret, nullifier]` of an anchor note, the leaf is bound to
+/// `chain_id`, the chain the note can be withdrawn on
+pub fn generate_secrets(
+	exponentiation: i8,
+	width: usize,
+	curve: Curve,
+	chain_id: u64,
+	rng: &mut OsRng,
+) -> Result<[Vec<u8>; 2], OperationError> {
+	let sec = match (curve, exponentiation, width) {
+		(Curve::Bn254, 5, 4) => AnchorR1CSProverBn254_30_2::create_random_leaf(ArkCurve::Bn254, chain_id, rng),
+		_ => {
+			let message = format!(
+				"No Anchor secrets setup available for curve {}, exponentiation {}, and width {}",
+				curve, exponentiation, width
+			);
+			return Err(OperationError::new_with_message(OpStatusCode::SecretGenFailed, message));
+		}
+	}
+	.map_err(|e| OperationError::new_with_message(OpStatusCode::SecretGenFailed, e.to_string()))?;
+
+	let secrets = [sec.secret_bytes, sec.nullifier_bytes];
+
+	Ok(secrets)
+}
+
+pub fn get_leaf_with_private_raw(
+	curve: Curve,
+	width: usize,
+	exponentiation: i8,
+	chain_id: u64,
+	raw: &[u8],
+) -> Result<Leaf, OperationError> {
+	if raw.len() < 64 {
+		return Err(OpStatusCode::InvalidNoteSecrets.into());
+	}
+
+	let secrets = raw[..32].to_vec();
+	let nullifer = raw[32..64].to_vec();
+	let sec = match (curve, exponentiation, width) {
+		(Curve::Bn254, 5, 4) => {
+			AnchorR1CSProverBn254_30_2::create_leaf_with_privates(ArkCurve::Bn254, chain_id, secrets, nullifer)
+		}
+		_ => {
+			let message = format!(
+				"No Anchor leaf setup for curve {}, exponentiation {}, and width {}",
+				curve, exponentiation, width
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::FailedToGenerateTheLeaf,
+				message,
+			));
+		}
+	}
+	.map_err(|e| OperationError::new_with_message(OpStatusCode::FailedToGenerateTheLeaf, e.to_string()))?;
+	Ok(sec)
+}
@@ -64,4 +64,25 @@ pub fn get_leaf_with_private_raw(
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+	use zeroize::Zeroize;
+
+	use super::*;
+	use crate::note::NoteLeaf;
+
+	#[test]
+	fn should_wipe_the_secrets_of_mixer_leaves() {
+		let secrets = generate_secrets(5, 3, Curve::Bn254, &mut OsRng).unwrap();
+		let mut leaf = NoteLeaf::Mixer(get_leaf_with_private_raw(Curve::Bn254, 3, 5, &secrets.concat()).unwrap());
+		let copy = leaf.clone();
+		let commitment = leaf.commitment();
+		leaf.zeroize();
+
+		let wiped = leaf.mixer_leaf().unwrap();
+		assert!(wiped.secret_bytes.is_empty() && wiped.nullifier_bytes.is_empty());
+		assert_eq!(leaf.commitment(), commitment);
+		// Clones own their buffers
+		let kept = copy.mixer_leaf().unwrap();
+		assert_eq!([kept.secret_bytes.clone(), kept.nullifier_bytes.clone()], secrets);
+	}
+}
@@ -1,18 +1,36 @@
 use core::fmt;
+#[cfg(feature = "js")]
+use core::ops::{Deref, DerefMut};
 use core::str::FromStr;
 
 use arkworks_setups::common::Leaf;
+#[cfg(feature = "js")]
 use js_sys::{JsString, Uint8Array};
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "js")]
 use wasm_bindgen::JsValue;
-
-use crate::types::{
-	Backend, Curve, HashFunction, NoteProtocol, NoteVersion, OpStatusCode, OperationError, Protocol, Version,
-	WasmCurve, BE, HF,
-};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::amount::{format_units, parse_units};
+use crate::secret::{encode_secrets, Secret};
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+#[cfg(feature = "js")]
+use crate::serialization::to_js_object;
+use crate::serialization::{hex_bytes_list, optional_hex_bytes, Serializable};
+use crate::types::{Backend, Curve, HashFunction, NoteProtocol, NoteVersion, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
+use crate::types::{Protocol, Version, WasmCurve, BE, HF};
+#[cfg(feature = "js")]
 use crate::utxo::JsUtxo;
+use crate::utxo::Utxo;
+use crate::{check_tree_height, TREE_HEIGHT};
+use vanchor::VAnchorNoteSecrets;
 
+pub mod anchor;
 pub mod mixer;
 pub mod vanchor;
 pub mod versioning;
@@ -23,52 +41,112 @@ macro_rules! console_log {
 	// `bare_bones`
 	($($t:tt)*) => (crate::types::log(&format_args!($($t)*).to_string()))
 }
-pub enum JsLeafInner {
+pub enum NoteLeaf {
 	Mixer(Leaf),
-	VAnchor(JsUtxo),
+	Anchor(Leaf),
+	VAnchor(Utxo),
+}
+
+fn clone_leaf(leaf: &Leaf) -> Leaf {
+	Leaf {
+		chain_id_bytes: leaf.chain_id_bytes.clone(),
+		secret_bytes: leaf.secret_bytes.clone(),
+		nullifier_bytes: leaf.nullifier_bytes.clone(),
+		leaf_bytes: leaf.leaf_bytes.clone(),
+		nullifier_hash_bytes: leaf.nullifier_hash_bytes.clone(),
+	}
 }
 
-impl Clone for JsLeafInner {
+impl Clone for NoteLeaf {
 	fn clone(&self) -> Self {
 		match self {
-			JsLeafInner::Mixer(leaf) => JsLeafInner::Mixer(Leaf {
-				chain_id_bytes: leaf.chain_id_bytes.clone(),
-				secret_bytes: leaf.secret_bytes.clone(),
-				nullifier_bytes: leaf.nullifier_bytes.clone(),
-				leaf_bytes: leaf.leaf_bytes.clone(),
-				nullifier_hash_bytes: leaf.nullifier_hash_bytes.clone(),
-			}),
-			JsLeafInner::VAnchor(utxo) => JsLeafInner::VAnchor(utxo.clone()),
+			NoteLeaf::Mixer(leaf) => NoteLeaf::Mixer(clone_leaf(leaf)),
+			NoteLeaf::Anchor(leaf) => NoteLeaf::Anchor(clone_leaf(leaf)),
+			NoteLeaf::VAnchor(utxo) => NoteLeaf::VAnchor(utxo.clone()),
+		}
+	}
+}
+/// Wipes the secret and nullifier bytes of Mixer and Anchor leaves. UTXOs
+/// hold their keys as `arkworks-setups` field elements, which aren't wiped.
+impl Zeroize for NoteLeaf {
+	fn zeroize(&mut self) {
+		if let NoteLeaf::Mixer(leaf) | NoteLeaf::Anchor(leaf) = self {
+			leaf.secret_bytes.zeroize();
+			leaf.nullifier_bytes.zeroize();
+		}
+	}
+}
+
+/// The secret and nullifier buffers move into `Secret`s, which wipe them as
+/// they drop
+impl Drop for NoteLeaf {
+	fn drop(&mut self) {
+		if let NoteLeaf::Mixer(leaf) | NoteLeaf::Anchor(leaf) = self {
+			drop(Secret::new(core::mem::take(&mut leaf.secret_bytes)));
+			drop(Secret::new(core::mem::take(&mut leaf.nullifier_bytes)));
+		}
+	}
+}
+
+impl NoteLeaf {
+	pub fn mixer_leaf(&self) -> Result<Leaf, OperationError> {
+		match self {
+			NoteLeaf::Mixer(leaf) => Ok(clone_leaf(leaf)),
+			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
+		}
+	}
+
+	pub fn anchor_leaf(&self) -> Result<Leaf, OperationError> {
+		match self {
+			NoteLeaf::Anchor(leaf) => Ok(clone_leaf(leaf)),
+			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
+		}
+	}
+
+	pub fn vanchor_leaf(&self) -> Result<Utxo, OperationError> {
+		match self {
+			NoteLeaf::VAnchor(leaf) => Ok(leaf.clone()),
+			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
+		}
+	}
+
+	pub fn commitment(&self) -> Vec<u8> {
+		match self {
+			NoteLeaf::Mixer(leaf) | NoteLeaf::Anchor(leaf) => leaf.leaf_bytes.clone(),
+			NoteLeaf::VAnchor(vanchor_leaf) => vanchor_leaf.get_commitment(),
 		}
 	}
 }
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 pub struct JsLeaf {
 	#[wasm_bindgen(skip)]
-	pub inner: JsLeafInner,
+	pub inner: NoteLeaf,
 }
+#[cfg(feature = "js")]
 impl JsLeaf {
 	pub fn mixer_leaf(&self) -> Result<Leaf, OperationError> {
-		match self.inner.clone() {
-			JsLeafInner::Mixer(leaf) => Ok(leaf),
-			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
-		}
+		self.inner.mixer_leaf()
+	}
+
+	pub fn anchor_leaf(&self) -> Result<Leaf, OperationError> {
+		self.inner.anchor_leaf()
 	}
 
 	pub fn vanchor_leaf(&self) -> Result<JsUtxo, OperationError> {
-		match self.inner.clone() {
-			JsLeafInner::VAnchor(leaf) => Ok(leaf),
-			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
-		}
+		self.inner.vanchor_leaf().map(Into::into)
 	}
 }
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl JsLeaf {
 	#[wasm_bindgen(getter)]
 	pub fn protocol(&self) -> Protocol {
 		let protocol = match self.inner {
-			JsLeafInner::Mixer(_) => "mixer",
-			JsLeafInner::VAnchor(_) => "vanchor",
+			NoteLeaf::Mixer(_) => "mixer",
+			NoteLeaf::Anchor(_) => "anchor",
+			NoteLeaf::VAnchor(_) => "vanchor",
 		};
 
 		JsValue::from(protocol).into()
@@ -76,16 +154,108 @@ impl JsLeaf {
 
 	#[wasm_bindgen(getter)]
 	pub fn commitment(&self) -> Uint8Array {
-		match &self.inner {
-			JsLeafInner::Mixer(leaf) => Uint8Array::from(leaf.leaf_bytes.as_slice()),
-			JsLeafInner::VAnchor(vanchor_leaf) => vanchor_leaf.commitment(),
+		Uint8Array::from(self.inner.commitment().as_slice())
+	}
+}
+/// A note metadata field that doesn't match the note secrets
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoteMismatch {
+	pub field: String,
+	/// Value in the note metadata
+	pub note: String,
+	/// Value the secrets, or the given commitment, call for
+	pub expected: String,
+}
+
+impl NoteMismatch {
+	fn new(field: &str, note: impl ToString, expected: impl ToString) -> Self {
+		Self {
+			field: field.to_string(),
+			note: note.to_string(),
+			expected: expected.to_string(),
 		}
 	}
 }
-impl JsNote {
+
+impl Note {
 	/// Deseralize note from a string
 	pub fn deserialize(note: &str) -> Result<Self, OperationError> {
-		note.parse().map_err(Into::into)
+		note.parse()
+	}
+
+	/// Deserialize a note and `validate` it
+	pub fn deserialize_strict(note: &str) -> Result<Self, OperationError> {
+		let note = Self::deserialize(note)?;
+		note.validate(None)?;
+		Ok(note)
+	}
+
+	/// Cross-check the note metadata with its secrets
+	///
+	/// For a VAnchor note the amount and target chain id have to match the
+	/// UTXO in the secrets, and the index has to fit in the tree. The leaf
+	/// commitment is compared with `commitment` when given.
+	pub fn check_integrity(&self, commitment: Option<&[u8]>) -> Result<Vec<NoteMismatch>, OperationError> {
+		let mut mismatches = Vec::new();
+		if self.protocol == NoteProtocol::VAnchor {
+			let secrets = VAnchorNoteSecrets::decode(&self.secrets)?;
+
+			let amount = secrets.amount();
+			match self.amount.as_deref().map(|note_amount| parse_units(note_amount, 0)) {
+				Some(Ok(note_amount)) if note_amount == amount => {}
+				_ => mismatches.push(NoteMismatch::new(
+					"amount",
+					self.amount.as_deref().unwrap_or_default(),
+					amount,
+				)),
+			}
+
+			let chain_id = secrets.chain_id();
+			if self.target_chain_id.parse::<u64>().ok() != Some(chain_id) {
+				mismatches.push(NoteMismatch::new("targetChainId", &self.target_chain_id, chain_id));
+			}
+
+			let height = self.height.unwrap_or(TREE_HEIGHT);
+			match self.index {
+				Some(index) if index < 1 << height => {}
+				index => mismatches.push(NoteMismatch::new(
+					"index",
+					index.map(|index| index.to_string()).unwrap_or_default(),
+					format!("below 2^{}", height),
+				)),
+			}
+		}
+
+		if let Some(commitment) = commitment {
+			match self.get_leaf_commitment() {
+				Ok(leaf) if leaf == commitment => {}
+				Ok(leaf) => mismatches.push(NoteMismatch::new(
+					"commitment",
+					hex::encode(leaf),
+					hex::encode(commitment),
+				)),
+				Err(e) => mismatches.push(NoteMismatch::new(
+					"commitment",
+					e.error_message,
+					hex::encode(commitment),
+				)),
+			}
+		}
+		Ok(mismatches)
+	}
+
+	/// `check_integrity` failing with `InvalidNoteIntegrity` on any
+	/// mismatch, the error data is the JSON list of the mismatches
+	pub fn validate(&self, commitment: Option<&[u8]>) -> Result<(), OperationError> {
+		let mismatches = self.check_integrity(commitment)?;
+		if mismatches.is_empty() {
+			return Ok(());
+		}
+		let fields: Vec<_> = mismatches.iter().map(|mismatch| mismatch.field.as_str()).collect();
+		let message = format!("Note metadata doesn't match its secrets: {}", fields.join(", "));
+		let mut error = OperationError::new_with_message(OpStatusCode::InvalidNoteIntegrity, message);
+		error.data = serde_json::to_string(&mismatches).ok();
+		Err(error)
 	}
 
 	pub fn mutate_index(&mut self, index: u64) -> Result<(), OperationError> {
@@ -102,16 +272,11 @@ impl JsNote {
 		Ok(())
 	}
 
-	pub fn get_leaf_and_nullifier(&self) -> Result<JsLeaf, OperationError> {
+	pub fn get_leaf_and_nullifier(&self) -> Result<NoteLeaf, OperationError> {
 		match self.protocol {
 			NoteProtocol::Mixer => {
 				let raw = match self.version {
-					NoteVersion::V1 => {
-						let mut raw = Vec::new();
-						raw.extend_from_slice(&self.secrets[0][..]);
-						raw.extend_from_slice(&self.secrets[1][..]);
-						raw
-					}
+					NoteVersion::V1 => self.private_raw()?,
 				};
 
 				let mixer_leaf = mixer::get_leaf_with_private_raw(
@@ -121,68 +286,222 @@ impl JsNote {
 					&raw,
 				)?;
 
-				Ok(JsLeaf {
-					inner: JsLeafInner::Mixer(mixer_leaf),
-				})
+				Ok(NoteLeaf::Mixer(mixer_leaf))
+			}
+			NoteProtocol::Anchor => {
+				let raw = match self.version {
+					NoteVersion::V1 => self.private_raw()?,
+				};
+				let chain_id: u64 = self
+					.target_chain_id
+					.parse()
+					.map_err(|_| OpStatusCode::InvalidTargetChain)?;
+
+				let anchor_leaf = anchor::get_leaf_with_private_raw(
+					self.curve.unwrap_or(Curve::Bn254),
+					self.width.unwrap_or(4),
+					self.exponentiation.unwrap_or(5),
+					chain_id,
+					&raw,
+				)?;
+
+				Ok(NoteLeaf::Anchor(anchor_leaf))
 			}
 			NoteProtocol::VAnchor => match self.version {
 				NoteVersion::V1 => {
-					if self.secrets.len() == 4 {
-						let chain_id = self.secrets[0].clone();
-
-						let amount = self.secrets[1].clone();
-						let secret_key = self.secrets[2].clone();
-						let blinding = self.secrets[3].clone();
-						let index = self.index;
-
-						let mut amount_slice = [0u8; 16];
-						amount_slice.copy_from_slice(amount[16..32].to_vec().as_slice());
-						let amount = u128::from_be_bytes(amount_slice);
-
-						let mut chain_id_slice = [0u8; 8];
-						chain_id_slice.copy_from_slice(chain_id[chain_id.len() - 8..].to_vec().as_slice());
-						let chain_id = u64::from_be_bytes(chain_id_slice);
-
-						let curve = self.curve.unwrap_or(Curve::Bn254);
-						let width = self.width.unwrap_or(2);
-						let exponentiation = self.exponentiation.unwrap_or(5);
-
-						let utxo = vanchor::get_leaf_with_private_raw(
-							curve,
-							width,
-							exponentiation,
-							Some(secret_key),
-							Some(blinding),
-							chain_id,
-							amount,
-							index,
-						)?;
-
-						Ok(JsLeaf {
-							inner: JsLeafInner::VAnchor(utxo),
-						})
-					} else {
-						let message = format!("Invalid secret format for protocol {}", self.protocol);
-						Err(OperationError::new_with_message(
-							OpStatusCode::InvalidNoteSecrets,
-							message,
-						))
-					}
+					let secrets = VAnchorNoteSecrets::decode(&self.secrets)?;
+					let utxo = secrets.to_utxo(
+						self.curve.unwrap_or(Curve::Bn254),
+						self.width.unwrap_or(2),
+						self.exponentiation.unwrap_or(5),
+						self.index,
+					)?;
+
+					Ok(NoteLeaf::VAnchor(utxo))
 				}
 			},
 		}
 	}
 
-	pub fn get_js_utxo(&self) -> Result<JsUtxo, OperationError> {
+	/// The `secret ++ nullifier` of a mixer or anchor note
+	fn private_raw(&self) -> Result<Zeroizing<Vec<u8>>, OperationError> {
+		check_secrets(self.protocol, &self.secrets)?;
+		let mut raw = Zeroizing::new(Vec::new());
+		raw.extend_from_slice(&self.secrets[0][..]);
+		raw.extend_from_slice(&self.secrets[1][..]);
+		Ok(raw)
+	}
+
+	/// Runs the checks of `FromStr` on the fields of the note
+	pub(crate) fn check_fields(&self) -> Result<(), OperationError> {
+		let _: u64 = self
+			.source_chain_id
+			.parse()
+			.map_err(|_| OpStatusCode::InvalidSourceChain)?;
+		let _: u64 = self
+			.target_chain_id
+			.parse()
+			.map_err(|_| OpStatusCode::InvalidTargetChain)?;
+		self.height.map(check_tree_height).transpose()?;
+		// Amounts are stored in base units
+		self.amount
+			.as_deref()
+			.map(|amount| parse_units(amount, 0))
+			.transpose()?;
+		check_secrets(self.protocol, &self.secrets)
+	}
+
+	pub fn get_leaf_commitment(&self) -> Result<Vec<u8>, OperationError> {
 		let leaf = self.get_leaf_and_nullifier()?;
-		match leaf.inner {
-			JsLeafInner::VAnchor(utxo) => Ok(utxo),
+		Ok(leaf.commitment())
+	}
+
+	pub fn get_utxo(&self) -> Result<Utxo, OperationError> {
+		match self.protocol {
+			NoteProtocol::VAnchor => {
+				let leaf = self.get_leaf_and_nullifier()?;
+				leaf.vanchor_leaf()
+			}
 			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
 		}
 	}
+
+	/// Amount of the note in base units
+	pub fn get_amount(&self) -> Result<u128, OperationError> {
+		let amount = self.amount.as_deref().ok_or(OpStatusCode::InvalidAmount)?;
+		parse_units(amount, 0)
+	}
+
+	/// Amount of the note as a decimal, its denomination being the decimals
+	/// of the token
+	pub fn format_amount(&self) -> Result<String, OperationError> {
+		let decimals = self.denomination.ok_or(OpStatusCode::InvalidDenomination)?;
+		Ok(format_units(self.get_amount()?, decimals))
+	}
+
+	/// The public fields of the note, safe to log or attach to error reports
+	///
+	/// The `Display` output is the note string and holds the secrets.
+	pub fn redacted(&self) -> RedactedNote {
+		RedactedNote {
+			protocol: self.protocol,
+			source_chain_id: self.source_chain_id.clone(),
+			target_chain_id: self.target_chain_id.clone(),
+			curve: self.curve,
+			token_symbol: self.token_symbol.clone(),
+			amount: self.amount.clone(),
+			denomination: self.denomination,
+			index: self.index,
+			commitment: self.get_leaf_commitment().ok(),
+		}
+	}
+
+	/// Check the amount of a VAnchor note matches the amount of its UTXO
+	pub fn check_utxo_amount(&self) -> Result<(), OperationError> {
+		if self.protocol != NoteProtocol::VAnchor {
+			return Ok(());
+		}
+		let amount = self.get_amount()?;
+		let utxo_amount = self.get_utxo()?.get_amount_raw()?;
+		if utxo_amount != amount {
+			let message = format!("Note amount {} doesn't match the UTXO amount {}", amount, utxo_amount);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidAmount, message));
+		}
+		Ok(())
+	}
+
+	/// Create a zero amount UTXO note sharing the metadata of `note`
+	pub fn default_utxo_note(note: &Note) -> Result<Note, OperationError> {
+		let mut new_note = Note {
+			scheme: note.scheme.clone(),
+			protocol: note.protocol,
+			version: note.version,
+			source_chain_id: note.source_chain_id.clone(),
+			target_chain_id: note.target_chain_id.clone(),
+			source_identifying_data: note.source_identifying_data.clone(),
+			target_identifying_data: note.target_identifying_data.clone(),
+			secrets: note.secrets.clone(),
+			curve: note.curve,
+			exponentiation: note.exponentiation,
+			width: note.width,
+			height: note.height,
+			token_symbol: note.token_symbol.clone(),
+			amount: Some("0".to_string()),
+			denomination: note.denomination,
+			backend: note.backend,
+			hash_function: note.hash_function,
+			index: Some(0),
+		};
+		let chain_id: u64 = new_note
+			.target_chain_id
+			.parse()
+			.map_err(|_| OpStatusCode::InvalidTargetChain)?;
+
+		let utxo = vanchor::generate_secrets(
+			0,
+			new_note.exponentiation.unwrap_or(5),
+			new_note.width.unwrap_or(5),
+			new_note.curve.unwrap_or(Curve::Bn254),
+			chain_id,
+			Some(0),
+			&mut OsRng,
+		)?;
+		new_note.update_vanchor_utxo(utxo)?;
+		Ok(new_note)
+	}
+
+	// for test and internal usage
+	pub fn update_vanchor_utxo(&mut self, utxo: Utxo) -> Result<(), OperationError> {
+		self.amount = Some(utxo.get_amount_raw()?.to_string());
+		self.secrets = VAnchorNoteSecrets::from_utxo(&utxo).encode().into();
+		Ok(())
+	}
+
+	/// Notes built before the secrets order was fixed stored the blinding
+	/// before the secret key. Given the commitment inserted in the tree, swap
+	/// them back if needed, returns whether the note was changed.
+	pub fn migrate_vanchor_secrets(&mut self, commitment: &[u8]) -> Result<bool, OperationError> {
+		if self.protocol != NoteProtocol::VAnchor {
+			let message = "Only VAnchor notes can be migrated".to_string();
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidNoteProtocol,
+				message,
+			));
+		}
+		if self.get_leaf_commitment()? == commitment {
+			return Ok(false);
+		}
+
+		let mismatch = || {
+			let message = "Note secrets don't match the commitment".to_string();
+			OperationError::new_with_message(OpStatusCode::InvalidNoteSecrets, message)
+		};
+		let swapped = match VAnchorNoteSecrets::decode(&self.secrets)? {
+			VAnchorNoteSecrets::Owned {
+				chain_id,
+				amount,
+				secret_key,
+				blinding,
+			} => VAnchorNoteSecrets::Owned {
+				chain_id,
+				amount,
+				secret_key: blinding,
+				blinding: secret_key,
+			},
+			VAnchorNoteSecrets::Public { .. } => return Err(mismatch()),
+		};
+		let mut migrated = self.clone();
+		migrated.secrets = swapped.encode().into();
+		if migrated.get_leaf_commitment()? != commitment {
+			return Err(mismatch());
+		}
+
+		*self = migrated;
+		Ok(true)
+	}
 }
 
-impl fmt::Display for JsNote {
+impl fmt::Display for Note {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		// Note URI scheme
 		let scheme = "webb://";
@@ -197,7 +516,7 @@ impl fmt::Display for JsNote {
 		]
 		.join(":");
 
-		let secrets = &self.secrets.iter().map(hex::encode).collect::<Vec<String>>().join(":");
+		let secrets = encode_secrets(&self.secrets);
 
 		// Note URI miscellaneous queries
 		#[allow(clippy::map_clone)]
@@ -217,6 +536,11 @@ impl fmt::Display for JsNote {
 			} else {
 				"".to_string()
 			},
+			if self.height.is_some() {
+				format!("height={}", self.height.unwrap())
+			} else {
+				"".to_string()
+			},
 			if self.hash_function.is_some() {
 				format!("hf={}", self.hash_function.unwrap())
 			} else {
@@ -256,14 +580,20 @@ impl fmt::Display for JsNote {
 		// Note URI queries are prefixed with `?`
 		let misc = ["?".to_string(), misc_values].join("");
 
-		let parts: Vec<String> = vec![authority, chain_ids, chain_identifying_data, secrets.to_string(), misc];
+		let parts = Zeroizing::new(vec![
+			authority,
+			chain_ids,
+			chain_identifying_data,
+			secrets.to_string(),
+			misc,
+		]);
 		// Join the parts with `/` and connect to the scheme as is
-		let note = [scheme.to_string(), parts.join("/")].join("");
-		write!(f, "{}", note)
+		let path = Zeroizing::new(parts.join("/"));
+		write!(f, "{}{}", scheme, path.as_str())
 	}
 }
 
-impl FromStr for JsNote {
+impl FromStr for Note {
 	type Err = OperationError;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -271,97 +601,299 @@ impl FromStr for JsNote {
 	}
 }
 
-#[wasm_bindgen]
-#[derive(Debug, Eq, PartialEq, Clone)]
-pub struct JsNote {
-	#[wasm_bindgen(skip)]
+impl Serializable for Note {}
+
+/// Unchecked fields of a decoded `Note`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NoteFields {
+	scheme: String,
+	protocol: NoteProtocol,
+	version: NoteVersion,
+	source_chain_id: String,
+	target_chain_id: String,
+	source_identifying_data: String,
+	target_identifying_data: String,
+	#[serde(with = "hex_bytes_list")]
+	secrets: Secret<Vec<Vec<u8>>>,
+	curve: Option<Curve>,
+	exponentiation: Option<i8>,
+	width: Option<usize>,
+	height: Option<usize>,
+	token_symbol: Option<String>,
+	amount: Option<String>,
+	denomination: Option<u8>,
+	backend: Option<Backend>,
+	hash_function: Option<HashFunction>,
+	index: Option<u64>,
+}
+
+impl TryFrom<NoteFields> for Note {
+	type Error = OperationError;
+
+	fn try_from(fields: NoteFields) -> Result<Self, Self::Error> {
+		let note = Note {
+			scheme: fields.scheme,
+			protocol: fields.protocol,
+			version: fields.version,
+			source_chain_id: fields.source_chain_id,
+			target_chain_id: fields.target_chain_id,
+			source_identifying_data: fields.source_identifying_data,
+			target_identifying_data: fields.target_identifying_data,
+			secrets: fields.secrets,
+			curve: fields.curve,
+			exponentiation: fields.exponentiation,
+			width: fields.width,
+			height: fields.height,
+			token_symbol: fields.token_symbol,
+			amount: fields.amount,
+			denomination: fields.denomination,
+			backend: fields.backend,
+			hash_function: fields.hash_function,
+			index: fields.index,
+		};
+		note.check_fields()?;
+		Ok(note)
+	}
+}
+
+/// Checks `secrets` are the `[secret, nullifier]` of a mixer or anchor note, or
+/// the UTXO secrets of a VAnchor note
+fn check_secrets(protocol: NoteProtocol, secrets: &[Vec<u8>]) -> Result<(), OperationError> {
+	match protocol {
+		NoteProtocol::Mixer | NoteProtocol::Anchor => match secrets {
+			[secret, nullifier] if secret.len() == 32 && nullifier.len() == 32 => Ok(()),
+			_ => Err(OperationError::new_with_message(
+				OpStatusCode::InvalidNoteSecrets,
+				format!("{} secrets should be a 32 bytes secret and nullifier", protocol),
+			)),
+		},
+		NoteProtocol::VAnchor => VAnchorNoteSecrets::decode(secrets).map(|_| ()),
+	}
+}
+
+/// Public view of a `Note`, see `Note::redacted`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactedNote {
+	pub protocol: NoteProtocol,
+	pub source_chain_id: String,
+	pub target_chain_id: String,
+	pub curve: Option<Curve>,
+	pub token_symbol: Option<String>,
+	pub amount: Option<String>,
+	pub denomination: Option<u8>,
+	pub index: Option<u64>,
+	/// Leaf of the note, unset if the secrets don't give one
+	#[serde(with = "optional_hex_bytes")]
+	pub commitment: Option<Vec<u8>>,
+}
+
+impl Serializable for RedactedNote {}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "NoteFields")]
+pub struct Note {
 	pub scheme: String,
-	#[wasm_bindgen(skip)]
 	pub protocol: NoteProtocol,
-	#[wasm_bindgen(skip)]
 	pub version: NoteVersion,
-	#[wasm_bindgen(skip)]
 	pub source_chain_id: String,
-	#[wasm_bindgen(skip)]
 	pub target_chain_id: String,
-	#[wasm_bindgen(skip)]
 	pub source_identifying_data: String,
-	#[wasm_bindgen(skip)]
 	pub target_identifying_data: String,
 
 	/// mixer related items
-	#[wasm_bindgen(skip)]
-	pub secrets: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes_list")]
+	pub secrets: Secret<Vec<Vec<u8>>>,
 
 	/// Misc - zkp related items
-	#[wasm_bindgen(skip)]
 	pub curve: Option<Curve>,
-	#[wasm_bindgen(skip)]
 	pub exponentiation: Option<i8>,
-	#[wasm_bindgen(skip)]
 	pub width: Option<usize>,
+	/// Height of the Merkle tree of the note, `TREE_HEIGHT` when unset
+	pub height: Option<usize>,
 
-	#[wasm_bindgen(skip)]
 	pub token_symbol: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub amount: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub denomination: Option<u8>,
 
-	#[wasm_bindgen(skip)]
 	pub backend: Option<Backend>,
-	#[wasm_bindgen(skip)]
 	pub hash_function: Option<HashFunction>,
 
-	#[wasm_bindgen(skip)]
 	pub index: Option<u64>,
 }
 
-#[wasm_bindgen]
 #[derive(Default)]
-pub struct JsNoteBuilder {
-	#[wasm_bindgen(skip)]
+pub struct NoteBuilder {
 	pub protocol: Option<NoteProtocol>,
-	#[wasm_bindgen(skip)]
 	pub version: Option<NoteVersion>,
-	#[wasm_bindgen(skip)]
 	pub source_chain_id: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub target_chain_id: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub source_identifying_data: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub target_identifying_data: Option<String>,
 
-	#[wasm_bindgen(skip)]
 	pub amount: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub denomination: Option<u8>,
-	#[wasm_bindgen(skip)]
-	pub secrets: Option<Vec<Vec<u8>>>,
+	pub secrets: Option<Secret<Vec<Vec<u8>>>>,
 
 	// Misc - zkp related items
-	#[wasm_bindgen(skip)]
 	pub backend: Option<Backend>,
-	#[wasm_bindgen(skip)]
 	pub hash_function: Option<HashFunction>,
-	#[wasm_bindgen(skip)]
 	pub curve: Option<Curve>,
-	#[wasm_bindgen(skip)]
 	pub token_symbol: Option<String>,
-	#[wasm_bindgen(skip)]
 	pub exponentiation: Option<i8>,
-	#[wasm_bindgen(skip)]
 	pub width: Option<usize>,
+	pub height: Option<usize>,
 	// Utxo index
-	#[wasm_bindgen(skip)]
 	pub index: Option<u64>,
+	pub private_key: Option<Secret<Vec<u8>>>,
+	/// Owner of a public UTXO, ignored when a private key is set
+	pub public_key: Option<Vec<u8>>,
+	pub blinding: Option<Secret<Vec<u8>>>,
+}
+
+impl NoteBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn build(self) -> Result<Note, OperationError> {
+		// Authority
+		let version = self.version.ok_or(OpStatusCode::InvalidNoteVersion)?;
+		let protocol = self.protocol.ok_or(OpStatusCode::InvalidNoteProtocol)?;
+
+		// Chain Ids
+		let source_chain_id = self.source_chain_id.ok_or(OpStatusCode::InvalidSourceChain)?;
+		let _: u64 = source_chain_id.parse().map_err(|_| OpStatusCode::InvalidSourceChain)?;
+		let target_chain_id = self.target_chain_id.ok_or(OpStatusCode::InvalidTargetChain)?;
+		let chain_id: u64 = target_chain_id.parse().map_err(|_| OpStatusCode::InvalidTargetChain)?;
+
+		// Chain identifying data
+		let source_identifying_data = self
+			.source_identifying_data
+			.ok_or(OpStatusCode::InvalidSourceIdentifyingData)?;
+		let target_identifying_data = self
+			.target_identifying_data
+			.ok_or(OpStatusCode::InvalidTargetIdentifyingData)?;
+
+		// Misc
+		let exponentiation = self.exponentiation;
+		let width = self.width;
+		let height = self.height.map(check_tree_height).transpose()?;
+		let curve = self.curve;
+		let amount = self
+			.amount
+			.as_deref()
+			.map(|amount| parse_units(amount, 0))
+			.transpose()?;
+		let index = self.index;
+		let backend = self.backend.unwrap_or(Backend::Arkworks);
+
+		if backend == Backend::Circom && self.secrets.is_none() {
+			let message = "Circom backend is supported when the secret value is supplied".to_string();
+			let operation_error = OperationError::new_with_message(OpStatusCode::UnsupportedBackend, message);
+			return Err(operation_error);
+		}
+
+		let secrets = match self.secrets {
+			None => match protocol {
+				NoteProtocol::Mixer => {
+					let secrets = mixer::generate_secrets(
+						exponentiation.unwrap_or(5),
+						width.unwrap_or(5),
+						curve.unwrap_or(Curve::Bn254),
+						&mut OsRng,
+					)?;
+
+					Vec::from(secrets).into()
+				}
+				NoteProtocol::Anchor => {
+					let secrets = anchor::generate_secrets(
+						exponentiation.unwrap_or(5),
+						width.unwrap_or(4),
+						curve.unwrap_or(Curve::Bn254),
+						chain_id,
+						&mut OsRng,
+					)?;
+
+					Vec::from(secrets).into()
+				}
+				NoteProtocol::VAnchor => {
+					// Cloned for the prover, which doesn't wipe them
+					let blinding = self.blinding.as_deref().cloned();
+					let amount = amount.unwrap_or(0);
+					let utxo = match (self.private_key.as_deref().cloned(), self.public_key) {
+						(None, Some(public_key)) => vanchor::get_public_leaf_raw(
+							curve.unwrap_or(Curve::Bn254),
+							width.unwrap_or(5),
+							exponentiation.unwrap_or(5),
+							public_key,
+							blinding,
+							chain_id,
+							amount,
+							index,
+						)?,
+						(private_key, _) => vanchor::get_leaf_with_private_raw(
+							curve.unwrap_or(Curve::Bn254),
+							width.unwrap_or(5),
+							exponentiation.unwrap_or(5),
+							private_key,
+							blinding,
+							chain_id,
+							amount,
+							index,
+						)?,
+					};
+
+					VAnchorNoteSecrets::from_utxo(&utxo).encode().into()
+				}
+			},
+			Some(secrets) => {
+				check_secrets(protocol, &secrets)?;
+				secrets
+			}
+		};
+
+		let backend = self.backend;
+		let hash_function = self.hash_function;
+		let token_symbol = self.token_symbol;
+		let amount = self.amount.clone();
+		let denomination = self.denomination;
+
+		let scheme = "webb://".to_string();
+		let note = Note {
+			scheme,
+			protocol,
+			version,
+			source_chain_id,
+			target_chain_id,
+			source_identifying_data,
+			target_identifying_data,
+			backend,
+			hash_function,
+			curve,
+			token_symbol,
+			amount,
+			denomination,
+			exponentiation,
+			width,
+			height,
+			secrets,
+			index,
+		};
+		Ok(note)
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct JsNoteBuilder {
 	#[wasm_bindgen(skip)]
-	pub private_key: Option<Vec<u8>>,
-	#[wasm_bindgen(skip)]
-	pub blinding: Option<Vec<u8>>,
+	pub inner: NoteBuilder,
 }
 
+#[cfg(feature = "js")]
 #[allow(clippy::unused_unit)]
 #[wasm_bindgen]
 impl JsNoteBuilder {
@@ -378,7 +910,7 @@ impl JsNoteBuilder {
 			.as_str()
 			.parse()
 			.map_err(|_| OpStatusCode::InvalidNoteProtocol)?;
-		self.protocol = Some(note_protocol);
+		self.inner.protocol = Some(note_protocol);
 		Ok(())
 	}
 
@@ -387,34 +919,34 @@ impl JsNoteBuilder {
 			.as_string()
 			.ok_or(OpStatusCode::InvalidNoteVersion)?;
 		let note_version: NoteVersion = version.as_str().parse().map_err(|_| OpStatusCode::InvalidNoteVersion)?;
-		self.version = Some(note_version);
+		self.inner.version = Some(note_version);
 		Ok(())
 	}
 
 	#[wasm_bindgen(js_name = sourceChainId)]
 	pub fn source_chain_id(&mut self, source_chain_id: JsString) {
-		self.source_chain_id = Some(source_chain_id.into());
+		self.inner.source_chain_id = Some(source_chain_id.into());
 	}
 
 	#[wasm_bindgen(js_name = targetChainId)]
 	pub fn target_chain_id(&mut self, target_chain_id: JsString) {
-		self.target_chain_id = Some(target_chain_id.into());
+		self.inner.target_chain_id = Some(target_chain_id.into());
 	}
 
 	#[wasm_bindgen(js_name = sourceIdentifyingData)]
 	pub fn source_identifying_data(&mut self, source_identifying_data: JsString) {
-		self.source_identifying_data = Some(source_identifying_data.into());
+		self.inner.source_identifying_data = Some(source_identifying_data.into());
 	}
 
 	#[wasm_bindgen(js_name = targetIdentifyingData)]
 	pub fn target_identifying_data(&mut self, target_identifying_data: JsString) {
-		self.target_identifying_data = Some(target_identifying_data.into());
+		self.inner.target_identifying_data = Some(target_identifying_data.into());
 	}
 
 	pub fn backend(&mut self, backend: BE) {
 		let c: String = JsValue::from(&backend).as_string().unwrap();
 		let backend: Backend = c.parse().unwrap();
-		self.backend = Some(backend);
+		self.inner.backend = Some(backend);
 	}
 
 	#[wasm_bindgen(js_name = hashFunction)]
@@ -423,196 +955,139 @@ impl JsNoteBuilder {
 			.as_string()
 			.ok_or(OpStatusCode::InvalidHasFunction)?;
 		let hash_function: HashFunction = hash_function.parse().map_err(|_| OpStatusCode::InvalidHasFunction)?;
-		self.hash_function = Some(hash_function);
+		self.inner.hash_function = Some(hash_function);
 		Ok(())
 	}
 
 	pub fn curve(&mut self, curve: WasmCurve) -> Result<(), JsValue> {
 		let curve: String = JsValue::from(&curve).as_string().ok_or(OpStatusCode::InvalidCurve)?;
 		let curve: Curve = curve.parse().map_err(|_| OpStatusCode::InvalidCurve)?;
-		self.curve = Some(curve);
+		self.inner.curve = Some(curve);
 		Ok(())
 	}
 
 	#[wasm_bindgen(js_name = tokenSymbol)]
 	pub fn token_symbol(&mut self, token_symbol: JsString) {
-		self.token_symbol = Some(token_symbol.into());
+		self.inner.token_symbol = Some(token_symbol.into());
 	}
 
 	pub fn amount(&mut self, amount: JsString) {
-		self.amount = Some(amount.into());
+		self.inner.amount = Some(amount.into());
 	}
 
 	pub fn denomination(&mut self, denomination: JsString) -> Result<(), JsValue> {
 		let den: String = denomination.into();
 		let denomination = den.parse().map_err(|_| OpStatusCode::InvalidDenomination)?;
-		self.denomination = Some(denomination);
+		self.inner.denomination = Some(denomination);
 		Ok(())
 	}
 
 	pub fn index(&mut self, index: JsString) -> Result<(), JsValue> {
 		let index: String = index.into();
 		let index: u64 = index.parse().map_err(|_| OpStatusCode::InvalidUTXOIndex)?;
-		self.index = Some(index);
+		self.inner.index = Some(index);
 		Ok(())
 	}
 
 	pub fn exponentiation(&mut self, exponentiation: JsString) -> Result<(), JsValue> {
 		let exp: String = exponentiation.into();
 		let exponentiation = exp.parse().map_err(|_| OpStatusCode::InvalidExponentiation)?;
-		self.exponentiation = Some(exponentiation);
+		self.inner.exponentiation = Some(exponentiation);
 		Ok(())
 	}
 
 	pub fn width(&mut self, width: JsString) -> Result<(), JsValue> {
 		let width: String = width.into();
 		let width = width.parse().map_err(|_| OpStatusCode::InvalidWidth)?;
-		self.width = Some(width);
+		self.inner.width = Some(width);
+		Ok(())
+	}
+
+	pub fn height(&mut self, height: JsString) -> Result<(), JsValue> {
+		let height: String = height.into();
+		let height = height.parse().map_err(|_| OpStatusCode::InvalidTreeHeight)?;
+		self.inner.height = Some(check_tree_height(height)?);
 		Ok(())
 	}
 
 	#[wasm_bindgen(js_name = setSecrets)]
 	pub fn set_secrets(&mut self, secrets: JsString) -> Result<(), JsValue> {
-		let secrets_string: String = secrets.into();
-		let secrets_parts: Vec<String> = secrets_string.split(':').map(String::from).collect();
-		let secs = secrets_parts
-			.iter()
-			.map(|v| hex::decode(v.replace("0x", "")).unwrap_or_default())
-			.collect();
-		self.secrets = Some(secs);
+		let secrets_string: Zeroizing<String> = Zeroizing::new(secrets.into());
+		let secs = secrets_string
+			.split(':')
+			.map(|v| {
+				hex::decode(Zeroizing::new(v.replace("0x", "")).as_bytes())
+					.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidNoteSecrets, e.to_string()))
+			})
+			.collect::<Result<Vec<Vec<u8>>, OperationError>>()?;
+		self.inner.secrets = Some(secs.into());
 		Ok(())
 	}
 
 	#[wasm_bindgen(js_name = setPrivateKey)]
 	pub fn set_private_key(&mut self, private_key: Uint8Array) -> Result<(), JsValue> {
-		self.private_key = Some(private_key.to_vec());
+		self.inner.private_key = Some(private_key.to_vec().into());
+		Ok(())
+	}
+
+	#[wasm_bindgen(js_name = setPublicKey)]
+	pub fn set_public_key(&mut self, public_key: Uint8Array) -> Result<(), JsValue> {
+		self.inner.public_key = Some(public_key.to_vec());
 		Ok(())
 	}
 
 	#[wasm_bindgen(js_name = setBlinding)]
 	pub fn set_blinding(&mut self, blinding: Uint8Array) -> Result<(), JsValue> {
-		self.blinding = Some(blinding.to_vec());
+		self.inner.blinding = Some(blinding.to_vec().into());
 		Ok(())
 	}
 
 	pub fn build(self) -> Result<JsNote, JsValue> {
-		// Authority
-		let version = self.version.ok_or(OpStatusCode::InvalidNoteVersion)?;
-		let protocol = self.protocol.ok_or(OpStatusCode::InvalidNoteProtocol)?;
-
-		// Chain Ids
-		let source_chain_id = self.source_chain_id.ok_or(OpStatusCode::InvalidSourceChain)?;
-		let _: u64 = source_chain_id.parse().map_err(|_| OpStatusCode::InvalidSourceChain)?;
-		let target_chain_id = self.target_chain_id.ok_or(OpStatusCode::InvalidTargetChain)?;
-		let chain_id: u64 = target_chain_id.parse().map_err(|_| OpStatusCode::InvalidTargetChain)?;
-
-		// Chain identifying data
-		let source_identifying_data = self.source_identifying_data.ok_or_else(|| "".to_string())?;
-		let target_identifying_data = self.target_identifying_data.ok_or_else(|| "".to_string())?;
-
-		// Misc
-		let exponentiation = self.exponentiation;
-		let width = self.width;
-		let curve = self.curve;
-		let amount = self.amount.clone();
-		let index = self.index;
-		let backend = self.backend.unwrap_or(Backend::Arkworks);
-
-		if backend == Backend::Circom && self.secrets.is_none() {
-			let message = "Circom backend is supported when the secret value is supplied".to_string();
-			let operation_error = OperationError::new_with_message(OpStatusCode::UnsupportedBackend, message);
-			return Err(operation_error.into());
-		}
-
-		let secrets = match self.secrets {
-			None => match protocol {
-				NoteProtocol::Mixer => {
-					let secrets = mixer::generate_secrets(
-						exponentiation.unwrap_or(5),
-						width.unwrap_or(5),
-						curve.unwrap_or(Curve::Bn254),
-						&mut OsRng,
-					)?;
+		let note = self.inner.build()?;
+		Ok(note.into())
+	}
+}
 
-					secrets.to_vec()
-				}
-				NoteProtocol::VAnchor => {
-					let blinding = self.blinding;
-					let private_key = self.private_key;
-					let utxo = vanchor::get_leaf_with_private_raw(
-						curve.unwrap_or(Curve::Bn254),
-						width.unwrap_or(5),
-						exponentiation.unwrap_or(5),
-						private_key,
-						blinding,
-						chain_id,
-						amount.unwrap_or_else(|| "0".to_string()).parse().unwrap(),
-						index,
-					)?;
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct JsNote {
+	#[wasm_bindgen(skip)]
+	pub inner: Note,
+}
 
-					let chain_id = utxo.get_chain_id_bytes();
-					let amount = utxo.get_amount();
-					let blinding = utxo.get_blinding();
-					let secret_key = utxo.get_secret_key().unwrap();
+#[cfg(feature = "js")]
+impl Deref for JsNote {
+	type Target = Note;
 
-					// secrets
-					vec![chain_id, amount, blinding, secret_key]
-				}
-			},
-			Some(secrets) => {
-				match protocol {
-					NoteProtocol::Mixer => {
-						if secrets.len() != 1 {
-							let message = "Mixer secrets length should be 1 in length".to_string();
-							let operation_error =
-								OperationError::new_with_message(OpStatusCode::InvalidNoteSecrets, message);
-							return Err(operation_error.into());
-						}
-					}
-					NoteProtocol::VAnchor => {
-						if secrets.len() != 4 {
-							let message = "VAnchor secrets length should be 4 in length".to_string();
-							let operation_error =
-								OperationError::new_with_message(OpStatusCode::InvalidNoteSecrets, message);
-							return Err(operation_error.into());
-						}
-					}
-				};
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
 
-				secrets
-			}
-		};
+#[cfg(feature = "js")]
+impl DerefMut for JsNote {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.inner
+	}
+}
 
-		let backend = self.backend;
-		let hash_function = self.hash_function;
-		let token_symbol = self.token_symbol;
-		let amount = self.amount.clone();
-		let denomination = self.denomination;
+#[cfg(feature = "js")]
+impl From<Note> for JsNote {
+	fn from(inner: Note) -> Self {
+		Self { inner }
+	}
+}
 
-		let scheme = "webb://".to_string();
-		let note = JsNote {
-			scheme,
-			protocol,
-			version,
-			source_chain_id,
-			target_chain_id,
-			source_identifying_data,
-			target_identifying_data,
-			backend,
-			hash_function,
-			curve,
-			token_symbol,
-			amount,
-			denomination,
-			exponentiation,
-			width,
-			secrets,
-			index,
-		};
-		Ok(note)
+#[cfg(feature = "js")]
+impl JsNote {
+	/// Deseralize note from a string
+	pub fn deserialize(note: &str) -> Result<Self, OperationError> {
+		Note::deserialize(note).map(Into::into)
 	}
 }
 
+#[cfg(feature = "js")]
 #[allow(clippy::unused_unit)]
 #[wasm_bindgen]
 impl JsNote {
@@ -621,22 +1096,35 @@ impl JsNote {
 		builder.build()
 	}
 
+	/// With `strict` the note is validated against its secrets
 	#[wasm_bindgen(js_name = deserialize)]
-	pub fn js_deserialize(note: JsString) -> Result<JsNote, JsValue> {
+	pub fn js_deserialize(note: JsString, strict: Option<bool>) -> Result<JsNote, JsValue> {
 		let n: String = note.into();
-		let n = JsNote::deserialize(&n)?;
+		let n = match strict {
+			Some(true) => Note::deserialize_strict(&n)?.into(),
+			_ => JsNote::deserialize(&n)?,
+		};
 		Ok(n)
 	}
 
+	/// Fails with `InvalidNoteIntegrity` when the metadata doesn't match the
+	/// secrets, or the leaf isn't `commitment`. The error data lists the
+	/// mismatching fields
+	pub fn validate(&self, commitment: Option<Uint8Array>) -> Result<(), JsValue> {
+		let commitment = commitment.map(|commitment| commitment.to_vec());
+		self.inner.validate(commitment.as_deref())?;
+		Ok(())
+	}
+
 	#[wasm_bindgen(js_name = getLeafCommitment)]
 	pub fn get_leaf_commitment(&self) -> Result<Uint8Array, JsValue> {
-		let leaf = self.get_leaf_and_nullifier()?;
+		let commitment = self.inner.get_leaf_commitment()?;
 
-		Ok(leaf.commitment())
+		Ok(Uint8Array::from(commitment.as_slice()))
 	}
 
 	pub fn serialize(&self) -> JsString {
-		JsString::from(self.to_string())
+		JsString::from(self.inner.to_string())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -691,8 +1179,8 @@ impl JsNote {
 
 	#[wasm_bindgen(getter)]
 	pub fn secrets(&self) -> JsString {
-		let secrets = self.secrets.iter().map(hex::encode).collect::<Vec<String>>().join(":");
-		secrets.into()
+		let secrets = encode_secrets(&self.secrets);
+		JsString::from(secrets.as_str())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -712,12 +1200,36 @@ impl JsNote {
 		denomination.into()
 	}
 
+	/// The amount as a decimal, using the denomination as decimals
+	#[wasm_bindgen(js_name = formatAmount)]
+	pub fn format_amount_js(&self) -> Result<JsString, JsValue> {
+		Ok(self.inner.format_amount()?.into())
+	}
+
+	#[wasm_bindgen(js_name = checkUtxoAmount)]
+	pub fn check_utxo_amount_js(&self) -> Result<(), JsValue> {
+		self.inner.check_utxo_amount()?;
+		Ok(())
+	}
+
+	/// The note without its secrets, as a plain object
+	#[wasm_bindgen(js_name = redacted)]
+	pub fn redacted_js(&self) -> Result<JsValue, JsValue> {
+		Ok(to_js_object(&self.inner.redacted())?)
+	}
+
 	#[wasm_bindgen(getter)]
 	pub fn width(&self) -> JsString {
 		let width = self.width.unwrap_or_default().to_string();
 		width.into()
 	}
 
+	#[wasm_bindgen(getter)]
+	pub fn height(&self) -> JsString {
+		let height = self.height.unwrap_or(TREE_HEIGHT).to_string();
+		height.into()
+	}
+
 	#[wasm_bindgen(getter)]
 	pub fn exponentiation(&self) -> JsString {
 		let exp = self.exponentiation.unwrap_or_default().to_string();
@@ -734,63 +1246,22 @@ impl JsNote {
 
 	#[wasm_bindgen(js_name = defaultUtxoNote)]
 	pub fn default_utxo_note(note: &JsNote) -> Result<JsNote, OperationError> {
-		let mut new_note = JsNote {
-			scheme: note.scheme.clone(),
-			protocol: note.protocol,
-			version: note.version,
-			source_chain_id: note.source_chain_id.clone(),
-			target_chain_id: note.target_chain_id.clone(),
-			source_identifying_data: note.source_identifying_data.clone(),
-			target_identifying_data: note.target_identifying_data.clone(),
-			secrets: note.secrets.clone(),
-			curve: note.curve,
-			exponentiation: note.exponentiation,
-			width: note.width,
-			token_symbol: note.token_symbol.clone(),
-			amount: Some("0".to_string()),
-			denomination: note.denomination,
-			backend: note.backend,
-			hash_function: note.hash_function,
-			index: Some(0),
-		};
-		let chain_id: u64 = new_note
-			.target_chain_id
-			.parse()
-			.map_err(|_| OpStatusCode::InvalidTargetChain)?;
-
-		let utxo = vanchor::generate_secrets(
-			0,
-			new_note.exponentiation.unwrap_or(5),
-			new_note.width.unwrap_or(5),
-			new_note.curve.unwrap_or(Curve::Bn254),
-			chain_id,
-			Some(0),
-			&mut OsRng,
-		)?;
-		new_note.update_vanchor_utxo(utxo)?;
-		Ok(new_note)
+		Note::default_utxo_note(&note.inner).map(Into::into)
 	}
 
 	#[wasm_bindgen(js_name = getUtxo)]
 	pub fn get_utxo(&self) -> Result<JsUtxo, OperationError> {
-		match self.protocol {
-			NoteProtocol::VAnchor => {
-				let leaf = self.get_leaf_and_nullifier()?;
-				leaf.vanchor_leaf()
-			}
-			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
-		}
+		self.inner.get_utxo().map(Into::into)
 	}
 
 	// for test and internal usage
 	pub fn update_vanchor_utxo(&mut self, utxo: JsUtxo) -> Result<(), OperationError> {
-		let chain_id = utxo.get_chain_id_bytes();
-		let amount = utxo.get_amount();
-		let blinding = utxo.get_blinding();
-		let secret_key = utxo.get_secret_key().unwrap();
-		self.amount = Some(utxo.get_amount_raw().to_string());
-		self.secrets = vec![chain_id, amount, blinding, secret_key];
-		Ok(())
+		self.inner.update_vanchor_utxo(utxo.inner)
+	}
+
+	#[wasm_bindgen(js_name = migrateVAnchorSecrets)]
+	pub fn migrate_vanchor_secrets(&mut self, commitment: Uint8Array) -> Result<bool, OperationError> {
+		self.inner.migrate_vanchor_secrets(&commitment.to_vec())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -802,7 +1273,128 @@ impl JsNote {
 	}
 }
 
+#[cfg(feature = "js")]
+impl_js_serialization!(JsNote => Note);
+
 #[cfg(test)]
+mod native_test {
+	use super::*;
+
+	fn builder(protocol: NoteProtocol) -> NoteBuilder {
+		NoteBuilder {
+			protocol: Some(protocol),
+			version: Some(NoteVersion::V1),
+			source_chain_id: Some("2".to_string()),
+			target_chain_id: Some("3".to_string()),
+			source_identifying_data: Some("2".to_string()),
+			target_identifying_data: Some("3".to_string()),
+			amount: Some("10".to_string()),
+			denomination: Some(18),
+			token_symbol: Some("EDG".to_string()),
+			curve: Some(Curve::Bn254),
+			hash_function: Some(HashFunction::Poseidon),
+			exponentiation: Some(5),
+			index: Some(10),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn should_generate_and_deserialize_mixer_notes() {
+		let note = NoteBuilder {
+			width: Some(3),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap();
+		assert_eq!(note.secrets.len(), 2);
+
+		let deserialized = Note::deserialize(&note.to_string()).unwrap();
+		assert_eq!(deserialized.to_string(), note.to_string());
+		assert_eq!(
+			deserialized.get_leaf_commitment().unwrap(),
+			note.get_leaf_commitment().unwrap()
+		);
+	}
+
+	#[test]
+	fn should_generate_and_deserialize_vanchor_notes() {
+		let mut note = NoteBuilder {
+			width: Some(5),
+			..builder(NoteProtocol::VAnchor)
+		}
+		.build()
+		.unwrap();
+		assert_eq!(note.secrets.len(), 4);
+		note.validate(None).unwrap();
+
+		note.mutate_index(11).unwrap();
+		let deserialized = Note::deserialize_strict(&note.to_string()).unwrap();
+		assert_eq!(deserialized.index, Some(11));
+		assert_eq!(
+			deserialized.get_leaf_commitment().unwrap(),
+			note.get_leaf_commitment().unwrap()
+		);
+	}
+
+	#[test]
+	fn should_only_mutate_the_index_of_vanchor_notes() {
+		let mut note = NoteBuilder {
+			width: Some(3),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap();
+		assert_eq!(
+			note.mutate_index(1).unwrap_err().code,
+			OpStatusCode::InvalidNoteProtocol
+		);
+	}
+
+	#[test]
+	fn should_rebuild_notes_from_their_secrets() {
+		let note = NoteBuilder {
+			width: Some(3),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap();
+		let rebuilt = NoteBuilder {
+			width: Some(3),
+			secrets: Some(note.secrets.clone()),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap();
+		assert_eq!(Note::deserialize(&rebuilt.to_string()).unwrap(), note);
+
+		let error = NoteBuilder {
+			width: Some(3),
+			secrets: Some(Secret::new(vec![note.secrets[0].clone()])),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidNoteSecrets);
+	}
+
+	#[test]
+	fn should_reject_short_secrets_of_a_leaf() {
+		let mut note = NoteBuilder {
+			width: Some(3),
+			..builder(NoteProtocol::Mixer)
+		}
+		.build()
+		.unwrap();
+		note.secrets = Secret::new(vec![note.secrets[0].clone()]);
+		assert_eq!(
+			note.get_leaf_and_nullifier().unwrap_err().code,
+			OpStatusCode::InvalidNoteSecrets
+		);
+	}
+}
+
+#[cfg(all(test, feature = "js"))]
 mod test {
 	use ark_bn254;
 	use wasm_bindgen_test::*;
@@ -914,4 +1506,11 @@ mod test {
 		note.get_leaf_commitment().unwrap();
 		assert_eq!(note.serialize(), vanchor_note_str);
 	}
+
+	#[wasm_bindgen_test]
+	fn should_reject_secrets_that_arent_hex() {
+		let mut note_builder = JsNoteBuilder::new();
+		assert!(note_builder.set_secrets(JsString::from("0x00:zz")).is_err());
+		assert!(note_builder.set_secrets(JsString::from("0x00:01")).is_ok());
+	}
 }
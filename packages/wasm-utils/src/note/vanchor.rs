@@ -1,3 +1,4 @@
+use ark_bls12_381::Fr as Bls381Fr;
 use ark_bn254::Fr as Bn254Fr;
 use ark_ff::{BigInteger, PrimeField};
 use ark_std::UniformRand;
@@ -5,8 +6,157 @@ use arkworks_setups::{Curve as ArkCurve, VAnchorProver};
 use rand::rngs::OsRng;
 
 use crate::types::{Curve, OpStatusCode, OperationError};
-use crate::utxo::JsUtxo;
-use crate::VAnchorR1CSProverBn254_30_2_2_2;
+use crate::utxo::Utxo;
+use crate::{VAnchorR1CSProverBls381_30_2_2_2, VAnchorR1CSProverBn254_30_2_2_2};
+
+/// Last secret of a note holding a public UTXO
+const PUBLIC_UTXO_TAG: u8 = 1;
+
+/// Secrets of a VAnchor note
+///
+/// A UTXO owned by the note holder is encoded as `chain_id:amount:secret_key:blinding`,
+/// a public UTXO that only knows the public key of its owner as
+/// `chain_id:amount:public_key:blinding:01`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VAnchorNoteSecrets {
+	Owned {
+		chain_id: Vec<u8>,
+		amount: Vec<u8>,
+		secret_key: Vec<u8>,
+		blinding: Vec<u8>,
+	},
+	Public {
+		chain_id: Vec<u8>,
+		amount: Vec<u8>,
+		public_key: Vec<u8>,
+		blinding: Vec<u8>,
+	},
+}
+
+impl VAnchorNoteSecrets {
+	pub fn from_utxo(utxo: &Utxo) -> Self {
+		let chain_id = utxo.get_chain_id_bytes();
+		let amount = utxo.get_amount();
+		let blinding = utxo.get_blinding();
+		match utxo.get_secret_key() {
+			Some(secret_key) => VAnchorNoteSecrets::Owned {
+				chain_id,
+				amount,
+				secret_key,
+				blinding,
+			},
+			None => VAnchorNoteSecrets::Public {
+				chain_id,
+				amount,
+				public_key: utxo.get_public_key(),
+				blinding,
+			},
+		}
+	}
+
+	pub fn decode(secrets: &[Vec<u8>]) -> Result<Self, OperationError> {
+		let invalid =
+			|message: &str| OperationError::new_with_message(OpStatusCode::InvalidNoteSecrets, message.to_string());
+		let (chain_id, amount, key, blinding) = match secrets {
+			[chain_id, amount, key, blinding] | [chain_id, amount, key, blinding, _] => {
+				(chain_id.clone(), amount.clone(), key.clone(), blinding.clone())
+			}
+			_ => return Err(invalid("VAnchor secrets length should be 4 or 5 in length")),
+		};
+		if chain_id.len() < 8 {
+			return Err(invalid("VAnchor chain id should be at least 8 bytes"));
+		}
+		if amount.len() != 32 {
+			return Err(invalid("VAnchor amount should be 32 bytes"));
+		}
+
+		match secrets.get(4).map(Vec::as_slice) {
+			None => Ok(VAnchorNoteSecrets::Owned {
+				chain_id,
+				amount,
+				secret_key: key,
+				blinding,
+			}),
+			Some([PUBLIC_UTXO_TAG]) => Ok(VAnchorNoteSecrets::Public {
+				chain_id,
+				amount,
+				public_key: key,
+				blinding,
+			}),
+			Some(_) => Err(invalid("Unknown VAnchor UTXO kind")),
+		}
+	}
+
+	pub fn encode(&self) -> Vec<Vec<u8>> {
+		match self.clone() {
+			VAnchorNoteSecrets::Owned {
+				chain_id,
+				amount,
+				secret_key,
+				blinding,
+			} => vec![chain_id, amount, secret_key, blinding],
+			VAnchorNoteSecrets::Public {
+				chain_id,
+				amount,
+				public_key,
+				blinding,
+			} => vec![chain_id, amount, public_key, blinding, vec![PUBLIC_UTXO_TAG]],
+		}
+	}
+
+	pub fn chain_id(&self) -> u64 {
+		let chain_id = match self {
+			VAnchorNoteSecrets::Owned { chain_id, .. } | VAnchorNoteSecrets::Public { chain_id, .. } => chain_id,
+		};
+		let mut chain_id_slice = [0u8; 8];
+		chain_id_slice.copy_from_slice(&chain_id[chain_id.len() - 8..]);
+		u64::from_be_bytes(chain_id_slice)
+	}
+
+	pub fn amount(&self) -> u128 {
+		let amount = match self {
+			VAnchorNoteSecrets::Owned { amount, .. } | VAnchorNoteSecrets::Public { amount, .. } => amount,
+		};
+		let mut amount_slice = [0u8; 16];
+		amount_slice.copy_from_slice(&amount[16..32]);
+		u128::from_be_bytes(amount_slice)
+	}
+
+	pub fn to_utxo(
+		&self,
+		curve: Curve,
+		width: usize,
+		exponentiation: i8,
+		index: Option<u64>,
+	) -> Result<Utxo, OperationError> {
+		match self.clone() {
+			VAnchorNoteSecrets::Owned {
+				secret_key, blinding, ..
+			} => get_leaf_with_private_raw(
+				curve,
+				width,
+				exponentiation,
+				Some(secret_key),
+				Some(blinding),
+				self.chain_id(),
+				self.amount(),
+				index,
+			),
+			VAnchorNoteSecrets::Public {
+				public_key, blinding, ..
+			} => get_public_leaf_raw(
+				curve,
+				width,
+				exponentiation,
+				public_key,
+				Some(blinding),
+				self.chain_id(),
+				self.amount(),
+				index,
+			),
+		}
+	}
+}
 
 pub fn generate_secrets(
 	amount: u128,
@@ -16,11 +166,15 @@ pub fn generate_secrets(
 	chain_id: u64,
 	index: Option<u64>,
 	rng: &mut OsRng,
-) -> Result<JsUtxo, OperationError> {
-	let utxo: JsUtxo = match (curve, exponentiation, width) {
+) -> Result<Utxo, OperationError> {
+	let utxo: Utxo = match (curve, exponentiation, width) {
 		(Curve::Bn254, 5, 5) => {
 			VAnchorR1CSProverBn254_30_2_2_2::create_random_leaf(ArkCurve::Bn254, chain_id, amount, index, rng)
-				.map(JsUtxo::new_from_bn254_utxo)
+				.map(Utxo::new_from_bn254_utxo)
+		}
+		(Curve::Bls381, 5, 5) => {
+			VAnchorR1CSProverBls381_30_2_2_2::create_random_leaf(ArkCurve::Bls381, chain_id, amount, index, rng)
+				.map(Utxo::new_from_bls381_utxo)
 		}
 		_ => {
 			let message = format!(
@@ -44,8 +198,8 @@ pub fn get_leaf_with_private_raw(
 	chain_id: u64,
 	amount: u128,
 	index: Option<u64>,
-) -> Result<JsUtxo, OperationError> {
-	let utxo: JsUtxo = match (curve, exponentiation, width) {
+) -> Result<Utxo, OperationError> {
+	let utxo: Utxo = match (curve, exponentiation, width) {
 		(Curve::Bn254, 5, 5) => {
 			let private_key = private_key.unwrap_or_else(|| Bn254Fr::rand(&mut OsRng).into_repr().to_bytes_be());
 			let blinding = blinding.unwrap_or_else(|| Bn254Fr::rand(&mut OsRng).into_repr().to_bytes_be());
@@ -57,7 +211,20 @@ pub fn get_leaf_with_private_raw(
 				private_key.to_vec(),
 				blinding.to_vec(),
 			)
-			.map(JsUtxo::new_from_bn254_utxo)
+			.map(Utxo::new_from_bn254_utxo)
+		}
+		(Curve::Bls381, 5, 5) => {
+			let private_key = private_key.unwrap_or_else(|| Bls381Fr::rand(&mut OsRng).into_repr().to_bytes_be());
+			let blinding = blinding.unwrap_or_else(|| Bls381Fr::rand(&mut OsRng).into_repr().to_bytes_be());
+			VAnchorR1CSProverBls381_30_2_2_2::create_leaf_with_privates(
+				ArkCurve::Bls381,
+				chain_id,
+				amount,
+				index,
+				private_key,
+				blinding,
+			)
+			.map(Utxo::new_from_bls381_utxo)
 		}
 		_ => {
 			let message = format!(
@@ -74,3 +241,177 @@ pub fn get_leaf_with_private_raw(
 
 	Ok(utxo)
 }
+
+/// Leaf of a UTXO owned by `public_key`, the note holder can't spend it
+#[allow(clippy::too_many_arguments)]
+pub fn get_public_leaf_raw(
+	curve: Curve,
+	width: usize,
+	exponentiation: i8,
+	public_key: Vec<u8>,
+	blinding: Option<Vec<u8>>,
+	chain_id: u64,
+	amount: u128,
+	index: Option<u64>,
+) -> Result<Utxo, OperationError> {
+	let utxo: Utxo = match (curve, exponentiation, width) {
+		(Curve::Bn254, 5, 5) => {
+			let blinding = blinding.unwrap_or_else(|| Bn254Fr::rand(&mut OsRng).into_repr().to_bytes_be());
+			VAnchorR1CSProverBn254_30_2_2_2::create_public_utxo(
+				ArkCurve::Bn254,
+				chain_id,
+				amount,
+				blinding,
+				public_key,
+				index,
+			)
+			.map(Utxo::new_from_bn254_utxo)
+		}
+		(Curve::Bls381, 5, 5) => {
+			let blinding = blinding.unwrap_or_else(|| Bls381Fr::rand(&mut OsRng).into_repr().to_bytes_be());
+			VAnchorR1CSProverBls381_30_2_2_2::create_public_utxo(
+				ArkCurve::Bls381,
+				chain_id,
+				amount,
+				blinding,
+				public_key,
+				index,
+			)
+			.map(Utxo::new_from_bls381_utxo)
+		}
+		_ => {
+			let message = format!(
+				"No VAnchor leaf setup for curve {}, exponentiation {}, and width {}",
+				curve, exponentiation, width
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::FailedToGenerateTheLeaf,
+				message,
+			));
+		}
+	}
+	.map_err(|e| OperationError::new_with_message(OpStatusCode::FailedToGenerateTheLeaf, e.to_string()))?;
+
+	Ok(utxo)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::note::{Note, NoteBuilder};
+	use crate::serialization::Serializable;
+	use crate::types::{Backend, NoteProtocol, NoteVersion};
+
+	fn vanchor_note_builder(utxo: &Utxo) -> NoteBuilder {
+		NoteBuilder {
+			protocol: Some(NoteProtocol::VAnchor),
+			version: Some(NoteVersion::V1),
+			source_chain_id: Some("1".to_string()),
+			target_chain_id: Some(utxo.get_chain_id_raw().to_string()),
+			source_identifying_data: Some("1".to_string()),
+			target_identifying_data: Some("1".to_string()),
+			amount: Some(utxo.get_amount_value().to_string()),
+			curve: Some(Curve::Bn254),
+			exponentiation: Some(5),
+			width: Some(5),
+			blinding: Some(utxo.get_blinding().into()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn should_keep_the_private_key_and_blinding_of_the_builder() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, None).unwrap();
+		let mut builder = vanchor_note_builder(&utxo);
+		builder.private_key = utxo.get_secret_key().map(Into::into);
+		let note = builder.build().unwrap();
+
+		assert_eq!(note.secrets[2], utxo.get_secret_key().unwrap());
+		assert_eq!(note.secrets[3], utxo.get_blinding());
+		assert_eq!(note.get_leaf_commitment().unwrap(), utxo.get_commitment());
+	}
+
+	#[test]
+	fn should_keep_secrets_out_of_debug_output_and_redacted_views() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		let mut builder = vanchor_note_builder(&utxo);
+		builder.private_key = utxo.get_secret_key().map(Into::into);
+		let note = builder.build().unwrap();
+
+		let secret_key = utxo.get_secret_key().unwrap();
+		let blinding = utxo.get_blinding();
+		let redacted_json = note.redacted().to_json().unwrap() + &utxo.redacted().to_json().unwrap();
+		for output in [format!("{:?}", note), format!("{:?}", utxo), redacted_json] {
+			for secret in [&secret_key, &blinding] {
+				assert!(!output.contains(&hex::encode(secret)));
+				assert!(!output.contains(&format!("{:?}", secret)));
+			}
+		}
+
+		let redacted = note.redacted();
+		assert_eq!(redacted.commitment, Some(utxo.get_commitment()));
+		assert_eq!(redacted.amount, Some("10".to_string()));
+		assert_eq!(utxo.redacted().index, Some(3));
+		assert_eq!(utxo.redacted().commitment, utxo.get_commitment());
+	}
+
+	#[test]
+	fn should_build_notes_for_public_utxos() {
+		let owned = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, None).unwrap();
+		let mut builder = vanchor_note_builder(&owned);
+		builder.public_key = Some(owned.get_public_key());
+		let note = builder.build().unwrap();
+		assert_eq!(note.secrets.len(), 5);
+
+		let note: Note = note.to_string().parse().unwrap();
+		let utxo = note.get_utxo().unwrap();
+		assert!(utxo.get_secret_key().is_none());
+		assert_eq!(utxo.get_commitment(), owned.get_commitment());
+	}
+
+	#[test]
+	fn should_migrate_notes_with_swapped_secrets() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, None).unwrap();
+		let mut builder = vanchor_note_builder(&utxo);
+		builder.private_key = utxo.get_secret_key().map(Into::into);
+		let mut note = builder.build().unwrap();
+		// Order written by older builders
+		note.secrets.swap(2, 3);
+		assert_ne!(note.get_leaf_commitment().unwrap(), utxo.get_commitment());
+
+		assert!(note.migrate_vanchor_secrets(&utxo.get_commitment()).unwrap());
+		assert_eq!(note.get_leaf_commitment().unwrap(), utxo.get_commitment());
+		assert!(!note.migrate_vanchor_secrets(&utxo.get_commitment()).unwrap());
+		assert_eq!(
+			note.migrate_vanchor_secrets(&[0u8; 32]).unwrap_err().code,
+			OpStatusCode::InvalidNoteSecrets
+		);
+	}
+
+	#[test]
+	fn should_report_metadata_that_does_not_match_the_secrets() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, None).unwrap();
+		let mut builder = vanchor_note_builder(&utxo);
+		builder.private_key = utxo.get_secret_key().map(Into::into);
+		builder.index = Some(0);
+		let note = builder.build().unwrap();
+		note.validate(Some(&utxo.get_commitment())).unwrap();
+		Note::deserialize_strict(&note.to_string()).unwrap();
+
+		let mut tampered = note.clone();
+		tampered.amount = Some("11".to_string());
+		tampered.target_chain_id = "2".to_string();
+		tampered.index = None;
+		let mismatches = tampered.check_integrity(Some(&[0u8; 32])).unwrap();
+		let fields: Vec<_> = mismatches.iter().map(|mismatch| mismatch.field.as_str()).collect();
+		assert_eq!(fields, ["amount", "targetChainId", "index", "commitment"]);
+		assert_eq!(mismatches[0].note, "11");
+		assert_eq!(mismatches[0].expected, "10");
+
+		let error = Note::deserialize_strict(&tampered.to_string()).unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidNoteIntegrity);
+		assert!(error.data.unwrap().contains("targetChainId"));
+		// The lenient deserializer still accepts it
+		Note::deserialize(&tampered.to_string()).unwrap();
+	}
+}
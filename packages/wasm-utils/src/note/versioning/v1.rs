@@ -1,11 +1,15 @@
 use crate::note::*;
 use crate::types::{OpStatusCode, OperationError};
 
-pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
-	let scheme_and_parts: Vec<&str> = s.split("://").collect();
-	let scheme = scheme_and_parts[0];
+pub fn note_from_str(s: &str) -> Result<Note, OperationError> {
+	let (scheme, path) = s.split_once("://").ok_or_else(|| {
+		OperationError::new_with_message(
+			OpStatusCode::InvalidNoteLength,
+			"Note is missing its scheme".to_string(),
+		)
+	})?;
 
-	let parts: Vec<&str> = scheme_and_parts[1].split('/').collect();
+	let parts: Vec<&str> = path.split('/').collect();
 	if parts.len() < 5 {
 		return Err(OperationError::new_with_message(
 			OpStatusCode::InvalidNoteLength,
@@ -40,9 +44,7 @@ pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
 		));
 	}
 	let source_chain_id = chain_ids_parts[0];
-	let _: u64 = source_chain_id.parse().map_err(|_| OpStatusCode::InvalidSourceChain)?;
 	let target_chain_id = chain_ids_parts[1];
-	let _: u64 = target_chain_id.parse().map_err(|_| OpStatusCode::InvalidTargetChain)?;
 
 	// Chain Identifying Data parsing
 	let chain_identifying_data_parts: Vec<&str> = chain_identifying_data.split(':').collect();
@@ -63,6 +65,7 @@ pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
 	let mut curve = None;
 	let mut width = None;
 	let mut exponentiation = None;
+	let mut height = None;
 	let mut hash_function = None;
 	let mut backend = None;
 	let mut token_symbol = None;
@@ -80,11 +83,11 @@ pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
 		}
 		let key = part_parts[0];
 		let value = part_parts[1];
-		println!("{}={}", key, value);
 		match key {
 			"curve" => curve = Some(value),
 			"width" => width = Some(value),
 			"exp" => exponentiation = Some(value),
+			"height" => height = Some(value),
 			"hf" => hash_function = Some(value),
 			"backend" => backend = Some(value),
 			"token" => token_symbol = Some(value),
@@ -100,12 +103,32 @@ pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
 		}
 	}
 
+	let curve = curve.map(Curve::from_str).transpose()?;
+	let hash_function = hash_function.map(HashFunction::from_str).transpose()?;
+	let backend = backend.map(Backend::from_str).transpose()?;
+	let exponentiation = exponentiation
+		.map(|v| v.parse::<i8>().map_err(|_| OpStatusCode::InvalidExponentiation))
+		.transpose()?;
+	let width = width
+		.map(|v| v.parse::<usize>().map_err(|_| OpStatusCode::InvalidWidth))
+		.transpose()?;
+	let height = height
+		.map(|v| v.parse::<usize>().map_err(|_| OpStatusCode::InvalidTreeHeight))
+		.transpose()?;
+	let index = index
+		.map(|v| v.parse::<u64>().map_err(|_| OpStatusCode::InvalidLeafIndex))
+		.transpose()?;
+
+	let denomination = denomination
+		.map(|v| v.parse::<u8>().map_err(|_| OpStatusCode::InvalidDenomination))
+		.transpose()?;
+
 	let secret_parts: Vec<Vec<u8>> = secrets
 		.split(':')
-		.map(|v| hex::decode(v).unwrap_or_default())
-		.collect::<Vec<Vec<u8>>>();
+		.map(|v| hex::decode(v).map_err(|_| OpStatusCode::InvalidNoteSecrets))
+		.collect::<Result<_, _>>()?;
 
-	Ok(JsNote {
+	let note = Note {
 		scheme: scheme.to_string(),
 		protocol,
 		version,
@@ -114,14 +137,118 @@ pub fn note_from_str(s: &str) -> Result<JsNote, OperationError> {
 		source_identifying_data: source_identifying_data.to_string(),
 		target_identifying_data: target_identifying_data.to_string(),
 		token_symbol: token_symbol.map(|v| v.to_string()),
-		curve: curve.map(|v| v.parse::<Curve>().unwrap()),
-		hash_function: hash_function.map(|v| HashFunction::from_str(v).unwrap()),
-		backend: backend.map(|b| b.parse().unwrap()),
-		denomination: denomination.map(|v| v.parse::<u8>().unwrap()),
-		amount: amount.map(|v| v.parse::<String>().unwrap()),
-		exponentiation: exponentiation.map(|v| v.parse::<i8>().unwrap()),
-		width: width.map(|v| v.parse::<usize>().unwrap()),
-		secrets: secret_parts,
-		index: index.map(|v| v.parse().unwrap()),
-	})
+		curve,
+		hash_function,
+		backend,
+		denomination,
+		amount: amount.map(|v| v.to_string()),
+		exponentiation,
+		width,
+		height,
+		secrets: secret_parts.into(),
+		index,
+	};
+	note.check_fields()?;
+	Ok(note)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_parse_and_serialize_notes_natively() {
+		let mixer_note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+		let note = note_from_str(mixer_note).unwrap();
+		assert_eq!(note.protocol, NoteProtocol::Mixer);
+		assert_eq!(note.secrets.len(), 2);
+		note.get_leaf_commitment().unwrap();
+		assert_eq!(note.to_string(), mixer_note);
+	}
+
+	#[test]
+	fn should_parse_the_tree_height() {
+		let mixer_note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&height=16&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+		let note = note_from_str(mixer_note).unwrap();
+		assert_eq!(note.height, Some(16));
+		assert_eq!(note.to_string(), mixer_note);
+
+		let unsupported = mixer_note.replace("height=16", "height=17");
+		assert_eq!(
+			note_from_str(&unsupported).unwrap_err().code,
+			OpStatusCode::InvalidTreeHeight
+		);
+	}
+
+	#[test]
+	fn should_encode_notes_as_json() {
+		use crate::serialization::Serializable;
+
+		let mixer_note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+		let note = note_from_str(mixer_note).unwrap();
+		let json = note.to_json().unwrap();
+		assert!(json.contains("\"protocol\":\"mixer\""));
+		assert!(json.contains("\"tokenSymbol\":\"EDG\""));
+
+		let decoded = Note::from_json(&json).unwrap();
+		assert_eq!(decoded.to_string(), mixer_note);
+
+		#[cfg(feature = "cbor")]
+		{
+			let decoded = Note::from_cbor(&note.to_cbor().unwrap()).unwrap();
+			assert_eq!(decoded.to_string(), mixer_note);
+		}
+	}
+
+	#[test]
+	fn should_check_notes_decoded_from_json() {
+		use crate::serialization::Serializable;
+
+		let mixer_note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+		let json = note_from_str(mixer_note).unwrap().to_json().unwrap();
+
+		let one_secret = json.replace(
+			",\"18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c\"",
+			"",
+		);
+		assert_ne!(one_secret, json);
+		assert_eq!(
+			Note::from_json(&one_secret).unwrap_err().code,
+			OpStatusCode::DeserializationFailed
+		);
+		let bad_chain = json.replace("\"sourceChainId\":\"2\"", "\"sourceChainId\":\"two\"");
+		assert_eq!(
+			Note::from_json(&bad_chain).unwrap_err().code,
+			OpStatusCode::DeserializationFailed
+		);
+	}
+
+	#[test]
+	fn should_reject_malformed_notes() {
+		let mixer_note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10";
+		let cases = [
+			(mixer_note.replace("webb://", ""), OpStatusCode::InvalidNoteLength),
+			(
+				mixer_note.replace("curve=Bn254", "curve=Bn255"),
+				OpStatusCode::InvalidCurve,
+			),
+			(
+				mixer_note.replace("exp=5", "exp=five"),
+				OpStatusCode::InvalidExponentiation,
+			),
+			(mixer_note.replace("width=3", "width=-3"), OpStatusCode::InvalidWidth),
+			(
+				mixer_note.replace("index=10", "index=x"),
+				OpStatusCode::InvalidLeafIndex,
+			),
+			(mixer_note.replace("fd717c", "zz717c"), OpStatusCode::InvalidNoteSecrets),
+			(
+				mixer_note.replace(":18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c", ""),
+				OpStatusCode::InvalidNoteSecrets,
+			),
+		];
+		for (note, code) in cases {
+			assert_eq!(note_from_str(&note).unwrap_err().code, code, "{}", note);
+		}
+	}
 }
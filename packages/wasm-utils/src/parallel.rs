@@ -0,0 +1,44 @@
+//! Thread pool the provers run on with the `parallel` feature
+//!
+//! arkworks parallelizes the MSMs and FFTs of proving with the rayon global
+//! pool. Natively rayon starts it on first use with a thread per core, in the
+//! browser it has to be started on web workers with `initThreadPool(n)` before
+//! the first proof.
+
+/// Start the global pool with `numThreads` web workers, returns a promise
+/// that resolves once they are all running
+#[cfg(all(feature = "js", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::{OpStatusCode, OperationError};
+
+/// Start the global pool with `num_threads` threads instead of one per core
+///
+/// It can only be started once, before the first proof, and fails with
+/// `InvalidThreadPool` afterwards.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init_thread_pool(num_threads: usize) -> Result<(), OperationError> {
+	rayon::ThreadPoolBuilder::new()
+		.num_threads(num_threads)
+		.build_global()
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidThreadPool, e.to_string()))
+}
+
+/// Threads of the global pool, the pool is started if it isn't yet
+pub fn current_num_threads() -> usize {
+	rayon::current_num_threads()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_start_the_thread_pool_once() {
+		// Another test may already have started the pool by proving
+		let _ = init_thread_pool(2);
+		assert_eq!(init_thread_pool(2).unwrap_err().code, OpStatusCode::InvalidThreadPool);
+		assert!(current_num_threads() >= 1);
+	}
+}
@@ -0,0 +1,449 @@
+use core::convert::TryInto;
+
+use crate::proof::proving_key::{prove, ProvingKey};
+use crate::proof::truncate_and_pad;
+use crate::secret::Secret;
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, hex_bytes_list, Serializable};
+use crate::types::{Backend, Curve, OpStatusCode, OperationError};
+use crate::{check_tree_height, with_tree_height, ANCHOR_COUNT, DEFAULT_LEAF, TREE_HEIGHT};
+use ark_bn254::Bn254;
+use ark_ec::PairingEngine;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::ProvingKey as Groth16ProvingKey;
+use arkworks_native_gadgets::poseidon::Poseidon;
+use arkworks_setups::common::{setup_params, setup_tree_and_create_path};
+use arkworks_setups::r1cs::anchor::AnchorR1CSProver;
+use arkworks_setups::{AnchorProver, Curve as ArkCurve};
+#[cfg(feature = "js")]
+use js_sys::{Array, JsString, Uint8Array};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+#[allow(clippy::unused_unit)]
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorProof {
+	#[serde(with = "hex_bytes")]
+	pub(crate) proof: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) nullifier_hash: Vec<u8>,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) roots: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) public_inputs: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) leaf: Vec<u8>,
+}
+
+impl Serializable for AnchorProof {}
+
+impl AnchorProof {
+	pub fn get_proof(&self) -> &[u8] {
+		&self.proof
+	}
+
+	pub fn get_nullifier_hash(&self) -> &[u8] {
+		&self.nullifier_hash
+	}
+
+	pub fn get_roots(&self) -> &[Vec<u8>] {
+		&self.roots
+	}
+
+	pub fn get_public_inputs(&self) -> &[Vec<u8>] {
+		&self.public_inputs
+	}
+
+	pub fn get_leaf(&self) -> &[u8] {
+		&self.leaf
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl AnchorProof {
+	#[wasm_bindgen(getter)]
+	pub fn proof(&self) -> JsString {
+		let proof_bytes = hex::encode(&self.proof);
+		proof_bytes.into()
+	}
+
+	#[wasm_bindgen(js_name = nullifierHash)]
+	#[wasm_bindgen(getter)]
+	pub fn nullifier_hash(&self) -> JsString {
+		let nullifier_bytes = hex::encode(&self.nullifier_hash);
+		nullifier_bytes.into()
+	}
+
+	#[wasm_bindgen(getter)]
+	pub fn roots(&self) -> Array {
+		let roots: Array = self.roots.iter().map(|x| JsString::from(hex::encode(x))).collect();
+		roots
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = publicInputs)]
+	pub fn public_inputs_raw(&self) -> Array {
+		let inputs: Array = self
+			.public_inputs
+			.iter()
+			.map(|x| JsString::from(hex::encode(x)))
+			.collect();
+		inputs
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = leaf)]
+	pub fn leaf(&self) -> Uint8Array {
+		let leaf = Uint8Array::from(self.leaf.as_slice());
+		leaf
+	}
+}
+
+#[cfg(feature = "js")]
+impl_js_serialization!(AnchorProof);
+
+#[derive(Debug, Clone)]
+pub struct AnchorProofPayload {
+	pub exponentiation: i8,
+	pub width: usize,
+	pub height: usize,
+	pub curve: Curve,
+	pub backend: Backend,
+	pub secret: Secret<Vec<u8>>,
+	pub nullifier: Secret<Vec<u8>>,
+	pub recipient: Vec<u8>,
+	pub relayer: Vec<u8>,
+	/// `None` when built with `build_without_pk`, until `create_proofs` sets
+	/// the key of the batch
+	pub pk: Option<ProvingKey>,
+	pub refund: u128,
+	pub fee: u128,
+	pub chain_id: u64,
+	pub leaves: Vec<Vec<u8>>,
+	pub leaf_index: u64,
+	/// One root per linked anchor, the first one is the root of the local tree
+	pub roots: Vec<Vec<u8>>,
+	/// Commitment inserted in place of the spent one, zeros for a withdrawal
+	pub refresh_commitment: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AnchorProofInput {
+	pub exponentiation: Option<i8>,
+	pub width: Option<usize>,
+	pub height: Option<usize>,
+	pub curve: Option<Curve>,
+	pub backend: Option<Backend>,
+	pub secret: Option<Secret<Vec<u8>>>,
+	pub nullifier: Option<Secret<Vec<u8>>>,
+	pub recipient: Option<Vec<u8>>,
+	pub relayer: Option<Vec<u8>>,
+	pub pk: Option<ProvingKey>,
+	pub refund: Option<u128>,
+	pub fee: Option<u128>,
+	pub chain_id: Option<u128>,
+	pub leaves: Option<Vec<Vec<u8>>>,
+	pub leaf_index: Option<u64>,
+	pub roots: Option<Vec<Vec<u8>>>,
+	pub refresh_commitment: Option<Vec<u8>>,
+}
+
+impl AnchorProofInput {
+	pub fn build(self) -> Result<AnchorProofPayload, OperationError> {
+		let payload = self.build_without_pk()?;
+		if payload.pk.is_none() {
+			return Err(OpStatusCode::InvalidProvingKey.into());
+		}
+		Ok(payload)
+	}
+
+	/// `build` without requiring the proving key, for inputs proved by
+	/// `create_proofs` with the key of the batch
+	pub fn build_without_pk(self) -> Result<AnchorProofPayload, OperationError> {
+		let recipient = self.recipient.ok_or(OpStatusCode::InvalidRecipient)?;
+		let relayer = self.relayer.ok_or(OpStatusCode::InvalidRelayer)?;
+		let leaf_index = self.leaf_index.ok_or(OpStatusCode::InvalidLeafIndex)?;
+		let secret = self.secret.ok_or(OpStatusCode::InvalidNoteSecrets)?;
+		let nullifier = self.nullifier.ok_or(OpStatusCode::InvalidNoteSecrets)?;
+		let leaves = self.leaves.ok_or(OpStatusCode::InvalidLeaves)?;
+		let fee = self.fee.ok_or(OpStatusCode::InvalidFee)?;
+		let refund = self.refund.ok_or(OpStatusCode::InvalidRefund)?;
+		let roots = self.roots.ok_or(OpStatusCode::RootsNotSet)?;
+		let refresh_commitment = self.refresh_commitment.ok_or(OpStatusCode::CommitmentNotSet)?;
+		let chain_id: u64 = self
+			.chain_id
+			.ok_or(OpStatusCode::InvalidChainId)?
+			.try_into()
+			.map_err(|_| OpStatusCode::InvalidChainId)?;
+
+		let exponentiation = self.exponentiation.unwrap_or(5);
+		let width = self.width.unwrap_or(4);
+		let height = check_tree_height(self.height.unwrap_or(TREE_HEIGHT))?;
+		let curve = self.curve.unwrap_or(Curve::Bn254);
+		let backend = self.backend.unwrap_or(Backend::Arkworks);
+		if let Some(pk) = &self.pk {
+			pk.check_curve(curve)?;
+		}
+
+		if roots.len() != ANCHOR_COUNT {
+			let message = format!(
+				"Anchor proof needs {} roots, one per linked anchor, while {} were supplied",
+				ANCHOR_COUNT,
+				roots.len()
+			);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidRoots, message));
+		}
+		if refresh_commitment.len() != 32 {
+			let message = format!(
+				"The refresh commitment should be 32 bytes, got {}",
+				refresh_commitment.len()
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::CommitmentNotSet,
+				message,
+			));
+		}
+
+		let processed_relayer = truncate_and_pad(&relayer);
+		let processed_recipient = truncate_and_pad(&recipient);
+
+		Ok(AnchorProofPayload {
+			exponentiation,
+			width,
+			height,
+			curve,
+			backend,
+			secret,
+			nullifier,
+			recipient: processed_recipient,
+			relayer: processed_relayer,
+			pk: self.pk,
+			refund,
+			fee,
+			chain_id,
+			leaves,
+			leaf_index,
+			roots,
+			refresh_commitment,
+		})
+	}
+}
+
+pub fn create_proof(anchor_proof_input: AnchorProofPayload, rng: &mut OsRng) -> Result<AnchorProof, OperationError> {
+	let payload = &anchor_proof_input;
+	let pk = payload.pk.as_ref().ok_or(OpStatusCode::InvalidProvingKey)?;
+	match (payload.backend, payload.curve, payload.exponentiation, payload.width) {
+		(Backend::Arkworks, Curve::Bn254, 5, 4) => with_tree_height!(payload.height, |H| {
+			prove_anchor::<Bn254, H>(ArkCurve::Bn254, pk.bn254()?, payload, rng)
+		}),
+		_ => return Err(OpStatusCode::UnsupportedParameterCombination.into()),
+	}
+	.ok_or(OpStatusCode::InvalidTreeHeight)?
+}
+
+fn proof_error(e: impl ToString) -> OperationError {
+	let mut error: OperationError = OpStatusCode::InvalidProofParameters.into();
+	error.data = Some(e.to_string());
+	error
+}
+
+/// Prove with the parsed key `pk` the spend of the leaf of the payload
+/// secrets on its chain, against the roots of the linked anchors
+fn prove_anchor<E: PairingEngine, const H: usize>(
+	curve: ArkCurve,
+	pk: &Groth16ProvingKey<E>,
+	payload: &AnchorProofPayload,
+	rng: &mut OsRng,
+) -> Result<AnchorProof, OperationError> {
+	let roots: [E::Fr; ANCHOR_COUNT] = payload
+		.roots
+		.iter()
+		.map(|root| E::Fr::from_be_bytes_mod_order(root))
+		.collect::<Vec<_>>()
+		.try_into()
+		.map_err(|_| OpStatusCode::InvalidRoots)?;
+	let tree_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 3));
+	let leaves: Vec<E::Fr> = payload
+		.leaves
+		.iter()
+		.map(|leaf| E::Fr::from_be_bytes_mod_order(leaf))
+		.collect();
+	let (_, path) = setup_tree_and_create_path::<E::Fr, Poseidon<E::Fr>, H>(
+		&tree_hasher,
+		&leaves,
+		payload.leaf_index,
+		&DEFAULT_LEAF,
+	)
+	.map_err(proof_error)?;
+
+	let leaf = AnchorR1CSProver::<E, H, ANCHOR_COUNT>::create_leaf_with_privates(
+		curve,
+		payload.chain_id,
+		payload.secret.to_vec(),
+		payload.nullifier.to_vec(),
+	)
+	.map_err(proof_error)?;
+	let chain_id = E::Fr::from(payload.chain_id);
+	let nullifier_hash = E::Fr::from_be_bytes_mod_order(&leaf.nullifier_hash_bytes);
+	let arbitrary_input = AnchorR1CSProver::<E, H, ANCHOR_COUNT>::setup_arbitrary_data(
+		E::Fr::from_be_bytes_mod_order(&payload.recipient),
+		E::Fr::from_be_bytes_mod_order(&payload.relayer),
+		E::Fr::from(payload.fee),
+		E::Fr::from(payload.refund),
+		E::Fr::from_be_bytes_mod_order(&payload.refresh_commitment),
+	);
+	let circuit = AnchorR1CSProver::<E, H, ANCHOR_COUNT>::create_circuit(
+		curve,
+		chain_id,
+		arbitrary_input,
+		E::Fr::from_be_bytes_mod_order(&payload.secret),
+		E::Fr::from_be_bytes_mod_order(&payload.nullifier),
+		path,
+		roots,
+		nullifier_hash,
+	);
+	let public_inputs = AnchorR1CSProver::<E, H, ANCHOR_COUNT>::construct_public_inputs(
+		chain_id,
+		nullifier_hash,
+		roots,
+		arbitrary_input,
+	);
+
+	Ok(AnchorProof {
+		proof: prove(circuit, pk, rng)?,
+		nullifier_hash: leaf.nullifier_hash_bytes,
+		roots: roots.iter().map(|root| root.into_repr().to_bytes_be()).collect(),
+		public_inputs: public_inputs
+			.iter()
+			.map(|input| input.into_repr().to_bytes_be())
+			.collect(),
+		leaf: leaf.leaf_bytes,
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use ark_bn254::Fr as Bn254Fr;
+	use ark_ff::{BigInteger, PrimeField};
+	use rand::rngs::OsRng;
+
+	use crate::note::{Note, NoteBuilder};
+	use crate::proof::anchor::AnchorProof;
+	use crate::proof::{create_proof, setup_keys, verify, MTBn254X5, ProofInputBuilder, ProofOutput, ProvingKey};
+	use crate::serialization::Serializable;
+	use crate::types::{Curve, NoteProtocol, NoteVersion, OpStatusCode};
+
+	fn anchor_note(chain_id: u64) -> Note {
+		let mut builder = NoteBuilder::new();
+		builder.protocol = Some(NoteProtocol::Anchor);
+		builder.version = Some(NoteVersion::V1);
+		builder.source_chain_id = Some("1".to_string());
+		builder.target_chain_id = Some(chain_id.to_string());
+		builder.source_identifying_data = Some("1".to_string());
+		builder.target_identifying_data = Some("1".to_string());
+		builder.curve = Some(Curve::Bn254);
+		builder.exponentiation = Some(5);
+		builder.width = Some(4);
+		builder.build().unwrap()
+	}
+
+	fn anchor_builder(note: &Note, roots: Vec<Vec<u8>>) -> ProofInputBuilder {
+		let leaf = note.get_leaf_and_nullifier().unwrap().anchor_leaf().unwrap();
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Anchor);
+		builder.chain_id(note.target_chain_id.parse().unwrap()).unwrap();
+		builder.leaves_list(vec![leaf.leaf_bytes.clone()]).unwrap();
+		builder.leaf_index(0).unwrap();
+		builder.fee(5).unwrap();
+		builder.refund(1).unwrap();
+		builder.relayer([1u8; 32].to_vec()).unwrap();
+		builder.recipient([2u8; 32].to_vec()).unwrap();
+		builder.roots(roots).unwrap();
+		builder.refresh_commitment([0u8; 32].to_vec()).unwrap();
+		builder.secrets(leaf).unwrap();
+		builder
+	}
+
+	#[test]
+	fn should_bind_the_anchor_leaf_to_the_chain_id() {
+		let note = anchor_note(3);
+		let serialized = note.to_string();
+		assert!(serialized.starts_with("webb://v1:anchor/1:3/"));
+		let decoded: Note = serialized.parse().unwrap();
+		assert_eq!(
+			decoded.get_leaf_commitment().unwrap(),
+			note.get_leaf_commitment().unwrap()
+		);
+
+		let mut other_chain = note.clone();
+		other_chain.target_chain_id = "4".to_string();
+		assert_ne!(
+			other_chain.get_leaf_commitment().unwrap(),
+			note.get_leaf_commitment().unwrap()
+		);
+	}
+
+	#[test]
+	fn should_require_roots_and_refresh_commitment() {
+		let note = anchor_note(3);
+		let leaf = note.get_leaf_and_nullifier().unwrap().anchor_leaf().unwrap();
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Anchor);
+		builder.chain_id(3).unwrap();
+		builder.leaves_list(vec![leaf.leaf_bytes.clone()]).unwrap();
+		builder.leaf_index(0).unwrap();
+		builder.fee(0).unwrap();
+		builder.refund(0).unwrap();
+		builder.relayer([1u8; 32].to_vec()).unwrap();
+		builder.recipient([2u8; 32].to_vec()).unwrap();
+		builder.secrets(leaf).unwrap();
+		assert_eq!(
+			builder.clone().build_without_pk().unwrap_err().code,
+			OpStatusCode::RootsNotSet
+		);
+
+		builder.roots(vec![vec![0u8; 32]]).unwrap();
+		assert_eq!(
+			builder.clone().build_without_pk().unwrap_err().code,
+			OpStatusCode::CommitmentNotSet
+		);
+
+		builder.refresh_commitment([0u8; 32].to_vec()).unwrap();
+		assert_eq!(
+			builder.clone().build_without_pk().unwrap_err().code,
+			OpStatusCode::InvalidRoots
+		);
+
+		builder.roots(vec![vec![0u8; 32]; 2]).unwrap();
+		builder.clone().build_without_pk().unwrap();
+		assert_eq!(builder.build().unwrap_err().code, OpStatusCode::InvalidProvingKey);
+	}
+
+	#[test]
+	fn should_create_and_verify_anchor_proof_natively() {
+		let note = anchor_note(3);
+		let tree = MTBn254X5::from_leaves(&[note.get_leaf_commitment().unwrap()], 0).unwrap();
+		// The root of the linked anchor, any known root of the other chain
+		let linked_root = Bn254Fr::from(7u64).into_repr().to_bytes_be();
+		let (pk, vk) = setup_keys(NoteProtocol::Anchor, Curve::Bn254, 2, 2, 2).unwrap();
+
+		let mut builder = anchor_builder(&note, vec![tree.get_root(), linked_root]);
+		builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+
+		let proof = match create_proof(builder.build().unwrap(), &mut OsRng).unwrap() {
+			ProofOutput::Anchor(proof) => proof,
+			_ => panic!("expected an anchor proof"),
+		};
+		assert_eq!(proof.get_roots()[0], tree.get_root());
+		let is_valid = verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap();
+		assert!(is_valid);
+
+		let decoded = AnchorProof::from_json(&proof.to_json().unwrap()).unwrap();
+		assert_eq!(decoded.get_roots(), proof.get_roots());
+		assert_eq!(decoded.get_nullifier_hash(), proof.get_nullifier_hash());
+	}
+}
@@ -0,0 +1,187 @@
+//! Proving many inputs of one circuit with a single proving key
+#[cfg(feature = "js")]
+use js_sys::Array;
+use rand::rngs::OsRng;
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::proof::{create_proof, ProofInput, ProofOutput, ProvingKey};
+#[cfg(feature = "js")]
+use crate::proof::{js_proof_input_of_jsval, JsProofOutput, JsProvingKey};
+use crate::types::{Curve, NoteProtocol, OpStatusCode, OperationError};
+
+/// The circuit an input is proved with, inputs sharing a proving key share it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitConfig {
+	pub protocol: NoteProtocol,
+	pub curve: Curve,
+	pub height: usize,
+	pub anchor_count: usize,
+	pub ins: usize,
+	pub outs: usize,
+}
+
+impl CircuitConfig {
+	pub fn of(input: &ProofInput) -> Self {
+		match input {
+			ProofInput::Mixer(input) => CircuitConfig {
+				protocol: NoteProtocol::Mixer,
+				curve: input.curve,
+				height: input.height,
+				anchor_count: 1,
+				ins: 1,
+				outs: 0,
+			},
+			ProofInput::Anchor(input) => CircuitConfig {
+				protocol: NoteProtocol::Anchor,
+				curve: input.curve,
+				height: input.height,
+				anchor_count: input.roots.len(),
+				ins: 1,
+				outs: 0,
+			},
+			ProofInput::VAnchor(input) => CircuitConfig {
+				protocol: NoteProtocol::VAnchor,
+				curve: input.curve,
+				height: input.height,
+				anchor_count: input.roots.len(),
+				ins: input.input_utxos.len(),
+				outs: input.output_utxos.len(),
+			},
+		}
+	}
+}
+
+/// Prove `inputs` in order with the proving key `pk`
+///
+/// The inputs are built with `build_without_pk`. They must all be for the
+/// same circuit and the curve of `pk`, which is checked before any proof is
+/// made so a batch fails fast rather than after proving part of it. Every
+/// input shares the parsed key, nothing is deserialized per proof.
+pub fn create_proofs(
+	pk: &ProvingKey,
+	inputs: Vec<ProofInput>,
+	rng: &mut OsRng,
+) -> Result<Vec<ProofOutput>, OperationError> {
+	let circuit = match inputs.first() {
+		Some(input) => CircuitConfig::of(input),
+		None => return Ok(Vec::new()),
+	};
+	if let Some(index) = inputs.iter().position(|input| CircuitConfig::of(input) != circuit) {
+		let message = format!("Input {} isn't for the circuit of the first input {:?}", index, circuit);
+		return Err(OperationError::new_with_message(
+			OpStatusCode::UnsupportedParameterCombination,
+			message,
+		));
+	}
+	pk.check_curve(circuit.curve)?;
+
+	inputs
+		.into_iter()
+		.enumerate()
+		.map(|(index, mut input)| {
+			input.set_pk(pk.clone());
+			create_proof(input, rng).map_err(|mut error| {
+				error.error_message = format!("Input {}: {}", index, error.error_message);
+				error
+			})
+		})
+		.collect()
+}
+
+/// Prove an array of `JsProofInput` with one proving key, the inputs are
+/// consumed
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = generateProofs)]
+pub fn generate_proofs_js(pk: &JsProvingKey, inputs: Array) -> Result<Array, JsValue> {
+	let inputs = inputs
+		.iter()
+		.map(|v| {
+			js_proof_input_of_jsval(v)
+				.map(|input| input.inner)
+				.ok_or(OpStatusCode::InvalidProofParameters)
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	let outputs = create_proofs(&pk.inner, inputs, &mut OsRng)?;
+	Ok(outputs
+		.into_iter()
+		.map(|inner| JsValue::from(JsProofOutput { inner }))
+		.collect())
+}
+
+#[cfg(test)]
+mod test {
+	use ark_bls12_381::Bls12_381;
+	use arkworks_setups::common::setup_keys_unchecked;
+	use arkworks_setups::r1cs::mixer::MixerR1CSProver;
+	use arkworks_setups::Curve as ArkCurve;
+
+	use super::*;
+	use crate::note::Note;
+	use crate::proof::{setup_keys_with_height, verify, ProofInputBuilder};
+	use crate::DEFAULT_LEAF;
+
+	fn mixer_input(note: &Note, leaves: Vec<Vec<u8>>, leaf_index: u64, height: usize) -> ProofInput {
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+		builder.height(height).unwrap();
+		builder.leaves_list(leaves).unwrap();
+		builder.leaf_index(leaf_index).unwrap();
+		builder.fee(0).unwrap();
+		builder.refund(0).unwrap();
+		builder.relayer([1u8; 32].to_vec()).unwrap();
+		builder.recipient([2u8; 32].to_vec()).unwrap();
+		builder
+			.secrets(note.get_leaf_and_nullifier().unwrap().mixer_leaf().unwrap())
+			.unwrap();
+		builder.build_without_pk().unwrap()
+	}
+
+	#[test]
+	fn should_prove_a_batch_with_one_key() {
+		let note: Note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10"
+			.parse()
+			.unwrap();
+		let leaf = note.get_leaf_commitment().unwrap();
+		let (pk, vk) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let pk = ProvingKey::new(Curve::Bn254, &pk).unwrap();
+
+		let inputs = vec![
+			mixer_input(&note, vec![leaf.clone()], 0, 16),
+			mixer_input(&note, vec![[0u8; 32].to_vec(), leaf.clone()], 1, 16),
+		];
+		// Without the key of the batch an input can't be proved
+		assert_eq!(
+			create_proof(inputs[0].clone(), &mut OsRng).unwrap_err().code,
+			OpStatusCode::InvalidProvingKey
+		);
+
+		// The key of the mixer circuit on another curve
+		let (c, ..) =
+			MixerR1CSProver::<Bls12_381, 16>::setup_random_circuit(ArkCurve::Bls381, DEFAULT_LEAF, &mut OsRng).unwrap();
+		let (bls381_pk, _) = setup_keys_unchecked::<Bls12_381, _, _>(c, &mut OsRng).unwrap();
+		let bls381_pk = ProvingKey::new(Curve::Bls381, &bls381_pk).unwrap();
+		assert_eq!(
+			create_proofs(&bls381_pk, inputs.clone(), &mut OsRng).unwrap_err().code,
+			OpStatusCode::InvalidProvingKey
+		);
+
+		let outputs = create_proofs(&pk, inputs, &mut OsRng).unwrap();
+		assert_eq!(outputs.len(), 2);
+		for output in outputs {
+			let proof = match output {
+				ProofOutput::Mixer(proof) => proof,
+				_ => panic!("expected a mixer proof"),
+			};
+			assert!(verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap());
+		}
+
+		let mixed = vec![
+			mixer_input(&note, vec![leaf.clone()], 0, 16),
+			mixer_input(&note, vec![leaf], 0, 20),
+		];
+		assert_eq!(
+			create_proofs(&pk, mixed, &mut OsRng).unwrap_err().code,
+			OpStatusCode::UnsupportedParameterCombination
+		);
+	}
+}
@@ -1,59 +1,61 @@
 use ark_bn254::Fr as Bn254Fr;
 use ark_ff::{BigInteger, PrimeField};
 use ethabi::{encode, Token};
+#[cfg(feature = "js")]
 use js_sys::{JsString, Uint8Array};
 use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 use tiny_keccak::{Hasher, Keccak};
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "js")]
 use wasm_bindgen::JsValue;
 
-#[derive(Encode, Decode, Default)]
-#[wasm_bindgen]
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, string, Serializable};
+
+#[derive(Debug, Clone, Encode, Decode, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "js", wasm_bindgen)]
 pub struct ExtData {
-	#[wasm_bindgen(skip)]
-	pub recipient: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub relayer: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub ext_amount: i128,
-	#[wasm_bindgen(skip)]
-	pub fee: u128,
-	#[wasm_bindgen(skip)]
-	pub refund: u128,
-	#[wasm_bindgen(skip)]
-	pub token: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub encrypted_output1: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub encrypted_output2: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) recipient: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) relayer: Vec<u8>,
+	#[serde(with = "string")]
+	pub(crate) ext_amount: i128,
+	#[serde(with = "string")]
+	pub(crate) fee: u128,
+	#[serde(with = "string")]
+	pub(crate) refund: u128,
+	#[serde(with = "hex_bytes")]
+	pub(crate) token: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) encrypted_output1: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) encrypted_output2: Vec<u8>,
 }
-#[wasm_bindgen]
+
+impl Serializable for ExtData {}
+
 impl ExtData {
-	#[wasm_bindgen(constructor)]
 	#[allow(clippy::too_many_arguments)]
 	pub fn new(
-		recipient: Uint8Array,
-		relayer: Uint8Array,
-		ext_amount: JsString,
-		fee: JsString,
-		refund: JsString,
-		token: Uint8Array,
-		encrypted_output1: Uint8Array,
-		encrypted_output2: Uint8Array,
+		recipient: Vec<u8>,
+		relayer: Vec<u8>,
+		ext_amount: i128,
+		fee: u128,
+		refund: u128,
+		token: Vec<u8>,
+		encrypted_output1: Vec<u8>,
+		encrypted_output2: Vec<u8>,
 	) -> ExtData {
-		let fee: u128 = JsValue::from(fee).as_string().unwrap().parse().unwrap();
-		let ext_amount: i128 = JsValue::from(ext_amount).as_string().unwrap().parse().unwrap();
-		let recipient = recipient.to_vec();
-		let relayer = relayer.to_vec();
-		let refund: u128 = JsValue::from(refund).as_string().unwrap().parse().unwrap();
-		let token = token.to_vec();
-		let encrypted_output1 = encrypted_output1.to_vec();
-		let encrypted_output2 = encrypted_output2.to_vec();
 		ExtData {
-			fee,
-			ext_amount,
 			recipient,
 			relayer,
+			ext_amount,
+			fee,
 			refund,
 			token,
 			encrypted_output1,
@@ -61,18 +63,56 @@ impl ExtData {
 		}
 	}
 
-	pub fn get_encode(&self) -> Uint8Array {
+	/// Keccak hash of the ABI encoded data, reduced into the BN254 scalar field
+	pub fn get_hash(&self) -> Vec<u8> {
 		let codec = self.encode_abi();
 		let mut keccak = Keccak::v256();
 		keccak.update(codec.as_slice());
 		let mut output = [0u8; 32];
 		keccak.finalize(&mut output);
 		let field_res = Bn254Fr::from_be_bytes_mod_order(&output);
-		let value = field_res.into_repr().to_bytes_be();
+		field_res.into_repr().to_bytes_be()
+	}
+}
 
-		Uint8Array::from(value.as_slice())
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl ExtData {
+	#[wasm_bindgen(constructor)]
+	#[allow(clippy::too_many_arguments)]
+	pub fn construct(
+		recipient: Uint8Array,
+		relayer: Uint8Array,
+		ext_amount: JsString,
+		fee: JsString,
+		refund: JsString,
+		token: Uint8Array,
+		encrypted_output1: Uint8Array,
+		encrypted_output2: Uint8Array,
+	) -> ExtData {
+		let fee: u128 = JsValue::from(fee).as_string().unwrap().parse().unwrap();
+		let ext_amount: i128 = JsValue::from(ext_amount).as_string().unwrap().parse().unwrap();
+		let refund: u128 = JsValue::from(refund).as_string().unwrap().parse().unwrap();
+		ExtData::new(
+			recipient.to_vec(),
+			relayer.to_vec(),
+			ext_amount,
+			fee,
+			refund,
+			token.to_vec(),
+			encrypted_output1.to_vec(),
+			encrypted_output2.to_vec(),
+		)
+	}
+
+	pub fn get_encode(&self) -> Uint8Array {
+		Uint8Array::from(self.get_hash().as_slice())
 	}
 }
+
+#[cfg(feature = "js")]
+impl_js_serialization!(ExtData);
+
 #[allow(clippy::wrong_self_convention)]
 pub trait IntoAbiToken {
 	fn into_abi(&self) -> Token;
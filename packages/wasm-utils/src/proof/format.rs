@@ -0,0 +1,530 @@
+//! Encodings of Groth16 proofs for the different verifiers
+//!
+//! The provers return `compressed` proofs, which `verify` takes. With `n` the
+//! byte size of a base field element (32 on Bn254, 48 on Bls381):
+//! - `compressed`: `a || b || c` arkworks compressed points, `4n` bytes. G1
+//!   points are `x` and G2 points `x.c0 || x.c1`, little-endian, with the sign
+//!   of `y` and the infinity flag in the top bits of the last byte.
+//! - `uncompressed`: `a || b || c` arkworks uncompressed points, `8n` bytes.
+//!   Points are `x || y`, little-endian, with the infinity flag in the top bits
+//!   of the last byte of `y`.
+//! - `solidity`: the `uint256[8]` of the Solidity verifiers, 256 bytes. The
+//!   big-endian words `a.x, a.y, b.x.c1, b.x.c0, b.y.c1, b.y.c0, c.x, c.y`, G2
+//!   coordinates have the imaginary part first as for the EIP-197 precompile
+//!   and the point at infinity is `(0, 0)`. Bn254 only.
+//! - `snarkjs`: the snarkjs JSON proof, with decimal projective coordinates
+//!   `{"pi_a": [x, y, "1"], "pi_b": [[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]],
+//!   "pi_c": [x, y, "1"], "protocol": "groth16", "curve": "bn128"}`. The curve
+//!   is `bls12381` on Bls381.
+use core::fmt;
+use std::str::FromStr;
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::models::short_weierstrass_jacobian::GroupAffine;
+use ark_ec::models::SWModelParameters;
+use ark_ec::PairingEngine;
+use ark_ff::{BigInteger, Fp2, Fp2Parameters, PrimeField, Zero};
+use ark_groth16::Proof;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "js")]
+use js_sys::JsString;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::types::{Curve, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
+use crate::types::{WasmCurve, WasmProofFormat};
+
+const SOLIDITY_WORD: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+	Compressed,
+	Uncompressed,
+	Solidity,
+	Snarkjs,
+}
+
+impl fmt::Display for ProofFormat {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ProofFormat::Compressed => write!(f, "compressed"),
+			ProofFormat::Uncompressed => write!(f, "uncompressed"),
+			ProofFormat::Solidity => write!(f, "solidity"),
+			ProofFormat::Snarkjs => write!(f, "snarkjs"),
+		}
+	}
+}
+
+impl FromStr for ProofFormat {
+	type Err = OpStatusCode;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"compressed" => Ok(ProofFormat::Compressed),
+			"uncompressed" => Ok(ProofFormat::Uncompressed),
+			"solidity" => Ok(ProofFormat::Solidity),
+			"snarkjs" => Ok(ProofFormat::Snarkjs),
+			_ => Err(OpStatusCode::InvalidProofFormat),
+		}
+	}
+}
+
+/// Encode a `compressed` proof, as returned by the provers, in `format`
+///
+/// The `snarkjs` encoding is the UTF-8 JSON.
+pub fn encode_proof(curve: Curve, proof: &[u8], format: ProofFormat) -> Result<Vec<u8>, OperationError> {
+	match curve {
+		Curve::Bn254 => encode::<Bn254>(curve, proof, format),
+		Curve::Bls381 => encode::<Bls12_381>(curve, proof, format),
+	}
+}
+
+/// Decode a proof in `format` into the `compressed` encoding `verify` takes
+///
+/// Every point is checked to be on the curve and in the prime order subgroup.
+pub fn decode_proof(curve: Curve, proof: &[u8], format: ProofFormat) -> Result<Vec<u8>, OperationError> {
+	match curve {
+		Curve::Bn254 => decode::<Bn254>(curve, proof, format),
+		Curve::Bls381 => decode::<Bls12_381>(curve, proof, format),
+	}
+}
+
+/// Re-encode a proof from the format `from` into `to`
+pub fn convert_proof(
+	curve: Curve,
+	proof: &[u8],
+	from: ProofFormat,
+	to: ProofFormat,
+) -> Result<Vec<u8>, OperationError> {
+	let compressed = decode_proof(curve, proof, from)?;
+	encode_proof(curve, &compressed, to)
+}
+
+fn encode<E: ProofCurve>(curve: Curve, proof: &[u8], format: ProofFormat) -> Result<Vec<u8>, OperationError> {
+	let proof = Proof::<E>::deserialize(proof).map_err(format_error)?;
+	let mut bytes = Vec::new();
+	match format {
+		ProofFormat::Compressed => proof.serialize(&mut bytes).map_err(format_error)?,
+		ProofFormat::Uncompressed => proof.serialize_uncompressed(&mut bytes).map_err(format_error)?,
+		ProofFormat::Solidity => bytes = E::points(&proof).to_solidity(curve)?,
+		ProofFormat::Snarkjs => bytes = E::points(&proof).to_snarkjs(curve)?,
+	}
+	Ok(bytes)
+}
+
+fn decode<E: ProofCurve>(curve: Curve, proof: &[u8], format: ProofFormat) -> Result<Vec<u8>, OperationError> {
+	let proof = match format {
+		ProofFormat::Compressed => Proof::<E>::deserialize(proof).map_err(format_error)?,
+		ProofFormat::Uncompressed => Proof::<E>::deserialize_uncompressed(proof).map_err(format_error)?,
+		ProofFormat::Solidity => E::proof(&ProofPoints::from_solidity(curve, proof)?)?,
+		ProofFormat::Snarkjs => E::proof(&ProofPoints::from_snarkjs(curve, proof)?)?,
+	};
+	let mut bytes = Vec::new();
+	proof.serialize(&mut bytes).map_err(format_error)?;
+	Ok(bytes)
+}
+
+fn format_error(e: impl fmt::Display) -> OperationError {
+	OperationError::new_with_message(OpStatusCode::InvalidProofFormat, e.to_string())
+}
+
+pub(crate) type G1Coordinates = [Vec<u8>; 2];
+pub(crate) type G2Coordinates = [[Vec<u8>; 2]; 2];
+
+/// Big-endian affine coordinates of the proof points, `None` is the point at
+/// infinity. G2 coordinates are `[[x.c0, x.c1], [y.c0, y.c1]]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProofPoints {
+	a: Option<G1Coordinates>,
+	b: Option<G2Coordinates>,
+	c: Option<G1Coordinates>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnarkjsProof {
+	pi_a: Vec<String>,
+	pi_b: Vec<Vec<String>>,
+	pi_c: Vec<String>,
+	protocol: String,
+	curve: String,
+}
+
+impl ProofPoints {
+	fn to_solidity(&self, curve: Curve) -> Result<Vec<u8>, OperationError> {
+		check_solidity_curve(curve)?;
+		let zero = vec![0u8; SOLIDITY_WORD];
+		let [a_x, a_y] = self.a.clone().unwrap_or_else(|| [zero.clone(), zero.clone()]);
+		let [[b_x0, b_x1], [b_y0, b_y1]] = self
+			.b
+			.clone()
+			.unwrap_or_else(|| [[zero.clone(), zero.clone()], [zero.clone(), zero.clone()]]);
+		let [c_x, c_y] = self.c.clone().unwrap_or_else(|| [zero.clone(), zero]);
+		Ok([a_x, a_y, b_x1, b_x0, b_y1, b_y0, c_x, c_y].concat())
+	}
+
+	fn from_solidity(curve: Curve, bytes: &[u8]) -> Result<Self, OperationError> {
+		check_solidity_curve(curve)?;
+		if bytes.len() != 8 * SOLIDITY_WORD {
+			let message = format!("Solidity proofs are 256 bytes, got {}", bytes.len());
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidProofFormat,
+				message,
+			));
+		}
+		let words: Vec<Vec<u8>> = bytes.chunks(SOLIDITY_WORD).map(|word| word.to_vec()).collect();
+		let is_zero = |words: &[Vec<u8>]| words.iter().all(|word| word.iter().all(|byte| *byte == 0));
+		let g1 = |words: &[Vec<u8>]| (!is_zero(words)).then(|| [words[0].clone(), words[1].clone()]);
+		// The imaginary parts come first
+		let b_x = [words[3].clone(), words[2].clone()];
+		let b_y = [words[5].clone(), words[4].clone()];
+		let b = (!is_zero(&words[2..6])).then(|| [b_x, b_y]);
+		Ok(ProofPoints {
+			a: g1(&words[0..2]),
+			b,
+			c: g1(&words[6..8]),
+		})
+	}
+
+	fn to_snarkjs(&self, curve: Curve) -> Result<Vec<u8>, OperationError> {
+		let g1 = |point: &Option<G1Coordinates>| match point {
+			Some([x, y]) => vec![to_decimal(x), to_decimal(y), "1".to_string()],
+			None => vec!["0".to_string(), "1".to_string(), "0".to_string()],
+		};
+		let pi_b = match &self.b {
+			Some([[x0, x1], [y0, y1]]) => vec![
+				vec![to_decimal(x0), to_decimal(x1)],
+				vec![to_decimal(y0), to_decimal(y1)],
+				vec!["1".to_string(), "0".to_string()],
+			],
+			None => vec![
+				vec!["0".to_string(), "0".to_string()],
+				vec!["1".to_string(), "0".to_string()],
+				vec!["0".to_string(), "0".to_string()],
+			],
+		};
+		let proof = SnarkjsProof {
+			pi_a: g1(&self.a),
+			pi_b,
+			pi_c: g1(&self.c),
+			protocol: "groth16".to_string(),
+			curve: snarkjs_curve(curve).to_string(),
+		};
+		serde_json::to_vec(&proof).map_err(format_error)
+	}
+
+	fn from_snarkjs(curve: Curve, bytes: &[u8]) -> Result<Self, OperationError> {
+		let proof: SnarkjsProof = serde_json::from_slice(bytes).map_err(format_error)?;
+		if proof.protocol != "groth16" || proof.curve != snarkjs_curve(curve) {
+			let message = format!(
+				"Expected a groth16 proof on {}, got a {} proof on {}",
+				snarkjs_curve(curve),
+				proof.protocol,
+				proof.curve
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidProofFormat,
+				message,
+			));
+		}
+		let size = field_size(curve);
+		let g1 = |point: &[String]| -> Result<Option<G1Coordinates>, OperationError> {
+			match point {
+				[_, _, z] if z == "0" => Ok(None),
+				[x, y, z] if z == "1" => Ok(Some([of_decimal(x, size)?, of_decimal(y, size)?])),
+				_ => Err(format_error("G1 points are [x, y, z] with z either 0 or 1")),
+			}
+		};
+		let b = match proof.pi_b.as_slice() {
+			[_, _, z] if z.iter().all(|c| c == "0") => None,
+			[x, y, z] if x.len() == 2 && y.len() == 2 && z.as_slice() == ["1", "0"] => Some([
+				[of_decimal(&x[0], size)?, of_decimal(&x[1], size)?],
+				[of_decimal(&y[0], size)?, of_decimal(&y[1], size)?],
+			]),
+			_ => return Err(format_error("G2 points are [x, y, z] with z either [0, 0] or [1, 0]")),
+		};
+		Ok(ProofPoints {
+			a: g1(&proof.pi_a)?,
+			b,
+			c: g1(&proof.pi_c)?,
+		})
+	}
+}
+
+fn check_solidity_curve(curve: Curve) -> Result<(), OperationError> {
+	match curve {
+		Curve::Bn254 => Ok(()),
+		Curve::Bls381 => Err(OperationError::new_with_message(
+			OpStatusCode::InvalidCurve,
+			"Solidity verifiers only support Bn254 proofs".to_string(),
+		)),
+	}
+}
+
+fn snarkjs_curve(curve: Curve) -> &'static str {
+	match curve {
+		Curve::Bn254 => "bn128",
+		Curve::Bls381 => "bls12381",
+	}
+}
+
+fn field_size(curve: Curve) -> usize {
+	match curve {
+		Curve::Bn254 => 32,
+		Curve::Bls381 => 48,
+	}
+}
+
+pub(crate) fn to_decimal(bytes: &[u8]) -> String {
+	BigUint::from_bytes_be(bytes).to_str_radix(10)
+}
+
+/// Big-endian bytes of a decimal number, left padded to `size` bytes
+fn of_decimal(decimal: &str, size: usize) -> Result<Vec<u8>, OperationError> {
+	let number = BigUint::parse_bytes(decimal.as_bytes(), 10)
+		.ok_or_else(|| format_error(format!("{} isn't a decimal number", decimal)))?;
+	let bytes = number.to_bytes_be();
+	if bytes.len() > size {
+		return Err(format_error(format!("{} doesn't fit in a field element", decimal)));
+	}
+	let mut padded = vec![0u8; size - bytes.len()];
+	padded.extend(bytes);
+	Ok(padded)
+}
+
+fn field_bytes<F: PrimeField>(element: &F) -> Vec<u8> {
+	element.into_repr().to_bytes_be()
+}
+
+fn field_of_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, OperationError> {
+	let element = F::from_be_bytes_mod_order(bytes);
+	// A non canonical encoding doesn't round trip
+	if field_bytes(&element) != bytes {
+		return Err(format_error(format!("{} isn't a field element", hex::encode(bytes))));
+	}
+	Ok(element)
+}
+
+fn checked_point<P: SWModelParameters>(point: GroupAffine<P>) -> Result<GroupAffine<P>, OperationError> {
+	if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+		return Err(format_error("Proof point isn't in the prime order subgroup"));
+	}
+	Ok(point)
+}
+
+pub(crate) fn g1_coordinates<P>(point: &GroupAffine<P>) -> Option<G1Coordinates>
+where
+	P: SWModelParameters,
+	P::BaseField: PrimeField,
+{
+	(!point.infinity).then(|| [field_bytes(&point.x), field_bytes(&point.y)])
+}
+
+fn g1_point<P>(coordinates: &Option<G1Coordinates>) -> Result<GroupAffine<P>, OperationError>
+where
+	P: SWModelParameters,
+	P::BaseField: PrimeField,
+{
+	match coordinates {
+		Some([x, y]) => checked_point(GroupAffine::new(field_of_bytes(x)?, field_of_bytes(y)?, false)),
+		None => Ok(GroupAffine::zero()),
+	}
+}
+
+pub(crate) fn g2_coordinates<P, Q>(point: &GroupAffine<P>) -> Option<G2Coordinates>
+where
+	P: SWModelParameters<BaseField = Fp2<Q>>,
+	Q: Fp2Parameters,
+{
+	(!point.infinity).then(|| {
+		[[field_bytes(&point.x.c0), field_bytes(&point.x.c1)], [
+			field_bytes(&point.y.c0),
+			field_bytes(&point.y.c1),
+		]]
+	})
+}
+
+fn g2_point<P, Q>(coordinates: &Option<G2Coordinates>) -> Result<GroupAffine<P>, OperationError>
+where
+	P: SWModelParameters<BaseField = Fp2<Q>>,
+	Q: Fp2Parameters,
+{
+	match coordinates {
+		Some([[x0, x1], [y0, y1]]) => {
+			let x = Fp2::new(field_of_bytes(x0)?, field_of_bytes(x1)?);
+			let y = Fp2::new(field_of_bytes(y0)?, field_of_bytes(y1)?);
+			checked_point(GroupAffine::new(x, y, false))
+		}
+		None => Ok(GroupAffine::zero()),
+	}
+}
+
+/// Pairings whose proof points can be read as coordinates
+trait ProofCurve: PairingEngine {
+	fn points(proof: &Proof<Self>) -> ProofPoints;
+	fn proof(points: &ProofPoints) -> Result<Proof<Self>, OperationError>;
+}
+
+macro_rules! impl_proof_curve {
+	($engine:ty) => {
+		impl ProofCurve for $engine {
+			fn points(proof: &Proof<Self>) -> ProofPoints {
+				ProofPoints {
+					a: g1_coordinates(&proof.a),
+					b: g2_coordinates(&proof.b),
+					c: g1_coordinates(&proof.c),
+				}
+			}
+
+			fn proof(points: &ProofPoints) -> Result<Proof<Self>, OperationError> {
+				Ok(Proof {
+					a: g1_point(&points.a)?,
+					b: g2_point(&points.b)?,
+					c: g1_point(&points.c)?,
+				})
+			}
+		}
+	};
+}
+
+impl_proof_curve!(Bn254);
+impl_proof_curve!(Bls12_381);
+
+/// Re-encode a proof between formats. Binary formats are hex strings, as the
+/// `proof` of the proof outputs, and `snarkjs` proofs are JSON strings.
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = convertProof)]
+pub fn convert_proof_js(
+	proof: JsString,
+	curve: WasmCurve,
+	from: WasmProofFormat,
+	to: WasmProofFormat,
+) -> Result<JsString, JsValue> {
+	let parse_format = |format: WasmProofFormat| -> Result<ProofFormat, OpStatusCode> {
+		JsValue::from(format)
+			.as_string()
+			.ok_or(OpStatusCode::InvalidProofFormat)?
+			.parse()
+	};
+	let curve: Curve = JsValue::from(curve)
+		.as_string()
+		.ok_or(OpStatusCode::InvalidCurve)?
+		.parse()?;
+	let from = parse_format(from)?;
+	let to = parse_format(to)?;
+
+	let proof: String = proof.into();
+	let proof = match from {
+		ProofFormat::Snarkjs => proof.into_bytes(),
+		_ => hex::decode(proof).map_err(|_| OpStatusCode::HexParsingFailed)?,
+	};
+	let converted = convert_proof(curve, &proof, from, to)?;
+	let converted = match to {
+		ProofFormat::Snarkjs => String::from_utf8(converted).map_err(|_| OpStatusCode::InvalidProofFormat)?,
+		_ => hex::encode(converted),
+	};
+	Ok(converted.into())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use ark_ec::{AffineCurve, ProjectiveCurve};
+	use ark_std::UniformRand;
+	use rand::rngs::OsRng;
+
+	fn random_proof<E: PairingEngine>() -> Vec<u8> {
+		let proof = Proof::<E> {
+			a: E::G1Projective::rand(&mut OsRng).into_affine(),
+			b: E::G2Projective::rand(&mut OsRng).into_affine(),
+			c: E::G1Projective::rand(&mut OsRng).into_affine(),
+		};
+		let mut bytes = Vec::new();
+		proof.serialize(&mut bytes).unwrap();
+		bytes
+	}
+
+	const FORMATS: [ProofFormat; 4] = [
+		ProofFormat::Compressed,
+		ProofFormat::Uncompressed,
+		ProofFormat::Solidity,
+		ProofFormat::Snarkjs,
+	];
+
+	#[test]
+	fn should_round_trip_every_format() {
+		for (curve, proof) in [
+			(Curve::Bn254, random_proof::<Bn254>()),
+			(Curve::Bls381, random_proof::<Bls12_381>()),
+		] {
+			let size = field_size(curve);
+			assert_eq!(proof.len(), 4 * size);
+			for format in FORMATS {
+				if curve == Curve::Bls381 && format == ProofFormat::Solidity {
+					assert_eq!(
+						encode_proof(curve, &proof, format).unwrap_err().code,
+						OpStatusCode::InvalidCurve
+					);
+					continue;
+				}
+				let encoded = encode_proof(curve, &proof, format).unwrap();
+				match format {
+					ProofFormat::Uncompressed => assert_eq!(encoded.len(), 8 * size),
+					ProofFormat::Solidity => assert_eq!(encoded.len(), 256),
+					_ => {}
+				}
+				assert_eq!(decode_proof(curve, &encoded, format).unwrap(), proof);
+				assert_eq!(format.to_string().parse::<ProofFormat>().unwrap(), format);
+			}
+		}
+	}
+
+	#[test]
+	fn should_lay_out_solidity_and_snarkjs_proofs() {
+		let proof = random_proof::<Bn254>();
+		let points = Bn254::points(&Proof::<Bn254>::deserialize(proof.as_slice()).unwrap());
+		let [[b_x0, b_x1], _] = points.b.clone().unwrap();
+
+		let solidity = encode_proof(Curve::Bn254, &proof, ProofFormat::Solidity).unwrap();
+		assert_eq!(&solidity[..32], points.a.as_ref().unwrap()[0].as_slice());
+		assert_eq!(&solidity[64..96], b_x1.as_slice());
+		assert_eq!(&solidity[96..128], b_x0.as_slice());
+
+		let snarkjs = encode_proof(Curve::Bn254, &proof, ProofFormat::Snarkjs).unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&snarkjs).unwrap();
+		assert_eq!(json["pi_b"][0][0], to_decimal(&b_x0));
+		assert_eq!(json["pi_a"][2], "1");
+		assert_eq!(json["curve"], "bn128");
+		assert_eq!(
+			convert_proof(Curve::Bn254, &snarkjs, ProofFormat::Snarkjs, ProofFormat::Solidity).unwrap(),
+			solidity
+		);
+	}
+
+	#[test]
+	fn should_reject_invalid_points() {
+		let proof = random_proof::<Bn254>();
+		let mut solidity = encode_proof(Curve::Bn254, &proof, ProofFormat::Solidity).unwrap();
+		solidity[31] ^= 1;
+		assert_eq!(
+			decode_proof(Curve::Bn254, &solidity, ProofFormat::Solidity)
+				.unwrap_err()
+				.code,
+			OpStatusCode::InvalidProofFormat
+		);
+
+		let generator = <Bn254 as PairingEngine>::G1Affine::prime_subgroup_generator();
+		let points = ProofPoints {
+			a: g1_coordinates(&generator),
+			b: None,
+			c: Some([vec![0xff; 32], vec![0xff; 32]]),
+		};
+		assert_eq!(
+			Bn254::proof(&points).unwrap_err().code,
+			OpStatusCode::InvalidProofFormat
+		);
+	}
+}
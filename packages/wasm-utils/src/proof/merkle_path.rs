@@ -0,0 +1,279 @@
+//! Merkle membership paths, so a leaf can be checked against a root without
+//! the leaves of the whole tree
+use core::convert::TryInto;
+use core::marker::PhantomData;
+
+use ark_bn254::Fr as Bn254Fr;
+use ark_ff::{BigInteger, PrimeField};
+use arkworks_native_gadgets::merkle_tree::Path;
+use arkworks_native_gadgets::poseidon::{FieldHasher, Poseidon};
+use arkworks_setups::common::setup_params;
+use arkworks_setups::Curve as ArkCurve;
+#[cfg(feature = "js")]
+use js_sys::{Array, JsString, Uint8Array};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::check_tree_height;
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, hex_bytes_list, Serializable};
+use crate::types::{OpStatusCode, OperationError};
+
+/// Path from a leaf to the root of a Bn254 Poseidon tree, in the
+/// `pathElements`/`pathIndices` form indexers serve
+///
+/// Both lists go from the leaf up. `path_indices[i]` is `1` when the node of
+/// the path at level `i` is a right child, so they are the bits of the leaf
+/// index, and `path_elements[i]` is its sibling.
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", try_from = "MerklePathFields")]
+pub struct MerklePath {
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) path_elements: Vec<Vec<u8>>,
+	pub(crate) path_indices: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) root: Vec<u8>,
+}
+
+impl Serializable for MerklePath {}
+
+/// Unchecked fields of a decoded `MerklePath`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MerklePathFields {
+	#[serde(with = "hex_bytes_list")]
+	path_elements: Vec<Vec<u8>>,
+	path_indices: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	root: Vec<u8>,
+}
+
+impl TryFrom<MerklePathFields> for MerklePath {
+	type Error = OperationError;
+
+	fn try_from(fields: MerklePathFields) -> Result<Self, Self::Error> {
+		MerklePath::new(fields.path_elements, fields.path_indices, fields.root)
+	}
+}
+
+impl MerklePath {
+	/// Elements and roots are 32 bytes big-endian, the path has to be as long
+	/// as one of the supported tree heights
+	pub fn new(path_elements: Vec<Vec<u8>>, path_indices: Vec<u8>, root: Vec<u8>) -> Result<Self, OperationError> {
+		check_tree_height(path_elements.len())?;
+		if path_indices.len() != path_elements.len() || path_indices.iter().any(|bit| *bit > 1) {
+			let message = format!(
+				"Expected {} path indices of 0 or 1, got {:?}",
+				path_elements.len(),
+				path_indices
+			);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidIndices, message));
+		}
+		Ok(Self {
+			path_elements,
+			path_indices,
+			root,
+		})
+	}
+
+	pub fn get_path_elements(&self) -> &[Vec<u8>] {
+		&self.path_elements
+	}
+
+	pub fn get_path_indices(&self) -> &[u8] {
+		&self.path_indices
+	}
+
+	pub fn get_root(&self) -> &[u8] {
+		&self.root
+	}
+
+	pub fn height(&self) -> usize {
+		self.path_elements.len()
+	}
+
+	/// Index of the leaf, read from the path indices
+	pub fn leaf_index(&self) -> u64 {
+		self.path_indices
+			.iter()
+			.enumerate()
+			.fold(0, |index, (level, bit)| index | (u64::from(*bit) << level))
+	}
+
+	/// Root of the tree holding `leaf` at the end of the path
+	pub fn compute_root(&self, leaf: &[u8]) -> Result<Vec<u8>, OperationError> {
+		self.compute_root_on::<Bn254Fr>(ArkCurve::Bn254, leaf)
+	}
+
+	/// Fails with `InvalidRoots` unless `leaf` is in the tree of the path root
+	pub fn check(&self, leaf: &[u8]) -> Result<(), OperationError> {
+		self.check_on::<Bn254Fr>(ArkCurve::Bn254, leaf)
+	}
+
+	/// `compute_root` for the Poseidon tree of `curve`
+	pub(crate) fn compute_root_on<F: PrimeField>(
+		&self,
+		curve: ArkCurve,
+		leaf: &[u8],
+	) -> Result<Vec<u8>, OperationError> {
+		let (_, root) = self.hash_levels::<F>(curve, leaf)?;
+		Ok(root.into_repr().to_bytes_be())
+	}
+
+	/// `check` for the Poseidon tree of `curve`
+	pub(crate) fn check_on<F: PrimeField>(&self, curve: ArkCurve, leaf: &[u8]) -> Result<(), OperationError> {
+		let computed = self.compute_root_on::<F>(curve, leaf)?;
+		if computed != self.root {
+			let message = format!(
+				"The path of {} leads to the root {}, not {}",
+				hex::encode(leaf),
+				hex::encode(&computed),
+				hex::encode(&self.root)
+			);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidRoots, message));
+		}
+		Ok(())
+	}
+
+	/// The path of `leaf` as the circuits of a tree of height `H` take it,
+	/// with the node of every level next to its sibling
+	///
+	/// The path isn't checked against its root, see `check_on`.
+	pub(crate) fn to_path<F: PrimeField, const H: usize>(
+		&self,
+		curve: ArkCurve,
+		leaf: &[u8],
+	) -> Result<Path<F, Poseidon<F>, H>, OperationError> {
+		if self.height() != H {
+			let message = format!("The path has {} levels, the tree of the circuit {}", self.height(), H);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidTreeHeight,
+				message,
+			));
+		}
+		let (levels, _) = self.hash_levels::<F>(curve, leaf)?;
+		let path = levels.try_into().map_err(|_| OpStatusCode::InvalidTreeHeight)?;
+		Ok(Path {
+			path,
+			marker: PhantomData,
+		})
+	}
+
+	/// The (left, right) nodes of every level from `leaf` up, and the root
+	/// they hash to
+	fn hash_levels<F: PrimeField>(&self, curve: ArkCurve, leaf: &[u8]) -> Result<(Vec<(F, F)>, F), OperationError> {
+		let hasher = Poseidon::new(setup_params::<F>(curve, 5, 3));
+		let mut node = F::from_be_bytes_mod_order(leaf);
+		let mut levels = Vec::with_capacity(self.height());
+		for (element, bit) in self.path_elements.iter().zip(&self.path_indices) {
+			let sibling = F::from_be_bytes_mod_order(element);
+			let (left, right) = if *bit == 1 { (sibling, node) } else { (node, sibling) };
+			node = hasher
+				.hash_two(&left, &right)
+				.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidLeaves, e.to_string()))?;
+			levels.push((left, right));
+		}
+		Ok((levels, node))
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl MerklePath {
+	#[wasm_bindgen(constructor)]
+	pub fn construct(path_elements: Array, path_indices: Array, root: Uint8Array) -> Result<MerklePath, JsValue> {
+		let path_elements = path_elements
+			.iter()
+			.map(|element| Uint8Array::new(&element).to_vec())
+			.collect();
+		let path_indices = path_indices
+			.iter()
+			.map(|bit| bit.as_f64().map(|bit| bit as u8).ok_or(OpStatusCode::InvalidIndices))
+			.collect::<Result<_, _>>()?;
+		Ok(Self::new(path_elements, path_indices, root.to_vec())?)
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = pathElements)]
+	pub fn path_elements(&self) -> Array {
+		self.path_elements
+			.iter()
+			.map(|element| JsString::from(hex::encode(element)))
+			.collect()
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = pathIndices)]
+	pub fn path_indices(&self) -> Array {
+		self.path_indices.iter().map(|bit| JsValue::from(*bit)).collect()
+	}
+
+	#[wasm_bindgen(getter)]
+	pub fn root(&self) -> JsString {
+		JsString::from(hex::encode(&self.root))
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = leafIndex)]
+	pub fn js_leaf_index(&self) -> JsString {
+		JsString::from(self.leaf_index().to_string())
+	}
+
+	#[wasm_bindgen(js_name = computeRoot)]
+	pub fn js_compute_root(&self, leaf: Uint8Array) -> Result<JsString, JsValue> {
+		Ok(JsString::from(hex::encode(self.compute_root(&leaf.to_vec())?)))
+	}
+
+	#[wasm_bindgen(js_name = check)]
+	pub fn js_check(&self, leaf: Uint8Array) -> Result<(), JsValue> {
+		self.check(&leaf.to_vec())?;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "js")]
+impl_js_serialization!(MerklePath);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::proof::MTBn254X5;
+
+	#[test]
+	fn should_check_leaves_against_tree_paths() {
+		let leaves: Vec<Vec<u8>> = (1u8..=5).map(|i| [i; 32].to_vec()).collect();
+		let tree = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap();
+		let path = tree.path(3).unwrap();
+		assert_eq!(path.height(), 16);
+		assert_eq!(path.leaf_index(), 3);
+		assert_eq!(path.get_root(), tree.get_root().as_slice());
+		path.check(&leaves[3]).unwrap();
+		assert_eq!(path.check(&leaves[2]).unwrap_err().code, OpStatusCode::InvalidRoots);
+
+		let json = path.to_json().unwrap();
+		let decoded = MerklePath::from_json(&json).unwrap();
+		assert_eq!(decoded, path);
+		// Decoding runs the checks of `new`
+		let bad_index = json.replacen("\"pathIndices\":[1,", "\"pathIndices\":[2,", 1);
+		assert_ne!(bad_index, json);
+		assert_eq!(
+			MerklePath::from_json(&bad_index).unwrap_err().code,
+			OpStatusCode::DeserializationFailed
+		);
+		let mut short = path.clone();
+		short.path_indices.pop();
+		assert_eq!(
+			MerklePath::from_json(&short.to_json().unwrap()).unwrap_err().code,
+			OpStatusCode::DeserializationFailed
+		);
+		assert_eq!(
+			MerklePath::new(vec![vec![0u8; 32]; 3], vec![0; 3], vec![0u8; 32])
+				.unwrap_err()
+				.code,
+			OpStatusCode::InvalidTreeHeight
+		);
+	}
+}
@@ -1,26 +1,70 @@
+use crate::proof::merkle_path::MerklePath;
+use crate::proof::proving_key::{prove, ProvingKey};
 use crate::proof::truncate_and_pad;
+use crate::secret::Secret;
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, hex_bytes_list, Serializable};
 use crate::types::{Backend, Curve, OpStatusCode, OperationError};
-use crate::{MixerR1CSProverBls381_30, MixerR1CSProverBn254_30, DEFAULT_LEAF};
+use crate::{check_tree_height, with_tree_height, DEFAULT_LEAF, TREE_HEIGHT};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::PairingEngine;
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::ProvingKey as Groth16ProvingKey;
+use arkworks_native_gadgets::poseidon::Poseidon;
+use arkworks_setups::common::{setup_params, setup_tree_and_create_path};
+use arkworks_setups::r1cs::mixer::MixerR1CSProver;
 use arkworks_setups::{Curve as ArkCurve, MixerProver};
+#[cfg(feature = "js")]
 use js_sys::{Array, JsString, Uint8Array};
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
 
 #[allow(clippy::unused_unit)]
-#[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MixerProof {
-	#[wasm_bindgen(skip)]
-	pub proof: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub nullifier_hash: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub root: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub public_inputs: Vec<Vec<u8>>,
-	#[wasm_bindgen(skip)]
-	pub leaf: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) proof: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) nullifier_hash: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) root: Vec<u8>,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) public_inputs: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) leaf: Vec<u8>,
+}
+
+impl Serializable for MixerProof {}
+
+impl MixerProof {
+	pub fn get_proof(&self) -> &[u8] {
+		&self.proof
+	}
+
+	pub fn get_nullifier_hash(&self) -> &[u8] {
+		&self.nullifier_hash
+	}
+
+	pub fn get_root(&self) -> &[u8] {
+		&self.root
+	}
+
+	pub fn get_public_inputs(&self) -> &[Vec<u8>] {
+		&self.public_inputs
+	}
+
+	pub fn get_leaf(&self) -> &[u8] {
+		&self.leaf
+	}
 }
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl MixerProof {
 	#[wasm_bindgen(getter)]
@@ -61,58 +105,99 @@ impl MixerProof {
 	}
 }
 
+#[cfg(feature = "js")]
+impl_js_serialization!(MixerProof);
+
 #[derive(Debug, Clone)]
 pub struct MixerProofPayload {
 	pub exponentiation: i8,
 	pub width: usize,
+	pub height: usize,
 	pub curve: Curve,
 	pub backend: Backend,
-	pub secret: Vec<u8>,
-	pub nullifier: Vec<u8>,
+	pub secret: Secret<Vec<u8>>,
+	pub nullifier: Secret<Vec<u8>>,
 	pub recipient: Vec<u8>,
 	pub relayer: Vec<u8>,
-	pub pk: Vec<u8>,
+	/// `None` when built with `build_without_pk`, until `create_proofs` sets
+	/// the key of the batch
+	pub pk: Option<ProvingKey>,
 	pub refund: u128,
 	pub fee: u128,
 	pub chain_id: u128,
+	/// Empty when the leaf is proved with `merkle_path`
 	pub leaves: Vec<Vec<u8>>,
 	pub leaf_index: u64,
+	/// Path of the leaf, used instead of building the tree from `leaves`
+	pub merkle_path: Option<MerklePath>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct MixerProofInput {
 	pub exponentiation: Option<i8>,
 	pub width: Option<usize>,
+	pub height: Option<usize>,
 	pub curve: Option<Curve>,
 	pub backend: Option<Backend>,
-	pub secret: Option<Vec<u8>>,
-	pub nullifier: Option<Vec<u8>>,
+	pub secret: Option<Secret<Vec<u8>>>,
+	pub nullifier: Option<Secret<Vec<u8>>>,
 	pub recipient: Option<Vec<u8>>,
 	pub relayer: Option<Vec<u8>>,
-	pub pk: Option<Vec<u8>>,
+	pub pk: Option<ProvingKey>,
 	pub refund: Option<u128>,
 	pub fee: Option<u128>,
 	pub chain_id: Option<u128>,
 	pub leaves: Option<Vec<Vec<u8>>>,
 	pub leaf_index: Option<u64>,
+	pub merkle_path: Option<MerklePath>,
 }
 
 impl MixerProofInput {
 	pub fn build(self) -> Result<MixerProofPayload, OperationError> {
-		let pk = self.pk.ok_or(OpStatusCode::InvalidProvingKey)?;
+		let payload = self.build_without_pk()?;
+		if payload.pk.is_none() {
+			return Err(OpStatusCode::InvalidProvingKey.into());
+		}
+		Ok(payload)
+	}
+
+	/// `build` without requiring the proving key, for inputs proved by
+	/// `create_proofs` with the key of the batch
+	pub fn build_without_pk(self) -> Result<MixerProofPayload, OperationError> {
 		let recipient = self.recipient.ok_or(OpStatusCode::InvalidRecipient)?;
 		let relayer = self.relayer.ok_or(OpStatusCode::InvalidRelayer)?;
-		let leaf_index = self.leaf_index.ok_or(OpStatusCode::InvalidLeafIndex)?;
 		let secret = self.secret.ok_or(OpStatusCode::InvalidNoteSecrets)?;
 		let nullifier = self.nullifier.ok_or(OpStatusCode::InvalidNoteSecrets)?;
-		let leaves = self.leaves.ok_or(OpStatusCode::InvalidLeaves)?;
+		// A Merkle path replaces the leaves and gives the leaf index
+		let (leaves, leaf_index) = match &self.merkle_path {
+			Some(merkle_path) => (Vec::new(), merkle_path.leaf_index()),
+			None => (
+				self.leaves.ok_or(OpStatusCode::InvalidLeaves)?,
+				self.leaf_index.ok_or(OpStatusCode::InvalidLeafIndex)?,
+			),
+		};
 		let fee = self.fee.ok_or(OpStatusCode::InvalidFee)?;
 		let refund = self.refund.ok_or(OpStatusCode::InvalidRefund)?;
 
 		let exponentiation = self.exponentiation.unwrap_or(5);
 		let width = self.width.unwrap_or(3);
+		let height = check_tree_height(self.height.unwrap_or(TREE_HEIGHT))?;
 		let curve = self.curve.unwrap_or(Curve::Bn254);
 		let backend = self.backend.unwrap_or(Backend::Arkworks);
+		if let Some(pk) = &self.pk {
+			pk.check_curve(curve)?;
+		}
+		if let Some(merkle_path) = self.merkle_path.as_ref().filter(|path| path.height() != height) {
+			let message = format!(
+				"The Merkle path has {} levels, the tree {}",
+				merkle_path.height(),
+				height
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidTreeHeight,
+				message,
+			));
+		}
 
 		let processed_relayer = truncate_and_pad(&relayer);
 		let processed_recipient = truncate_and_pad(&recipient);
@@ -120,82 +205,237 @@ impl MixerProofInput {
 		Ok(MixerProofPayload {
 			exponentiation,
 			width,
+			height,
 			curve,
 			backend,
 			secret,
 			nullifier,
 			recipient: processed_recipient,
 			relayer: processed_relayer,
-			pk,
+			pk: self.pk,
 			refund,
 			fee,
-			chain_id: 0,
+			chain_id: self.chain_id.unwrap_or(0),
 			leaves,
 			leaf_index,
+			merkle_path: self.merkle_path,
 		})
 	}
 }
 
 pub fn create_proof(mixer_proof_input: MixerProofPayload, rng: &mut OsRng) -> Result<MixerProof, OperationError> {
-	let MixerProofPayload {
-		recipient,
-		relayer,
-		leaves,
-		leaf_index,
-		fee,
-		refund,
-		pk,
-		secret,
-		nullifier,
-		backend,
-		curve,
-		exponentiation,
-		width,
-		..
-	} = mixer_proof_input;
-
-	let mixer_proof = match (backend, curve, exponentiation, width) {
-		(Backend::Arkworks, Curve::Bn254, 5, 3) => MixerR1CSProverBn254_30::create_proof(
-			ArkCurve::Bn254,
-			secret,
-			nullifier,
-			leaves,
-			leaf_index,
-			recipient,
-			relayer,
-			fee,
-			refund,
-			pk,
-			DEFAULT_LEAF,
-			rng,
-		),
-		(Backend::Arkworks, Curve::Bls381, 5, 3) => MixerR1CSProverBls381_30::create_proof(
-			ArkCurve::Bls381,
-			secret,
-			nullifier,
-			leaves,
-			leaf_index,
-			recipient,
-			relayer,
-			fee,
-			refund,
-			pk,
-			DEFAULT_LEAF,
-			rng,
-		),
+	let payload = &mixer_proof_input;
+	let pk = payload.pk.as_ref().ok_or(OpStatusCode::InvalidProvingKey)?;
+	match (payload.backend, payload.curve, payload.exponentiation, payload.width) {
+		(Backend::Arkworks, Curve::Bn254, 5, 3) => with_tree_height!(payload.height, |H| {
+			prove_mixer::<Bn254, H>(ArkCurve::Bn254, pk.bn254()?, payload, rng)
+		}),
+		(Backend::Arkworks, Curve::Bls381, 5, 3) => with_tree_height!(payload.height, |H| {
+			prove_mixer::<Bls12_381, H>(ArkCurve::Bls381, pk.bls381()?, payload, rng)
+		}),
 		_ => return Err(OpStatusCode::UnsupportedParameterCombination.into()),
 	}
-	.map_err(|e| {
-		let mut error: OperationError = OpStatusCode::InvalidProofParameters.into();
-		error.data = Some(e.to_string());
-		error
-	})?;
+	.ok_or(OpStatusCode::InvalidTreeHeight)?
+}
+
+fn proof_error(e: impl ToString) -> OperationError {
+	let mut error: OperationError = OpStatusCode::InvalidProofParameters.into();
+	error.data = Some(e.to_string());
+	error
+}
+
+/// Prove with the parsed key `pk` the spend of the leaf of the payload
+/// secrets, from the circuit `arkworks-setups` builds out of its Merkle path
+///
+/// The path is the payload `merkle_path`, checked against the leaf first, or
+/// the one of the tree of the payload leaves.
+fn prove_mixer<E: PairingEngine, const H: usize>(
+	curve: ArkCurve,
+	pk: &Groth16ProvingKey<E>,
+	payload: &MixerProofPayload,
+	rng: &mut OsRng,
+) -> Result<MixerProof, OperationError> {
+	let leaf =
+		MixerR1CSProver::<E, H>::create_leaf_with_privates(curve, payload.secret.to_vec(), payload.nullifier.to_vec())
+			.map_err(proof_error)?;
+	let (path, root) = match &payload.merkle_path {
+		Some(merkle_path) => {
+			merkle_path.check_on::<E::Fr>(curve, &leaf.leaf_bytes)?;
+			let path = merkle_path.to_path::<E::Fr, H>(curve, &leaf.leaf_bytes)?;
+			(path, E::Fr::from_be_bytes_mod_order(merkle_path.get_root()))
+		}
+		None => {
+			let tree_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 3));
+			let leaves: Vec<E::Fr> = payload
+				.leaves
+				.iter()
+				.map(|leaf| E::Fr::from_be_bytes_mod_order(leaf))
+				.collect();
+			let (tree, path) = setup_tree_and_create_path::<E::Fr, Poseidon<E::Fr>, H>(
+				&tree_hasher,
+				&leaves,
+				payload.leaf_index,
+				&DEFAULT_LEAF,
+			)
+			.map_err(proof_error)?;
+			(path, tree.root())
+		}
+	};
+	let nullifier_hash = E::Fr::from_be_bytes_mod_order(&leaf.nullifier_hash_bytes);
+	let arbitrary_input = MixerR1CSProver::<E, H>::setup_arbitrary_data(
+		E::Fr::from_be_bytes_mod_order(&payload.recipient),
+		E::Fr::from_be_bytes_mod_order(&payload.relayer),
+		E::Fr::from(payload.fee),
+		E::Fr::from(payload.refund),
+	);
+	let circuit = MixerR1CSProver::<E, H>::create_circuit(
+		curve,
+		arbitrary_input,
+		E::Fr::from_be_bytes_mod_order(&payload.secret),
+		E::Fr::from_be_bytes_mod_order(&payload.nullifier),
+		path,
+		root,
+		nullifier_hash,
+	);
+	let public_inputs = MixerR1CSProver::<E, H>::construct_public_inputs(nullifier_hash, root, arbitrary_input);
 
 	Ok(MixerProof {
-		proof: mixer_proof.proof,
-		nullifier_hash: mixer_proof.nullifier_hash_raw,
-		root: mixer_proof.root_raw,
-		public_inputs: mixer_proof.public_inputs_raw,
-		leaf: mixer_proof.leaf_raw,
+		proof: prove(circuit, pk, rng)?,
+		nullifier_hash: leaf.nullifier_hash_bytes,
+		root: root.into_repr().to_bytes_be(),
+		public_inputs: public_inputs
+			.iter()
+			.map(|input| input.into_repr().to_bytes_be())
+			.collect(),
+		leaf: leaf.leaf_bytes,
 	})
 }
+
+#[cfg(test)]
+mod test {
+	use rand::rngs::OsRng;
+
+	use crate::note::Note;
+	use crate::proof::mixer::{MixerProof, MixerProofInput};
+	use crate::proof::{
+		create_proof, setup_keys, setup_keys_with_height, verify, MTBn254X5, ProofInputBuilder, ProofOutput, ProvingKey,
+	};
+	use crate::serialization::Serializable;
+	use crate::types::{Curve, NoteProtocol, OpStatusCode};
+
+	#[test]
+	fn should_create_and_verify_mixer_proof_natively() {
+		let note: Note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10"
+			.parse()
+			.unwrap();
+		let (pk, vk) = setup_keys(NoteProtocol::Mixer, Curve::Bn254, 2, 2, 2).unwrap();
+
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+		builder.leaves_list(vec![note.get_leaf_commitment().unwrap()]).unwrap();
+		builder.leaf_index(0).unwrap();
+		builder.fee(5).unwrap();
+		builder.refund(1).unwrap();
+		builder.relayer([1u8; 32].to_vec()).unwrap();
+		builder.recipient([2u8; 32].to_vec()).unwrap();
+		builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+		builder
+			.secrets(note.get_leaf_and_nullifier().unwrap().mixer_leaf().unwrap())
+			.unwrap();
+
+		let proof = match create_proof(builder.build().unwrap(), &mut OsRng).unwrap() {
+			ProofOutput::Mixer(proof) => proof,
+			_ => panic!("expected a mixer proof"),
+		};
+		let is_valid = verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap();
+		assert!(is_valid);
+
+		let decoded = MixerProof::from_json(&proof.to_json().unwrap()).unwrap();
+		assert_eq!(decoded.get_proof(), proof.get_proof());
+		assert_eq!(decoded.get_public_inputs(), proof.get_public_inputs());
+		assert_eq!(decoded.get_nullifier_hash(), proof.get_nullifier_hash());
+	}
+
+	#[test]
+	fn should_create_mixer_proof_for_a_height_16_tree() {
+		let note: Note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&height=16&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10"
+			.parse()
+			.unwrap();
+		let (pk, vk) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let leaves = vec![note.get_leaf_commitment().unwrap()];
+		let tree = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap();
+		assert_eq!(tree.height(), 16);
+
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+		builder.height(note.height.unwrap()).unwrap();
+		builder.leaves_list(leaves).unwrap();
+		builder.leaf_index(0).unwrap();
+		builder.fee(5).unwrap();
+		builder.refund(1).unwrap();
+		builder.relayer([1u8; 32].to_vec()).unwrap();
+		builder.recipient([2u8; 32].to_vec()).unwrap();
+		builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+		builder
+			.secrets(note.get_leaf_and_nullifier().unwrap().mixer_leaf().unwrap())
+			.unwrap();
+
+		let proof = match create_proof(builder.build().unwrap(), &mut OsRng).unwrap() {
+			ProofOutput::Mixer(proof) => proof,
+			_ => panic!("expected a mixer proof"),
+		};
+		assert_eq!(proof.get_root(), tree.get_root().as_slice());
+		let is_valid = verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap();
+		assert!(is_valid);
+
+		let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+		assert_eq!(builder.height(17).unwrap_err().code, OpStatusCode::InvalidTreeHeight);
+	}
+
+	#[test]
+	fn should_create_mixer_proof_from_a_merkle_path() {
+		let note: Note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&height=16&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10"
+			.parse()
+			.unwrap();
+		let (pk, vk) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let leaves = vec![[1u8; 32].to_vec(), note.get_leaf_commitment().unwrap()];
+		let tree = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap();
+
+		let builder = |merkle_path| {
+			let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+			builder.height(16).unwrap();
+			builder.merkle_paths(vec![merkle_path]).unwrap();
+			builder.fee(5).unwrap();
+			builder.refund(1).unwrap();
+			builder.relayer([1u8; 32].to_vec()).unwrap();
+			builder.recipient([2u8; 32].to_vec()).unwrap();
+			builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+			builder
+				.secrets(note.get_leaf_and_nullifier().unwrap().mixer_leaf().unwrap())
+				.unwrap();
+			builder.build().unwrap()
+		};
+
+		// No leaves, only the path of the note leaf
+		let proof = match create_proof(builder(tree.path(1).unwrap()), &mut OsRng).unwrap() {
+			ProofOutput::Mixer(proof) => proof,
+			_ => panic!("expected a mixer proof"),
+		};
+		assert_eq!(proof.get_root(), tree.get_root().as_slice());
+		assert!(verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap());
+
+		// The path of another leaf fails before proving
+		let error = create_proof(builder(tree.path(0).unwrap()), &mut OsRng).unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidRoots);
+	}
+
+	#[test]
+	fn should_redact_the_secrets_in_debug_output() {
+		let input = MixerProofInput {
+			secret: Some(vec![171u8; 32].into()),
+			nullifier: Some(vec![205u8; 32].into()),
+			..Default::default()
+		};
+		let debug = format!("{:?}", input);
+		assert!(debug.contains("[REDACTED]"));
+		assert!(!debug.contains("171") && !debug.contains("205"));
+	}
+}
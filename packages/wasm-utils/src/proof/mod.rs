@@ -1,7 +1,11 @@
 #![allow(clippy::unused_unit)]
 
+use crate::amount::Amount;
+use crate::proof::anchor::{AnchorProof, AnchorProofInput, AnchorProofPayload};
+use crate::proof::ext_data::ExtData;
 use crate::proof::mixer::{MixerProof, MixerProofInput, MixerProofPayload};
 use crate::proof::vanchor::{VAnchorProof, VAnchorProofInput, VAnchorProofPayload};
+#[cfg(feature = "js")]
 use core::convert::TryFrom;
 
 use ark_bls12_381::Bls12_381;
@@ -12,32 +16,58 @@ use arkworks_native_gadgets::poseidon::Poseidon;
 use arkworks_setups::common::{
 	setup_keys_unchecked, setup_params, setup_tree_and_create_path, verify_unchecked_raw, Leaf,
 };
+use arkworks_setups::r1cs::anchor::AnchorR1CSProver;
+use arkworks_setups::r1cs::mixer::MixerR1CSProver;
+use arkworks_setups::r1cs::vanchor::VAnchorR1CSProver;
 use arkworks_setups::Curve as ArkCurve;
+#[cfg(feature = "js")]
 use js_sys::{Array, JsString, Uint8Array};
 use rand::rngs::OsRng;
-use wasm_bindgen::__rt::std::collections::btree_map::BTreeMap;
+use std::collections::BTreeMap;
 // https://github.com/rustwasm/wasm-bindgen/issues/2231#issuecomment-656293288
+#[cfg(feature = "js")]
 use wasm_bindgen::convert::FromWasmAbi;
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(feature = "js")]
 use crate::note::JsNote;
-use crate::types::{
-	Backend, Curve, Indices, Leaves, NoteProtocol, OpStatusCode, OperationError, Protocol, Uint8Arrayx32, WasmCurve,
-};
+use crate::types::{Backend, Curve, NoteProtocol, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
+use crate::types::{Indices, Leaves, Protocol, Uint8Arrayx32, WasmCurve};
+#[cfg(feature = "js")]
 use crate::utxo::JsUtxo;
-use crate::{
-	MixerR1CSProverBn254_30, VAnchorR1CSProverBn254_30_16_16_2, VAnchorR1CSProverBn254_30_16_2_2,
-	VAnchorR1CSProverBn254_30_2_16_2, VAnchorR1CSProverBn254_30_2_2_2, DEFAULT_LEAF, TREE_HEIGHT,
-};
+use crate::utxo::Utxo;
+use crate::{check_tree_height, with_tree_height, ANCHOR_COUNT, DEFAULT_LEAF, TREE_HEIGHT};
 
+pub mod anchor;
+pub mod batch;
 pub mod ext_data;
+pub mod format;
+pub mod merkle_path;
 pub mod mixer;
+pub mod planner;
+pub mod proving_key;
+pub mod tree_snapshot;
 pub mod vanchor;
+pub mod verifier;
+
+pub use batch::create_proofs;
+pub use format::{convert_proof, decode_proof, encode_proof, ProofFormat};
+pub use merkle_path::MerklePath;
+#[cfg(feature = "js")]
+pub use proving_key::JsProvingKey;
+pub use proving_key::ProvingKey;
+pub use tree_snapshot::MerkleTreeSnapshot;
+pub use verifier::render_solidity_verifier;
 
 #[cfg(test)]
+mod native_test;
+
+#[cfg(all(test, feature = "js"))]
 mod test;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "js"))]
 mod test_utils;
 
 pub fn truncate_and_pad(t: &[u8]) -> Vec<u8> {
@@ -49,9 +79,11 @@ pub fn truncate_and_pad(t: &[u8]) -> Vec<u8> {
 #[derive(Debug, Clone)]
 pub enum ProofOutput {
 	Mixer(MixerProof),
+	Anchor(AnchorProof),
 	VAnchor(VAnchorProof),
 }
 
+#[cfg(feature = "js")]
 #[derive(Debug, Clone)]
 #[wasm_bindgen]
 pub struct JsProofOutput {
@@ -59,6 +91,7 @@ pub struct JsProofOutput {
 	pub inner: ProofOutput,
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl JsProofOutput {
 	#[wasm_bindgen(getter)]
@@ -66,6 +99,7 @@ impl JsProofOutput {
 	pub fn output_protocol(&self) -> Protocol {
 		let protocol = match self.inner {
 			ProofOutput::Mixer(_) => "mixer",
+			ProofOutput::Anchor(_) => "anchor",
 			ProofOutput::VAnchor(_) => "vanchor",
 		};
 
@@ -81,6 +115,15 @@ impl JsProofOutput {
 		}
 	}
 
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = anchorProof)]
+	pub fn anchor_proof(&self) -> Result<AnchorProof, OperationError> {
+		match self.inner.clone() {
+			ProofOutput::Anchor(proof) => Ok(proof),
+			_ => Err(OpStatusCode::InvalidNoteProtocol.into()),
+		}
+	}
+
 	#[wasm_bindgen(getter)]
 	#[wasm_bindgen(js_name = vanchorProof)]
 	pub fn vanchor_proof(&self) -> Result<VAnchorProof, OperationError> {
@@ -91,6 +134,7 @@ impl JsProofOutput {
 	}
 }
 
+#[cfg(feature = "js")]
 pub fn generic_of_jsval<T: FromWasmAbi<Abi = u32>>(js: JsValue, classname: &str) -> Result<T, JsValue> {
 	use js_sys::{Object, Reflect};
 	let ctor_name = Object::get_prototype_of(&js).constructor().name();
@@ -104,27 +148,45 @@ pub fn generic_of_jsval<T: FromWasmAbi<Abi = u32>>(js: JsValue, classname: &str)
 	}
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 pub fn js_note_of_jsval(js: JsValue) -> Option<JsNote> {
 	generic_of_jsval(js, "JsNote").unwrap_or(None)
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 pub fn js_utxo_of_jsval(js: JsValue) -> Option<JsUtxo> {
 	generic_of_jsval(js, "JsUtxo").unwrap_or(None)
 }
 
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+pub fn js_merkle_path_of_jsval(js: JsValue) -> Option<MerklePath> {
+	generic_of_jsval(js, "MerklePath").unwrap_or(None)
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+pub fn js_proof_input_of_jsval(js: JsValue) -> Option<JsProofInput> {
+	generic_of_jsval(js, "JsProofInput").unwrap_or(None)
+}
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 pub struct LeavesMapInput {
 	#[wasm_bindgen(skip)]
 	pub leaves: BTreeMap<u64, Vec<Vec<u8>>>,
 }
 
+#[cfg(feature = "js")]
 impl Default for LeavesMapInput {
 	fn default() -> Self {
 		Self::new()
 	}
 }
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl LeavesMapInput {
 	#[wasm_bindgen(constructor)]
@@ -156,6 +218,7 @@ impl LeavesMapInput {
 #[derive(Debug, Clone)]
 pub enum ProofInput {
 	Mixer(Box<MixerProofPayload>),
+	Anchor(Box<AnchorProofPayload>),
 	VAnchor(Box<VAnchorProofPayload>),
 }
 
@@ -173,6 +236,36 @@ impl ProofInput {
 		}
 	}
 
+	pub fn anchor_input(&self) -> Result<AnchorProofPayload, OperationError> {
+		match self {
+			ProofInput::Anchor(anchor_input) => Ok(*anchor_input.clone()),
+			_ => {
+				let message = "Can't construct proof input for AnchorProofInput".to_string();
+				Err(OperationError::new_with_message(
+					OpStatusCode::InvalidNoteProtocol,
+					message,
+				))
+			}
+		}
+	}
+
+	/// Proving key, `None` when it's left to `create_proofs`
+	pub fn pk(&self) -> Option<&ProvingKey> {
+		match self {
+			ProofInput::Mixer(input) => input.pk.as_ref(),
+			ProofInput::Anchor(input) => input.pk.as_ref(),
+			ProofInput::VAnchor(input) => input.pk.as_ref(),
+		}
+	}
+
+	pub fn set_pk(&mut self, pk: ProvingKey) {
+		match self {
+			ProofInput::Mixer(input) => input.pk = Some(pk),
+			ProofInput::Anchor(input) => input.pk = Some(pk),
+			ProofInput::VAnchor(input) => input.pk = Some(pk),
+		}
+	}
+
 	pub fn vanchor_input(&self) -> Result<VAnchorProofPayload, OperationError> {
 		match self {
 			ProofInput::VAnchor(vanchor) => Ok(*vanchor.clone()),
@@ -187,6 +280,7 @@ impl ProofInput {
 	}
 }
 
+#[cfg(feature = "js")]
 #[allow(unused_macros)]
 macro_rules! console_log {
 	// Note that this is using the `log` function imported above during
@@ -194,6 +288,7 @@ macro_rules! console_log {
 	($($t:tt)*) => (crate::types::log(&format_args!($($t)*).to_string()))
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
 pub struct JsProofInput {
@@ -203,10 +298,54 @@ pub struct JsProofInput {
 #[derive(Debug, Clone)]
 pub enum ProofInputBuilder {
 	Mixer(Box<MixerProofInput>),
+	Anchor(Box<AnchorProofInput>),
 	VAnchor(Box<VAnchorProofInput>),
 }
 impl ProofInputBuilder {
-	pub fn set_input_utxos(&mut self, utxo_list: Vec<JsUtxo>) -> Result<(), OperationError> {
+	pub fn new(protocol: NoteProtocol) -> Self {
+		match protocol {
+			NoteProtocol::Mixer => ProofInputBuilder::Mixer(Default::default()),
+			NoteProtocol::Anchor => ProofInputBuilder::Anchor(Default::default()),
+			NoteProtocol::VAnchor => ProofInputBuilder::VAnchor(Default::default()),
+		}
+	}
+
+	pub fn build(self) -> Result<ProofInput, OperationError> {
+		let proof_input = match self {
+			ProofInputBuilder::Mixer(mixer_proof_input) => {
+				let mixer_payload = mixer_proof_input.build()?;
+				ProofInput::Mixer(Box::new(mixer_payload))
+			}
+			ProofInputBuilder::Anchor(anchor_proof_input) => {
+				let anchor_payload = anchor_proof_input.build()?;
+				ProofInput::Anchor(Box::new(anchor_payload))
+			}
+			ProofInputBuilder::VAnchor(vanchor_proof_input) => {
+				let vanchor_payload = vanchor_proof_input.build()?;
+				ProofInput::VAnchor(Box::new(vanchor_payload))
+			}
+		};
+		Ok(proof_input)
+	}
+
+	/// `build` without requiring the proving key, for inputs proved together
+	/// by `create_proofs` with the key of the batch
+	pub fn build_without_pk(self) -> Result<ProofInput, OperationError> {
+		let proof_input = match self {
+			ProofInputBuilder::Mixer(mixer_proof_input) => {
+				ProofInput::Mixer(Box::new(mixer_proof_input.build_without_pk()?))
+			}
+			ProofInputBuilder::Anchor(anchor_proof_input) => {
+				ProofInput::Anchor(Box::new(anchor_proof_input.build_without_pk()?))
+			}
+			ProofInputBuilder::VAnchor(vanchor_proof_input) => {
+				ProofInput::VAnchor(Box::new(vanchor_proof_input.build_without_pk()?))
+			}
+		};
+		Ok(proof_input)
+	}
+
+	pub fn set_input_utxos(&mut self, utxo_list: Vec<Utxo>) -> Result<(), OperationError> {
 		match self {
 			Self::VAnchor(input) => {
 				input.input_utxos = Some(utxo_list);
@@ -217,7 +356,7 @@ impl ProofInputBuilder {
 	}
 
 	/// Directly set the output Utxos in the proving payload
-	pub fn set_output_utxos(&mut self, output_utxos: [JsUtxo; 2]) -> Result<(), OperationError> {
+	pub fn set_output_utxos(&mut self, output_utxos: [Utxo; 2]) -> Result<(), OperationError> {
 		match self {
 			Self::VAnchor(input) => {
 				input.output_utxos = Some(output_utxos);
@@ -227,8 +366,24 @@ impl ProofInputBuilder {
 		}
 	}
 
+	/// Pad 1 or 3 to 15 input UTXOs with zero amount UTXOs up to the next
+	/// supported circuit arity when building
+	pub fn pad_inputs(&mut self, pad_inputs: bool) -> Result<(), OperationError> {
+		match self {
+			Self::VAnchor(input) => {
+				input.pad_inputs = Some(pad_inputs);
+				Ok(())
+			}
+			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
+		}
+	}
+
 	pub fn roots(&mut self, roots: Vec<Vec<u8>>) -> Result<(), OperationError> {
 		match self {
+			ProofInputBuilder::Anchor(value) => {
+				value.roots = Some(roots);
+				Ok(())
+			}
 			ProofInputBuilder::VAnchor(value) => {
 				value.roots = Some(roots);
 				Ok(())
@@ -237,7 +392,7 @@ impl ProofInputBuilder {
 		}
 	}
 
-	pub fn public_amount(&mut self, public_amount: i128) -> Result<(), OperationError> {
+	pub fn public_amount(&mut self, public_amount: Amount) -> Result<(), OperationError> {
 		match self {
 			ProofInputBuilder::VAnchor(input) => {
 				input.public_amount = Some(public_amount);
@@ -250,8 +405,13 @@ impl ProofInputBuilder {
 	pub fn secrets(&mut self, leaf: Leaf) -> Result<(), OperationError> {
 		match self {
 			ProofInputBuilder::Mixer(input) => {
-				input.secret = Some(leaf.secret_bytes);
-				input.nullifier = Some(leaf.nullifier_bytes);
+				input.secret = Some(leaf.secret_bytes.into());
+				input.nullifier = Some(leaf.nullifier_bytes.into());
+				Ok(())
+			}
+			ProofInputBuilder::Anchor(input) => {
+				input.secret = Some(leaf.secret_bytes.into());
+				input.nullifier = Some(leaf.nullifier_bytes.into());
 				Ok(())
 			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
@@ -278,6 +438,17 @@ impl ProofInputBuilder {
 		}
 	}
 
+	/// Set the ext data the ext data hash and public amount are derived from
+	pub fn ext_data(&mut self, ext_data: ExtData) -> Result<(), OperationError> {
+		match self {
+			Self::VAnchor(input) => {
+				input.ext_data = Some(ext_data);
+				Ok(())
+			}
+			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
+		}
+	}
+
 	pub fn leaf_indices(&mut self, leaf_indices: Vec<u64>) -> Result<(), OperationError> {
 		match self {
 			Self::VAnchor(input) => {
@@ -294,6 +465,10 @@ impl ProofInputBuilder {
 				input.recipient = Some(recipient);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.recipient = Some(recipient);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
@@ -304,6 +479,10 @@ impl ProofInputBuilder {
 				input.relayer = Some(relayer);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.relayer = Some(relayer);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
@@ -314,6 +493,10 @@ impl ProofInputBuilder {
 				input.leaves = Some(leaves);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.leaves = Some(leaves);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
@@ -324,6 +507,31 @@ impl ProofInputBuilder {
 				input.leaf_index = Some(leaf_index);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.leaf_index = Some(leaf_index);
+				Ok(())
+			}
+			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
+		}
+	}
+
+	/// Prove the leaves with their Merkle paths instead of the leaves of the
+	/// whole trees: the one path of the Mixer leaf, or the paths of the
+	/// VAnchor inputs in order, the inputs after them get an empty tree path
+	pub fn merkle_paths(&mut self, merkle_paths: Vec<MerklePath>) -> Result<(), OperationError> {
+		match self {
+			Self::Mixer(input) => {
+				if merkle_paths.len() != 1 {
+					let message = format!("A Mixer leaf has one Merkle path, got {}", merkle_paths.len());
+					return Err(OperationError::new_with_message(OpStatusCode::InvalidLeaves, message));
+				}
+				input.merkle_path = merkle_paths.into_iter().next();
+				Ok(())
+			}
+			Self::VAnchor(input) => {
+				input.merkle_paths = Some(merkle_paths);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
@@ -334,6 +542,10 @@ impl ProofInputBuilder {
 				input.fee = Some(fee);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.fee = Some(fee);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
@@ -344,15 +556,34 @@ impl ProofInputBuilder {
 				input.refund = Some(refund);
 				Ok(())
 			}
+			Self::Anchor(input) => {
+				input.refund = Some(refund);
+				Ok(())
+			}
 			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
 		}
 	}
 
-	pub fn pk(&mut self, pk: Vec<u8>) -> Result<(), OperationError> {
+	/// Commitment of the note that replaces the spent one, zeros to withdraw
+	pub fn refresh_commitment(&mut self, refresh_commitment: Vec<u8>) -> Result<(), OperationError> {
+		match self {
+			Self::Anchor(input) => {
+				input.refresh_commitment = Some(refresh_commitment);
+				Ok(())
+			}
+			_ => Err(OpStatusCode::ProofInputFieldInstantiationProtocolInvalid.into()),
+		}
+	}
+
+	/// A parsed key, cloning a `ProvingKey` shares it between inputs
+	pub fn pk(&mut self, pk: ProvingKey) -> Result<(), OperationError> {
 		match self {
 			ProofInputBuilder::Mixer(input) => {
 				input.pk = Some(pk);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.pk = Some(pk);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.pk = Some(pk);
 			}
@@ -365,6 +596,9 @@ impl ProofInputBuilder {
 			ProofInputBuilder::Mixer(input) => {
 				input.exponentiation = Some(exponentiation);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.exponentiation = Some(exponentiation);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.exponentiation = Some(exponentiation);
 			}
@@ -377,6 +611,9 @@ impl ProofInputBuilder {
 			ProofInputBuilder::Mixer(input) => {
 				input.width = Some(width);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.width = Some(width);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.width = Some(width);
 			}
@@ -384,11 +621,32 @@ impl ProofInputBuilder {
 		Ok(())
 	}
 
+	/// Height of the Merkle tree the leaves are in, one of
+	/// `SUPPORTED_TREE_HEIGHTS`
+	pub fn height(&mut self, height: usize) -> Result<(), OperationError> {
+		let height = check_tree_height(height)?;
+		match self {
+			ProofInputBuilder::Mixer(input) => {
+				input.height = Some(height);
+			}
+			ProofInputBuilder::Anchor(input) => {
+				input.height = Some(height);
+			}
+			ProofInputBuilder::VAnchor(input) => {
+				input.height = Some(height);
+			}
+		}
+		Ok(())
+	}
+
 	pub fn curve(&mut self, curve: Curve) -> Result<(), OperationError> {
 		match self {
 			ProofInputBuilder::Mixer(input) => {
 				input.curve = Some(curve);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.curve = Some(curve);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.curve = Some(curve);
 			}
@@ -401,6 +659,9 @@ impl ProofInputBuilder {
 			ProofInputBuilder::Mixer(input) => {
 				input.backend = Some(backend);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.backend = Some(backend);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.backend = Some(backend);
 			}
@@ -413,6 +674,9 @@ impl ProofInputBuilder {
 			ProofInputBuilder::Mixer(input) => {
 				input.chain_id = Some(chain_id);
 			}
+			ProofInputBuilder::Anchor(input) => {
+				input.chain_id = Some(chain_id);
+			}
 			ProofInputBuilder::VAnchor(input) => {
 				input.chain_id = Some(chain_id);
 			}
@@ -421,12 +685,14 @@ impl ProofInputBuilder {
 	}
 	/* Shared fields  [VAnchor,Anchor,Mixer] */
 }
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 #[derive(Debug)]
 pub struct JsProofInputBuilder {
 	#[wasm_bindgen(skip)]
 	pub inner: ProofInputBuilder,
 }
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl JsProofInputBuilder {
 	#[wasm_bindgen(constructor)]
@@ -438,13 +704,8 @@ impl JsProofInputBuilder {
 			.as_str()
 			.parse()
 			.map_err(|_| OpStatusCode::InvalidNoteProtocol)?;
-		let proof_input_builder = match note_protocol {
-			NoteProtocol::Mixer => ProofInputBuilder::Mixer(Default::default()),
-			NoteProtocol::VAnchor => ProofInputBuilder::VAnchor(Default::default()),
-		};
-
 		Ok(JsProofInputBuilder {
-			inner: proof_input_builder,
+			inner: ProofInputBuilder::new(note_protocol),
 		})
 	}
 
@@ -498,7 +759,7 @@ impl JsProofInputBuilder {
 
 	#[wasm_bindgen(js_name = setOutputUtxos)]
 	pub fn set_output_utxos(&mut self, utxo1: JsUtxo, utxo2: JsUtxo) -> Result<(), JsValue> {
-		self.inner.set_output_utxos([utxo1, utxo2])?;
+		self.inner.set_output_utxos([utxo1.inner, utxo2.inner])?;
 		Ok(())
 	}
 
@@ -525,6 +786,17 @@ impl JsProofInputBuilder {
 		Ok(())
 	}
 
+	/// Array of `MerklePath`, which are consumed
+	#[wasm_bindgen(js_name = setMerklePaths)]
+	pub fn set_merkle_paths(&mut self, merkle_paths: Array) -> Result<(), JsValue> {
+		let merkle_paths = merkle_paths
+			.iter()
+			.map(|v| js_merkle_path_of_jsval(v).ok_or(OpStatusCode::InvalidLeaves))
+			.collect::<Result<Vec<_>, _>>()?;
+		self.inner.merkle_paths(merkle_paths)?;
+		Ok(())
+	}
+
 	#[wasm_bindgen(js_name = setLeafIndex)]
 	pub fn set_leaf_index(&mut self, leaf_index: JsString) -> Result<(), JsValue> {
 		let leaf_index: String = leaf_index.into();
@@ -552,18 +824,36 @@ impl JsProofInputBuilder {
 		Ok(())
 	}
 
+	#[wasm_bindgen(js_name = setRefreshCommitment)]
+	pub fn set_refresh_commitment(&mut self, refresh_commitment: JsString) -> Result<(), JsValue> {
+		let c: String = refresh_commitment.into();
+		let refresh_commitment = hex::decode(c).map_err(|_| OpStatusCode::CommitmentNotSet)?;
+		self.inner.refresh_commitment(refresh_commitment)?;
+		Ok(())
+	}
+
+	#[wasm_bindgen(js_name = setHeight)]
+	pub fn set_height(&mut self, height: JsString) -> Result<(), JsValue> {
+		let height: String = height.into();
+		let height = height.parse().map_err(|_| OpStatusCode::InvalidTreeHeight)?;
+		self.inner.height(height)?;
+		Ok(())
+	}
+
+	/// Share a key parsed once with `new JsProvingKey(...)`
 	#[wasm_bindgen(js_name = setPk)]
-	pub fn set_pk(&mut self, pk: JsString) -> Result<(), JsValue> {
-		let p: String = pk.into();
-		let proving_key = hex::decode(p).map_err(|_| OpStatusCode::InvalidProvingKey)?;
-		self.inner.pk(proving_key)?;
+	pub fn set_pk(&mut self, pk: &JsProvingKey) -> Result<(), JsValue> {
+		self.inner.pk(pk.inner.clone())?;
 		Ok(())
 	}
 
 	#[wasm_bindgen]
 	pub fn public_amount(&mut self, public_amount: JsString) -> Result<(), JsValue> {
 		let pa: String = public_amount.into();
-		let pa: i128 = pa.parse().map_err(|_| OpStatusCode::InvalidPublicAmount)?;
+		let pa: Amount = pa.parse().map_err(|_| {
+			let message = format!("Public amount {} isn't an integer", pa);
+			OperationError::new_with_message(OpStatusCode::InvalidPublicAmount, message)
+		})?;
 		self.inner.public_amount(pa)?;
 		Ok(())
 	}
@@ -580,7 +870,11 @@ impl JsProofInputBuilder {
 		let exponentiation = note.exponentiation.unwrap_or(5);
 		let backend = note.backend.unwrap_or(Backend::Circom);
 		let curve = note.curve.unwrap_or(Curve::Bn254);
-		let width = note.width.unwrap_or(3);
+		// The anchor leaf hashes the chain id along the secrets
+		let width = match note.protocol {
+			NoteProtocol::Anchor => note.width.unwrap_or(4),
+			_ => note.width.unwrap_or(3),
+		};
 
 		let chain_id = note
 			.target_chain_id
@@ -592,6 +886,9 @@ impl JsProofInputBuilder {
 		self.inner.width(width)?;
 		self.inner.curve(curve)?;
 		self.inner.chain_id(chain_id)?;
+		if let Some(height) = note.height {
+			self.inner.height(height)?;
+		}
 		Ok(())
 	}
 
@@ -601,17 +898,21 @@ impl JsProofInputBuilder {
 		// For the VAnchor there is a call `set_notes` that will set UTXOs in the
 		// `ProofInput::VAnchor(VAnchorProofInput)`
 		match self.inner {
-			ProofInputBuilder::Mixer(_) => self.set_meta_data(note)?,
+			ProofInputBuilder::Mixer(_) | ProofInputBuilder::Anchor(_) => self.set_meta_data(note)?,
 			_ => return Err(OpStatusCode::InvalidNoteProtocol.into()),
 		}
 
-		#[allow(clippy::single_match)]
 		match self.inner {
 			ProofInputBuilder::Mixer(_) => {
 				let leaf = note.get_leaf_and_nullifier()?;
 				let mixer_leaf = leaf.mixer_leaf()?;
 				self.inner.secrets(mixer_leaf)?
 			}
+			ProofInputBuilder::Anchor(_) => {
+				let leaf = note.get_leaf_and_nullifier()?;
+				let anchor_leaf = leaf.anchor_leaf()?;
+				self.inner.secrets(anchor_leaf)?
+			}
 			_ => {}
 		}
 		Ok(())
@@ -623,24 +924,42 @@ impl JsProofInputBuilder {
 		Ok(JsProofInput { inner: proof_input })
 	}
 
+	/// Build an input without its proving key, for `generateProofs`
+	#[wasm_bindgen(js_name = buildWithoutPk)]
+	pub fn build_without_pk(self) -> Result<JsProofInput, JsValue> {
+		let proof_input = self.inner.build_without_pk()?;
+		Ok(JsProofInput { inner: proof_input })
+	}
+
 	/// Set utxos for vanchor
 	#[wasm_bindgen(js_name=setInputUtxos)]
 	pub fn set_input_utxos(&mut self, input_utxos: Array) -> Result<(), JsValue> {
-		let utxos: Vec<JsUtxo> = input_utxos
+		let utxos: Vec<Utxo> = input_utxos
 			.iter()
-			.map(|v| js_utxo_of_jsval(v).ok_or(OpStatusCode::InvalidInputUtxoConfig))
-			.collect::<Result<Vec<JsUtxo>, _>>()?;
+			.map(|v| {
+				js_utxo_of_jsval(v)
+					.map(|utxo| utxo.inner)
+					.ok_or(OpStatusCode::InvalidInputUtxoConfig)
+			})
+			.collect::<Result<Vec<Utxo>, _>>()?;
 
 		self.inner.exponentiation(5)?;
 		self.inner.backend(Backend::Arkworks)?;
 		self.inner.width(5)?;
-		self.inner.curve(Curve::Bn254)?;
-		self.inner.chain_id(utxos[0].chain_id_raw().into())?;
+		self.inner.curve(utxos[0].curve())?;
+		self.inner.chain_id(utxos[0].get_chain_id_raw().into())?;
 
 		self.inner.set_input_utxos(utxos)?;
 		Ok(())
 	}
 
+	/// Let the builder pad the input utxos to the 2 or 16 inputs circuit
+	#[wasm_bindgen(js_name = setPadInputs)]
+	pub fn set_pad_inputs(&mut self, pad_inputs: bool) -> Result<(), JsValue> {
+		self.inner.pad_inputs(pad_inputs)?;
+		Ok(())
+	}
+
 	#[wasm_bindgen(js_name=setExtDatahash)]
 	pub fn set_ext_data_hash(&mut self, ex_data_hash: JsString) -> Result<(), JsValue> {
 		let ex_data_hash: String = ex_data_hash.into();
@@ -649,36 +968,129 @@ impl JsProofInputBuilder {
 		self.inner.ext_data_hash(bytes)?;
 		Ok(())
 	}
+
+	/// Set the ext data instead of its hash, the public amount then defaults to
+	/// `extAmount - fee`
+	#[wasm_bindgen(js_name = setExtData)]
+	pub fn set_ext_data(&mut self, ext_data: ExtData) -> Result<(), JsValue> {
+		self.inner.ext_data(ext_data)?;
+		Ok(())
+	}
 }
+#[cfg(feature = "js")]
 impl JsProofInputBuilder {
 	pub fn build(self) -> Result<ProofInput, OperationError> {
-		let proof_input = match self.inner {
-			ProofInputBuilder::Mixer(mixer_proof_input) => {
-				let mixer_payload = mixer_proof_input.build()?;
-				ProofInput::Mixer(Box::new(mixer_payload))
-			}
-			ProofInputBuilder::VAnchor(vanchor_proof_input) => {
-				let vanchor_payload = vanchor_proof_input.build()?;
-				ProofInput::VAnchor(Box::new(vanchor_payload))
-			}
-		};
-		Ok(proof_input)
+		self.inner.build()
 	}
 }
 
-#[wasm_bindgen]
+/// Poseidon sparse Merkle tree of one of the `SUPPORTED_TREE_HEIGHTS`
+#[derive(Clone)]
+pub(crate) enum MerkleTreeBn254 {
+	Height16(SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, 16>),
+	Height20(SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, 20>),
+	Height30(SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, 30>),
+}
+
+fn setup_tree<const H: usize>(
+	leaves: &[Bn254Fr],
+	leaf_index: u64,
+) -> Result<SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, H>, OperationError> {
+	let params3 = setup_params::<Bn254Fr>(ArkCurve::Bn254, 5, 3);
+	let poseidon3 = Poseidon::new(params3);
+
+	let (tree, _) =
+		setup_tree_and_create_path::<Bn254Fr, Poseidon<Bn254Fr>, H>(&poseidon3, leaves, leaf_index, &DEFAULT_LEAF)
+			.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidLeaves, e.to_string()))?;
+	Ok(tree)
+}
+
+#[cfg_attr(feature = "js", wasm_bindgen)]
 pub struct MTBn254X5 {
-	#[wasm_bindgen(skip)]
-	pub inner: SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, TREE_HEIGHT>,
+	pub(crate) inner: MerkleTreeBn254,
 }
 
+impl MTBn254X5 {
+	/// Build the tree out of 32 bytes big-endian leaves
+	pub fn from_leaves(leaves: &[Vec<u8>], leaf_index: u64) -> Result<MTBn254X5, OperationError> {
+		Self::from_leaves_with_height(leaves, leaf_index, TREE_HEIGHT)
+	}
+
+	/// Build a tree of `height` out of 32 bytes big-endian leaves
+	pub fn from_leaves_with_height(
+		leaves: &[Vec<u8>],
+		leaf_index: u64,
+		height: usize,
+	) -> Result<MTBn254X5, OperationError> {
+		let leaves: Vec<_> = leaves
+			.iter()
+			.map(|leaf| Bn254Fr::from_be_bytes_mod_order(leaf))
+			.collect();
+		let inner = match check_tree_height(height)? {
+			16 => MerkleTreeBn254::Height16(setup_tree(&leaves, leaf_index)?),
+			20 => MerkleTreeBn254::Height20(setup_tree(&leaves, leaf_index)?),
+			_ => MerkleTreeBn254::Height30(setup_tree(&leaves, leaf_index)?),
+		};
+		Ok(Self { inner })
+	}
+
+	pub fn height(&self) -> usize {
+		match self.inner {
+			MerkleTreeBn254::Height16(_) => 16,
+			MerkleTreeBn254::Height20(_) => 20,
+			MerkleTreeBn254::Height30(_) => 30,
+		}
+	}
+
+	pub fn get_root(&self) -> Vec<u8> {
+		let root = match &self.inner {
+			MerkleTreeBn254::Height16(tree) => tree.root(),
+			MerkleTreeBn254::Height20(tree) => tree.root(),
+			MerkleTreeBn254::Height30(tree) => tree.root(),
+		};
+		root.into_repr().to_bytes_be()
+	}
+
+	pub fn insert_leaves(&mut self, leaves: &[Vec<u8>]) -> Result<(), OperationError> {
+		let mut leaves_bt = BTreeMap::<_, Bn254Fr>::new();
+		leaves.iter().for_each(|leaf| {
+			leaves_bt.insert(leaves_bt.len() as u32 + 1_u32, Bn254Fr::from_be_bytes_mod_order(leaf));
+		});
+
+		let params3 = setup_params::<Bn254Fr>(ArkCurve::Bn254, 5, 3);
+		let poseidon3 = Poseidon::new(params3);
+		match &mut self.inner {
+			MerkleTreeBn254::Height16(tree) => tree.insert_batch(&leaves_bt, &poseidon3),
+			MerkleTreeBn254::Height20(tree) => tree.insert_batch(&leaves_bt, &poseidon3),
+			MerkleTreeBn254::Height30(tree) => tree.insert_batch(&leaves_bt, &poseidon3),
+		}
+		.map_err(|_| OpStatusCode::InvalidLeaves)?;
+		Ok(())
+	}
+
+	/// Path of the leaf at `index`, to check it against the root without the
+	/// other leaves
+	pub fn path(&self, index: u64) -> Result<MerklePath, OperationError> {
+		if index >> self.height() != 0 {
+			let message = format!("Leaf index {} is out of a tree of height {}", index, self.height());
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidIndices, message));
+		}
+		match &self.inner {
+			MerkleTreeBn254::Height16(tree) => tree_snapshot::path(tree, index),
+			MerkleTreeBn254::Height20(tree) => tree_snapshot::path(tree, index),
+			MerkleTreeBn254::Height30(tree) => tree_snapshot::path(tree, index),
+		}
+	}
+}
+
+#[cfg(feature = "js")]
 #[allow(clippy::unused_unit)]
 #[wasm_bindgen]
 impl MTBn254X5 {
 	#[wasm_bindgen(constructor)]
-	pub fn new(initial_leaves: Leaves, leaf_index: JsString) -> Result<MTBn254X5, JsValue> {
+	pub fn new(initial_leaves: Leaves, leaf_index: JsString, height: Option<u32>) -> Result<MTBn254X5, JsValue> {
 		let leaf_index: String = leaf_index.into();
-		let leaf_index: u64 = leaf_index.parse().expect("Failed to parse the leaf index");
+		let leaf_index: u64 = leaf_index.parse().map_err(|_| OpStatusCode::InvalidLeafIndex)?;
 		let leaves: Vec<_> = Array::from(&initial_leaves)
 			.to_vec()
 			.into_iter()
@@ -687,83 +1099,91 @@ impl MTBn254X5 {
 			.collect::<Result<Vec<_>, _>>()
 			.map_err(|_| OpStatusCode::InvalidLeaves)?
 			.into_iter()
-			.map(|v| Bn254Fr::from_be_bytes_mod_order(v.0.as_ref()))
+			.map(|v| v.0.to_vec())
 			.collect();
 
-		let curve = ArkCurve::Bn254;
-		let params3 = setup_params::<Bn254Fr>(curve, 5, 3);
-		let poseidon3 = Poseidon::new(params3);
-
-		let (tree, _) = setup_tree_and_create_path::<Bn254Fr, Poseidon<Bn254Fr>, TREE_HEIGHT>(
-			&poseidon3,
-			&leaves,
-			leaf_index,
-			&DEFAULT_LEAF,
-		)
-		.unwrap();
-		Ok(Self { inner: tree })
+		let height = height.map(|height| height as usize).unwrap_or(TREE_HEIGHT);
+		Ok(Self::from_leaves_with_height(&leaves, leaf_index, height)?)
 	}
 
 	#[wasm_bindgen(getter)]
 	pub fn root(&self) -> JsString {
-		let root = self.inner.root().into_repr().to_bytes_be().to_vec();
-		JsString::from(hex::encode(root))
+		JsString::from(hex::encode(self.get_root()))
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = height)]
+	pub fn js_height(&self) -> u32 {
+		self.height() as u32
 	}
 
 	#[wasm_bindgen]
 	pub fn insert(&mut self, leaves: Leaves) -> Result<(), JsValue> {
-		let mut leaves_bt = BTreeMap::<_, Bn254Fr>::new();
-		Array::from(&leaves)
+		let leaves: Vec<_> = Array::from(&leaves)
 			.to_vec()
 			.into_iter()
 			.map(|v| Uint8Array::new_with_byte_offset_and_length(&v, 0, 32))
 			.map(Uint8Arrayx32::try_from)
 			.collect::<Result<Vec<_>, _>>()
 			.map_err(|_| OpStatusCode::InvalidLeaves)?
-			.iter()
-			.for_each(|leaf| {
-				leaves_bt
-					.insert(
-						leaves_bt.len() as u32 + 1_u32,
-						Bn254Fr::from_be_bytes_mod_order(leaf.0.as_ref()),
-					)
-					.unwrap();
-			});
+			.into_iter()
+			.map(|v| v.0.to_vec())
+			.collect();
 
-		let params3 = setup_params::<Bn254Fr>(ArkCurve::Bn254, 5, 3);
-		let poseidon3 = Poseidon::new(params3);
-		self.inner
-			.insert_batch(&leaves_bt, &poseidon3)
-			.map_err(|_| OpStatusCode::InvalidLeaves)?;
+		self.insert_leaves(&leaves)?;
 		Ok(())
 	}
+
+	#[wasm_bindgen(js_name = path)]
+	pub fn js_path(&self, index: JsString) -> Result<MerklePath, JsValue> {
+		let index: String = index.into();
+		let index: u64 = index.parse().map_err(|_| OpStatusCode::InvalidIndices)?;
+		Ok(self.path(index)?)
+	}
+}
+
+/// Verify a Groth16 proof against the verifying key of the given curve
+pub fn verify(curve: Curve, public_inputs: &[Vec<u8>], vk: &[u8], proof: &[u8]) -> Result<bool, OperationError> {
+	match curve {
+		Curve::Bls381 => verify_unchecked_raw::<Bls12_381>(public_inputs, vk, proof),
+		Curve::Bn254 => verify_unchecked_raw::<Bn254>(public_inputs, vk, proof),
+	}
+	.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidProofParameters, e.to_string()))
 }
+
 // For testing on js side
+#[cfg(feature = "js")]
 #[wasm_bindgen]
-pub fn verify_js_proof(proof: JsString, public_inputs: Array, vk: JsString, curve: WasmCurve) -> bool {
-	let proof = hex::decode(JsValue::from(proof).as_string().unwrap()).unwrap();
-	let pub_ins: Vec<Vec<u8>> = public_inputs
+pub fn verify_js_proof(proof: JsString, public_inputs: Array, vk: JsString, curve: WasmCurve) -> Result<bool, JsValue> {
+	let decode = |value: JsValue, code: OpStatusCode| -> Result<Vec<u8>, OperationError> {
+		let value = value.as_string().ok_or_else(|| code.clone())?;
+		hex::decode(value).map_err(|e| OperationError::new_with_message(code, e.to_string()))
+	};
+	let proof = decode(proof.into(), OpStatusCode::InvalidProofFormat)?;
+	let pub_ins = public_inputs
 		.to_vec()
 		.into_iter()
-		.map(|v| JsValue::from(JsString::from(v)).as_string().unwrap())
-		.map(|x| hex::decode(x).unwrap())
-		.collect();
-
-	let curve: Curve = JsValue::from(curve).as_string().unwrap().parse().unwrap();
-	let vk = hex::decode(JsValue::from(vk).as_string().unwrap()).unwrap();
-	match curve {
-		Curve::Bls381 => verify_unchecked_raw::<Bls12_381>(&pub_ins, &vk, &proof).unwrap(),
-		Curve::Bn254 => verify_unchecked_raw::<Bn254>(&pub_ins, &vk, &proof).unwrap(),
-	}
+		.map(|v| decode(v, OpStatusCode::InvalidProofParameters))
+		.collect::<Result<Vec<_>, _>>()?;
+	let curve: Curve = JsValue::from(curve)
+		.as_string()
+		.ok_or(OpStatusCode::InvalidCurve)?
+		.parse()?;
+	let vk = decode(vk.into(), OpStatusCode::InvalidVerifyingKey)?;
+	Ok(verify(curve, &pub_ins, &vk, &proof)?)
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 pub struct JsProvingKeys {
 	#[wasm_bindgen(skip)]
 	pub pk: Vec<u8>,
 	#[wasm_bindgen(skip)]
 	pub vk: Vec<u8>,
+	#[wasm_bindgen(skip)]
+	pub proving_key: ProvingKey,
 }
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl JsProvingKeys {
 	#[wasm_bindgen(getter)]
@@ -775,73 +1195,182 @@ impl JsProvingKeys {
 	pub fn vk(&self) -> Uint8Array {
 		Uint8Array::from(self.pk.as_slice())
 	}
+
+	/// The proving key parsed by `setupKeys`, shared without copying it to JS
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = provingKey)]
+	pub fn proving_key(&self) -> JsProvingKey {
+		self.proving_key.clone().into()
+	}
 }
-#[wasm_bindgen(js_name = setupKeys)]
+
+fn key_setup_error(e: impl ToString) -> OperationError {
+	OperationError::new_with_message(OpStatusCode::KeySetupFailed, e.to_string())
+}
+
+/// Generate a random proving/verifying key pair for the given circuit configuration
 pub fn setup_keys(
+	protocol: NoteProtocol,
+	curve: Curve,
+	anchor_count: u32,
+	in_count: u32,
+	out_count: u32,
+) -> Result<(Vec<u8>, Vec<u8>), OperationError> {
+	setup_keys_with_height(protocol, curve, TREE_HEIGHT, anchor_count, in_count, out_count)
+}
+
+/// `setup_keys` for the circuits of a tree of `height`
+pub fn setup_keys_with_height(
+	protocol: NoteProtocol,
+	curve: Curve,
+	height: usize,
+	anchor_count: u32,
+	in_count: u32,
+	out_count: u32,
+) -> Result<(Vec<u8>, Vec<u8>), OperationError> {
+	let height = check_tree_height(height)?;
+	let keys = match (protocol, curve, anchor_count, in_count, out_count) {
+		(NoteProtocol::Mixer, ..) => with_tree_height!(height, |H| {
+			let (c, ..) = MixerR1CSProver::<Bn254, H>::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
+				.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::Anchor, Curve::Bn254, ..) => with_tree_height!(height, |H| {
+			let (c, ..) = AnchorR1CSProver::<Bn254, H, ANCHOR_COUNT>::setup_random_circuit(
+				ArkCurve::Bn254,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bn254, 2, 2, 2) => with_tree_height!(height, |H| {
+			let c =
+				VAnchorR1CSProver::<Bn254, H, 2, 2, 2>::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
+					.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bn254, 2, 16, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bn254, H, 2, 16, 2>::setup_random_circuit(
+				ArkCurve::Bn254,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bn254, 16, 2, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bn254, H, 16, 2, 2>::setup_random_circuit(
+				ArkCurve::Bn254,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bn254, 16, 16, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bn254, H, 16, 16, 2>::setup_random_circuit(
+				ArkCurve::Bn254,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bls381, 2, 2, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bls12_381, H, 2, 2, 2>::setup_random_circuit(
+				ArkCurve::Bls381,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bls12_381, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bls381, 2, 16, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bls12_381, H, 2, 16, 2>::setup_random_circuit(
+				ArkCurve::Bls381,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bls12_381, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bls381, 16, 2, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bls12_381, H, 16, 2, 2>::setup_random_circuit(
+				ArkCurve::Bls381,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bls12_381, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		(NoteProtocol::VAnchor, Curve::Bls381, 16, 16, 2) => with_tree_height!(height, |H| {
+			let c = VAnchorR1CSProver::<Bls12_381, H, 16, 16, 2>::setup_random_circuit(
+				ArkCurve::Bls381,
+				DEFAULT_LEAF,
+				&mut OsRng,
+			)
+			.map_err(key_setup_error)?;
+			setup_keys_unchecked::<Bls12_381, _, _>(c, &mut OsRng).map_err(key_setup_error)?
+		}),
+		_ => {
+			return Err(OperationError::new_with_message(
+				OpStatusCode::UnsupportedParameterCombination,
+				"Unsupported input".to_string(),
+			))
+		}
+	};
+	keys.ok_or_else(|| OpStatusCode::InvalidTreeHeight.into())
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = setupKeys)]
+pub fn setup_keys_js(
 	protocol: Protocol,
 	curve: Option<WasmCurve>,
 	anchor_count: Option<u32>,
 	in_count: Option<u32>,
 	out_count: Option<u32>,
+	height: Option<u32>,
 ) -> Result<JsProvingKeys, JsValue> {
 	let curve: Curve = match curve {
-		Some(curve) => JsValue::from(curve).as_string().unwrap().parse().unwrap(),
+		Some(curve) => JsValue::from(curve)
+			.as_string()
+			.ok_or(OpStatusCode::InvalidCurve)?
+			.parse()?,
 		None => Curve::Bn254,
 	};
 	let anchor_count = anchor_count.unwrap_or(2);
 	let in_count = in_count.unwrap_or(2);
 	let out_count = out_count.unwrap_or(2);
-	let note_protocol: NoteProtocol = JsValue::from(protocol).as_string().unwrap().parse().unwrap();
-	let (pk, vk) = match (note_protocol, curve, anchor_count, in_count, out_count) {
-		(NoteProtocol::Mixer, ..) => {
-			let (c, ..) = MixerR1CSProverBn254_30::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
-				.expect("Failed to create a circuit");
-			let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).expect("failed to generate keys");
-			(pk, vk)
-		}
-		(NoteProtocol::VAnchor, Curve::Bn254, 2, 2, 2) => {
-			let c = VAnchorR1CSProverBn254_30_2_2_2::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
-				.expect("Failed to create a circuit");
-			let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).expect("failed to generate keys");
-			(pk, vk)
-		}
-		(NoteProtocol::VAnchor, Curve::Bn254, 2, 16, 2) => {
-			let c = VAnchorR1CSProverBn254_30_2_16_2::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
-				.expect("Failed to create a circuit");
-			let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).expect("failed to generate keys");
-			(pk, vk)
-		}
-		(NoteProtocol::VAnchor, Curve::Bn254, 16, 2, 2) => {
-			let c = VAnchorR1CSProverBn254_30_16_2_2::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
-				.expect("Failed to create a circuit");
-			let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).expect("failed to generate keys");
-			(pk, vk)
-		}
-		(NoteProtocol::VAnchor, Curve::Bn254, 16, 16, 2) => {
-			let c = VAnchorR1CSProverBn254_30_16_16_2::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng)
-				.expect("Failed to create a circuit");
-			let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).expect("failed to generate keys");
-			(pk, vk)
-		}
-		_ => return Err(JsValue::from(JsString::from("Unsupported input"))),
-	};
-	Ok(JsProvingKeys { pk, vk })
+	let note_protocol: NoteProtocol = JsValue::from(protocol)
+		.as_string()
+		.ok_or(OpStatusCode::InvalidNoteProtocol)?
+		.parse()?;
+	let height = height.map(|height| height as usize).unwrap_or(TREE_HEIGHT);
+	let (pk, vk) = setup_keys_with_height(note_protocol, curve, height, anchor_count, in_count, out_count)?;
+	let proving_key = ProvingKey::new(curve, &pk)?;
+	Ok(JsProvingKeys { pk, vk, proving_key })
 }
-#[wasm_bindgen]
-pub fn generate_proof_js(proof_input: JsProofInput) -> Result<JsProofOutput, JsValue> {
-	let mut rng = OsRng;
-	let proof_input_value = proof_input.inner;
-	match proof_input_value {
-		ProofInput::Mixer(mixer_proof_input) => {
-			mixer::create_proof(*mixer_proof_input, &mut rng).map(|v| JsProofOutput {
-				inner: ProofOutput::Mixer(v),
-			})
+
+/// Generate a proof for the built proof input
+pub fn create_proof(proof_input: ProofInput, rng: &mut OsRng) -> Result<ProofOutput, OperationError> {
+	match proof_input {
+		ProofInput::Mixer(mixer_proof_input) => mixer::create_proof(*mixer_proof_input, rng).map(ProofOutput::Mixer),
+		ProofInput::Anchor(anchor_proof_input) => {
+			anchor::create_proof(*anchor_proof_input, rng).map(ProofOutput::Anchor)
 		}
 		ProofInput::VAnchor(vanchor_proof_input) => {
-			vanchor::create_proof(*vanchor_proof_input, &mut rng).map(|v| JsProofOutput {
-				inner: ProofOutput::VAnchor(v),
-			})
+			vanchor::create_proof(*vanchor_proof_input, rng).map(ProofOutput::VAnchor)
 		}
 	}
-	.map_err(|e| e.into())
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+pub fn generate_proof_js(proof_input: JsProofInput) -> Result<JsProofOutput, JsValue> {
+	let mut rng = OsRng;
+	create_proof(proof_input.inner, &mut rng)
+		.map(|inner| JsProofOutput { inner })
+		.map_err(|e| e.into())
 }
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::OsRng;
+
+use crate::amount::Amount;
+use crate::proof::{
+	create_proof, setup_keys, setup_keys_with_height, verify, MTBn254X5, ProofInputBuilder, ProofOutput, ProvingKey,
+};
+use crate::types::{Backend, Curve, NoteProtocol, OpStatusCode};
+use crate::utxo::Utxo;
+
+fn utxo(amount: u128, index: Option<u64>) -> Utxo {
+	Utxo::new(Curve::Bn254, Backend::Arkworks, amount, 0, None, None, None, index).unwrap()
+}
+
+#[test]
+fn should_reject_unsupported_key_setups() {
+	let error = setup_keys(NoteProtocol::VAnchor, Curve::Bn254, 3, 3, 3).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::UnsupportedParameterCombination);
+
+	let error = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 17, 2, 2, 2).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidTreeHeight);
+}
+
+#[test]
+fn should_fail_to_verify_with_a_malformed_key() {
+	let error = verify(Curve::Bn254, &[vec![0u8; 32]], &[1u8; 8], &[2u8; 8]).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidProofParameters);
+}
+
+#[test]
+fn should_reject_fields_of_another_protocol() {
+	let mut builder = ProofInputBuilder::new(NoteProtocol::Mixer);
+	let error = builder.public_amount(Amount::from(10i128)).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::ProofInputFieldInstantiationProtocolInvalid);
+	let error = builder.leaves_map(BTreeMap::new()).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::ProofInputFieldInstantiationProtocolInvalid);
+}
+
+#[test]
+fn should_create_and_verify_vanchor_proof_natively() {
+	let (pk, vk) = setup_keys_with_height(NoteProtocol::VAnchor, Curve::Bn254, 16, 2, 2, 2).unwrap();
+	let inputs = vec![utxo(5, Some(0)), utxo(5, Some(1))];
+	let leaves: Vec<_> = inputs.iter().map(Utxo::get_commitment).collect();
+	let root = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap().get_root();
+
+	let mut builder = ProofInputBuilder::new(NoteProtocol::VAnchor);
+	builder.height(16).unwrap();
+	builder.exponentiation(5).unwrap();
+	builder.width(5).unwrap();
+	builder.curve(Curve::Bn254).unwrap();
+	builder.chain_id(0).unwrap();
+	builder.public_amount(Amount::from(10i128)).unwrap();
+	builder.ext_data_hash([1u8; 32].to_vec()).unwrap();
+	builder.roots(vec![root.clone(), root]).unwrap();
+	builder.leaves_map(BTreeMap::from([(0, leaves)])).unwrap();
+	builder.leaf_indices(vec![0, 1]).unwrap();
+	builder.set_input_utxos(inputs).unwrap();
+	builder.set_output_utxos([utxo(10, None), utxo(10, None)]).unwrap();
+	builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+
+	let proof = match create_proof(builder.build().unwrap(), &mut OsRng).unwrap() {
+		ProofOutput::VAnchor(proof) => proof,
+		_ => panic!("expected a vanchor proof"),
+	};
+	assert_eq!(
+		proof.get_public_amount(),
+		Amount::from(10i128).to_field_bytes(Curve::Bn254)
+	);
+	assert!(verify(Curve::Bn254, proof.get_public_inputs(), &vk, proof.get_proof()).unwrap());
+}
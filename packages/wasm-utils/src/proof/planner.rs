@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+
+#[cfg(feature = "js")]
+use js_sys::{Array, JsString, Uint8Array};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::amount::Amount;
+use crate::proof::ProofInputBuilder;
+#[cfg(feature = "js")]
+use crate::proof::{js_utxo_of_jsval, JsProofInputBuilder};
+use crate::types::{Backend, Curve, NoteProtocol, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
+use crate::utxo::JsUtxo;
+use crate::utxo::Utxo;
+
+/// Input counts of the VAnchor circuits
+pub const SUPPORTED_INPUT_ARITIES: [usize; 2] = [2, 16];
+
+/// Smallest supported circuit arity that fits `count` inputs
+pub fn input_arity(count: usize) -> Option<usize> {
+	SUPPORTED_INPUT_ARITIES.iter().copied().find(|arity| *arity >= count)
+}
+
+/// A fresh zero amount UTXO, used to fill unused circuit inputs
+pub fn zero_utxo(curve: Curve, chain_id: u64) -> Result<Utxo, OperationError> {
+	Utxo::new(curve, Backend::Arkworks, 0, chain_id, None, None, None, Some(0))
+}
+
+/// Only indexed UTXOs with a secret key and an amount can be spent
+fn is_spendable(utxo: &Utxo) -> bool {
+	utxo.get_secret_key().is_some() && utxo.get_index().is_some() && !utxo.get_amount_value().is_zero()
+}
+
+/// Plans a VAnchor transaction paying `amount` to `recipient_public_key`
+///
+/// A positive `ext_amount` is deposited into the pool, a negative one is
+/// withdrawn. Whatever the selected inputs hold above what is sent goes to a
+/// change output owned by `change_private_key`.
+#[derive(Debug, Clone)]
+pub struct VAnchorPlanner {
+	pub chain_id: u64,
+	pub amount: u128,
+	pub recipient_public_key: Vec<u8>,
+	pub ext_amount: i128,
+	pub change_private_key: Vec<u8>,
+}
+
+/// Inputs and outputs chosen by the `VAnchorPlanner`
+#[derive(Debug, Clone)]
+pub struct VAnchorPlan {
+	pub chain_id: u64,
+	/// Selected UTXOs followed by the zero amount padding
+	pub input_utxos: Vec<Utxo>,
+	/// Recipient output then change output
+	pub output_utxos: [Utxo; 2],
+	pub public_amount: i128,
+}
+
+impl VAnchorPlanner {
+	/// Select inputs out of `utxos`, only the UTXOs of `chain_id` that have a
+	/// secret key and an index can be spent
+	pub fn plan(&self, utxos: &[Utxo]) -> Result<VAnchorPlan, OperationError> {
+		let required = Amount::from(self.amount) - Amount::from(self.ext_amount);
+
+		let mut spendable: Vec<&Utxo> = utxos
+			.iter()
+			.filter(|utxo| utxo.get_chain_id_raw() == self.chain_id && is_spendable(utxo))
+			.collect();
+		// Largest first keeps the number of inputs low
+		spendable.sort_by_key(|utxo| core::cmp::Reverse(utxo.get_amount_value()));
+
+		let mut selected = Vec::new();
+		let mut selected_amount = Amount::default();
+		for utxo in spendable {
+			if selected_amount >= required {
+				break;
+			}
+			selected_amount += utxo.get_amount_value();
+			selected.push(utxo.clone());
+		}
+		if selected_amount < required {
+			let message = format!(
+				"Spendable UTXOs hold {} while {} is required",
+				selected_amount, required
+			);
+			let mut oe = OperationError::new_with_message(OpStatusCode::InsufficientFunds, message);
+			oe.data = Some(
+				serde_json::json!({ "available": selected_amount.to_string(), "required": required.to_string() })
+					.to_string(),
+			);
+			return Err(oe);
+		}
+
+		let arity = input_arity(selected.len()).ok_or_else(|| {
+			let message = format!(
+				"The transaction needs {} inputs while at most {} are supported, consolidate the UTXOs first",
+				selected.len(),
+				SUPPORTED_INPUT_ARITIES[SUPPORTED_INPUT_ARITIES.len() - 1]
+			);
+			OperationError::new_with_message(OpStatusCode::InvalidInputUtxoConfig, message)
+		})?;
+		let mut input_utxos = selected;
+		while input_utxos.len() < arity {
+			input_utxos.push(zero_utxo(Curve::Bn254, self.chain_id)?);
+		}
+
+		let change = (selected_amount - required)
+			.to_u128()
+			.ok_or(OpStatusCode::InvalidAmount)?;
+		let recipient_output = Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			self.amount,
+			self.chain_id,
+			None,
+			Some(self.recipient_public_key.clone()),
+			None,
+			None,
+		)?;
+		let change_output = Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			change,
+			self.chain_id,
+			None,
+			None,
+			Some(self.change_private_key.clone()),
+			None,
+		)?;
+
+		Ok(VAnchorPlan {
+			chain_id: self.chain_id,
+			input_utxos,
+			output_utxos: [recipient_output, change_output],
+			public_amount: self.ext_amount,
+		})
+	}
+}
+
+/// Merge the spendable UTXOs into one UTXO per batch of up to 16 inputs
+///
+/// Each plan spends a batch of the same chain with a public amount of 0 and
+/// creates the merged output and a zero amount output, both owned by
+/// `private_key`. The plans don't depend on each other and can be proven and
+/// submitted in any order. A batch of a single UTXO would only move it, it's
+/// skipped.
+pub fn consolidate(utxos: &[Utxo], private_key: &[u8]) -> Result<Vec<VAnchorPlan>, OperationError> {
+	let max_arity = SUPPORTED_INPUT_ARITIES[SUPPORTED_INPUT_ARITIES.len() - 1];
+	let mut chains: BTreeMap<u64, Vec<&Utxo>> = BTreeMap::new();
+	utxos.iter().filter(|utxo| is_spendable(utxo)).for_each(|utxo| {
+		chains.entry(utxo.get_chain_id_raw()).or_default().push(utxo);
+	});
+
+	let mut plans = Vec::new();
+	for (chain_id, chain_utxos) in chains {
+		for batch in chain_utxos.chunks(max_arity).filter(|batch| batch.len() > 1) {
+			let amount = batch
+				.iter()
+				.fold(Amount::default(), |sum, utxo| sum + utxo.get_amount_value())
+				.to_u128()
+				.ok_or(OpStatusCode::InvalidAmount)?;
+			let arity = input_arity(batch.len()).unwrap_or(max_arity);
+			let mut input_utxos: Vec<Utxo> = batch.iter().copied().cloned().collect();
+			while input_utxos.len() < arity {
+				input_utxos.push(zero_utxo(Curve::Bn254, chain_id)?);
+			}
+
+			let merged_output = Utxo::new(
+				Curve::Bn254,
+				Backend::Arkworks,
+				amount,
+				chain_id,
+				None,
+				None,
+				Some(private_key.to_vec()),
+				None,
+			)?;
+			let zero_output = Utxo::new(
+				Curve::Bn254,
+				Backend::Arkworks,
+				0,
+				chain_id,
+				None,
+				None,
+				Some(private_key.to_vec()),
+				None,
+			)?;
+			plans.push(VAnchorPlan {
+				chain_id,
+				input_utxos,
+				output_utxos: [merged_output, zero_output],
+				public_amount: 0,
+			});
+		}
+	}
+	Ok(plans)
+}
+
+impl VAnchorPlan {
+	/// Proof input builder with the UTXOs, indices and public amount set,
+	/// the leaves, roots, ext data hash and proving key are left to the caller
+	pub fn into_builder(self) -> Result<ProofInputBuilder, OperationError> {
+		let indices = self
+			.input_utxos
+			.iter()
+			.map(|utxo| utxo.get_index().unwrap_or(0))
+			.collect();
+
+		let mut builder = ProofInputBuilder::new(NoteProtocol::VAnchor);
+		builder.exponentiation(5)?;
+		builder.width(5)?;
+		builder.curve(Curve::Bn254)?;
+		builder.backend(Backend::Arkworks)?;
+		builder.chain_id(self.chain_id.into())?;
+		builder.public_amount(Amount::from(self.public_amount))?;
+		builder.leaf_indices(indices)?;
+		builder.set_input_utxos(self.input_utxos)?;
+		builder.set_output_utxos(self.output_utxos)?;
+		Ok(builder)
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+pub struct JsVAnchorPlan {
+	#[wasm_bindgen(skip)]
+	pub inner: VAnchorPlan,
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl JsVAnchorPlan {
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = inputUtxos)]
+	pub fn input_utxos(&self) -> Array {
+		self.inner
+			.input_utxos
+			.iter()
+			.cloned()
+			.map(|utxo| JsValue::from(JsUtxo::from(utxo)))
+			.collect()
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = outputUtxos)]
+	pub fn output_utxos(&self) -> Array {
+		self.inner
+			.output_utxos
+			.iter()
+			.cloned()
+			.map(|utxo| JsValue::from(JsUtxo::from(utxo)))
+			.collect()
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = publicAmount)]
+	pub fn public_amount(&self) -> JsString {
+		self.inner.public_amount.to_string().into()
+	}
+
+	#[wasm_bindgen(js_name = intoBuilder)]
+	pub fn into_builder(self) -> Result<JsProofInputBuilder, OperationError> {
+		let inner = self.inner.into_builder()?;
+		Ok(JsProofInputBuilder { inner })
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = planVAnchorTransaction)]
+pub fn plan_vanchor_transaction_js(
+	utxos: Array,
+	chain_id: JsString,
+	amount: JsString,
+	recipient_public_key: Uint8Array,
+	ext_amount: JsString,
+	change_private_key: Uint8Array,
+) -> Result<JsVAnchorPlan, JsValue> {
+	let utxos: Vec<Utxo> = utxos
+		.iter()
+		.map(|v| {
+			js_utxo_of_jsval(v)
+				.map(|utxo| utxo.inner)
+				.ok_or(OpStatusCode::InvalidInputUtxoConfig)
+		})
+		.collect::<Result<Vec<Utxo>, _>>()?;
+	let chain_id: String = chain_id.into();
+	let amount: String = amount.into();
+	let ext_amount: String = ext_amount.into();
+
+	let planner = VAnchorPlanner {
+		chain_id: chain_id.parse().map_err(|_| OpStatusCode::InvalidChainId)?,
+		amount: amount.parse().map_err(|_| OpStatusCode::InvalidAmount)?,
+		recipient_public_key: recipient_public_key.to_vec(),
+		ext_amount: ext_amount.parse().map_err(|_| OpStatusCode::InvalidPublicAmount)?,
+		change_private_key: change_private_key.to_vec(),
+	};
+	let inner = planner.plan(&utxos)?;
+	Ok(JsVAnchorPlan { inner })
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = consolidateUtxos)]
+pub fn consolidate_utxos_js(utxos: Array, private_key: Uint8Array) -> Result<Array, JsValue> {
+	let utxos: Vec<Utxo> = utxos
+		.iter()
+		.map(|v| {
+			js_utxo_of_jsval(v)
+				.map(|utxo| utxo.inner)
+				.ok_or(OpStatusCode::InvalidInputUtxoConfig)
+		})
+		.collect::<Result<Vec<Utxo>, _>>()?;
+	let plans = consolidate(&utxos, &private_key.to_vec())?;
+	Ok(plans
+		.into_iter()
+		.map(|inner| JsValue::from(JsVAnchorPlan { inner }))
+		.collect())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn owned_utxo(amount: u128, index: u64) -> Utxo {
+		Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			amount,
+			1,
+			None,
+			None,
+			None,
+			Some(index),
+		)
+		.unwrap()
+	}
+
+	fn planner(amount: u128, ext_amount: i128) -> VAnchorPlanner {
+		let keys = Utxo::new(Curve::Bn254, Backend::Arkworks, 0, 1, None, None, None, None).unwrap();
+		VAnchorPlanner {
+			chain_id: 1,
+			amount,
+			recipient_public_key: keys.get_public_key(),
+			ext_amount,
+			change_private_key: keys.get_secret_key().unwrap(),
+		}
+	}
+
+	#[test]
+	fn should_select_largest_inputs_and_pad() {
+		let utxos = vec![owned_utxo(5, 1), owned_utxo(20, 2), owned_utxo(7, 3)];
+		let plan = planner(25, 0).plan(&utxos).unwrap();
+
+		assert_eq!(plan.input_utxos.len(), 2);
+		assert_eq!(plan.input_utxos[0].get_amount_raw().unwrap(), 20);
+		assert_eq!(plan.input_utxos[1].get_amount_raw().unwrap(), 7);
+		assert_eq!(plan.output_utxos[0].get_amount_raw().unwrap(), 25);
+		assert!(plan.output_utxos[0].get_secret_key().is_none());
+		assert_eq!(plan.output_utxos[1].get_amount_raw().unwrap(), 2);
+
+		let mut builder = plan.into_builder().unwrap();
+		let mut leaves = BTreeMap::new();
+		leaves.insert(1, utxos.iter().map(Utxo::get_commitment).collect());
+		builder.leaves_map(leaves).unwrap();
+		builder.roots(vec![vec![0u8; 32], vec![0u8; 32]]).unwrap();
+		builder.ext_data_hash(vec![0u8; 32]).unwrap();
+		builder.build_without_pk().unwrap();
+	}
+
+	#[test]
+	fn should_use_the_16_inputs_circuit_and_deposits() {
+		let utxos: Vec<_> = (1..=5).map(|index| owned_utxo(10, index)).collect();
+		let plan = planner(45, 0).plan(&utxos).unwrap();
+		assert_eq!(plan.input_utxos.len(), 16);
+		assert_eq!(plan.output_utxos[1].get_amount_raw().unwrap(), 5);
+
+		let plan = planner(10, 10).plan(&[]).unwrap();
+		assert_eq!(plan.input_utxos.len(), 2);
+		assert_eq!(plan.public_amount, 10);
+		assert_eq!(plan.output_utxos[1].get_amount_raw().unwrap(), 0);
+	}
+
+	#[test]
+	fn should_fail_on_insufficient_funds() {
+		// Unindexed UTXOs can't be spent
+		let utxos = vec![
+			owned_utxo(5, 1),
+			Utxo::new(Curve::Bn254, Backend::Arkworks, 50, 1, None, None, None, None).unwrap(),
+		];
+		let error = planner(10, -1).plan(&utxos).unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InsufficientFunds);
+		let data: serde_json::Value = serde_json::from_str(&error.data.unwrap()).unwrap();
+		assert_eq!(data, serde_json::json!({ "available": "5", "required": "11" }));
+	}
+
+	#[test]
+	fn should_leave_a_lone_utxo_to_the_next_consolidation() {
+		let keys = Utxo::new(Curve::Bn254, Backend::Arkworks, 0, 1, None, None, None, None).unwrap();
+		let utxos: Vec<_> = (1..=17).map(|index| owned_utxo(index.into(), index)).collect();
+
+		let plans = consolidate(&utxos, &keys.get_secret_key().unwrap()).unwrap();
+		assert_eq!(plans.len(), 1);
+		assert_eq!(plans[0].input_utxos.len(), 16);
+	}
+
+	#[test]
+	fn should_consolidate_in_batches_of_16() {
+		let keys = Utxo::new(Curve::Bn254, Backend::Arkworks, 0, 1, None, None, None, None).unwrap();
+		let private_key = keys.get_secret_key().unwrap();
+		let mut utxos: Vec<_> = (1..=20).map(|index| owned_utxo(index.into(), index)).collect();
+		// Other chains are merged separately, unindexed UTXOs are skipped
+		utxos.push(Utxo::new(Curve::Bn254, Backend::Arkworks, 7, 2, None, None, None, Some(1)).unwrap());
+		utxos.push(Utxo::new(Curve::Bn254, Backend::Arkworks, 8, 2, None, None, None, Some(2)).unwrap());
+		utxos.push(Utxo::new(Curve::Bn254, Backend::Arkworks, 50, 1, None, None, None, None).unwrap());
+		// A lone UTXO has nothing to be merged with
+		utxos.push(Utxo::new(Curve::Bn254, Backend::Arkworks, 9, 3, None, None, None, Some(1)).unwrap());
+
+		let plans = consolidate(&utxos, &private_key).unwrap();
+		assert_eq!(plans.len(), 3);
+		assert_eq!(plans[0].input_utxos.len(), 16);
+		assert_eq!(
+			plans[0].output_utxos[0].get_amount_raw().unwrap(),
+			(1..=16u128).sum::<u128>()
+		);
+		assert_eq!(plans[1].input_utxos.len(), 16);
+		assert_eq!(
+			plans[1].output_utxos[0].get_amount_raw().unwrap(),
+			(17..=20u128).sum::<u128>()
+		);
+		assert_eq!(plans[2].chain_id, 2);
+		assert_eq!(plans[2].input_utxos.len(), 2);
+		assert_eq!(plans[2].output_utxos[0].get_amount_raw().unwrap(), 15);
+		for plan in &plans {
+			assert_eq!(plan.public_amount, 0);
+			assert_eq!(plan.output_utxos[1].get_amount_raw().unwrap(), 0);
+			assert!(plan
+				.output_utxos
+				.iter()
+				.all(|utxo| utxo.get_public_key() == keys.get_public_key()));
+		}
+
+		let mut builder = plans[1].clone().into_builder().unwrap();
+		let mut leaves = BTreeMap::new();
+		leaves.insert(1, utxos.iter().map(Utxo::get_commitment).collect());
+		builder.leaves_map(leaves).unwrap();
+		builder.roots(vec![vec![0u8; 32], vec![0u8; 32]]).unwrap();
+		builder.ext_data_hash(vec![0u8; 32]).unwrap();
+		builder.build_without_pk().unwrap();
+	}
+}
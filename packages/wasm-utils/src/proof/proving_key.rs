@@ -0,0 +1,203 @@
+//! Proving keys shared by reference between proof inputs
+use core::fmt;
+use std::sync::Arc;
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::PairingEngine;
+use ark_groth16::{create_random_proof, ProvingKey as Groth16ProvingKey};
+use ark_relations::r1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(feature = "js")]
+use js_sys::Uint8Array;
+use rand::rngs::OsRng;
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "js")]
+use crate::types::WasmCurve;
+use crate::types::{Curve, OpStatusCode, OperationError};
+
+/// Groth16 proving key of one of the supported curves, deserialized once
+///
+/// Cloning it shares the parsed key, so builders, payloads and batches hold
+/// it once however many inputs use it, and no proof parses it again.
+#[derive(Clone)]
+pub enum ProvingKey {
+	Bn254(Arc<Groth16ProvingKey<Bn254>>),
+	Bls381(Arc<Groth16ProvingKey<Bls12_381>>),
+}
+
+impl ProvingKey {
+	/// Parse the uncompressed key of `curve` written by `setup_keys`, without
+	/// checking its points
+	pub fn new(curve: Curve, bytes: &[u8]) -> Result<Self, OperationError> {
+		Ok(match curve {
+			Curve::Bn254 => ProvingKey::Bn254(parse_key(bytes, false)?),
+			Curve::Bls381 => ProvingKey::Bls381(parse_key(bytes, false)?),
+		})
+	}
+
+	/// `new` with the checked deserialization, which validates every curve
+	/// point so a corrupted key fails here instead of producing invalid
+	/// proofs
+	pub fn new_checked(curve: Curve, bytes: &[u8]) -> Result<Self, OperationError> {
+		Ok(match curve {
+			Curve::Bn254 => ProvingKey::Bn254(parse_key(bytes, true)?),
+			Curve::Bls381 => ProvingKey::Bls381(parse_key(bytes, true)?),
+		})
+	}
+
+	pub fn curve(&self) -> Curve {
+		match self {
+			ProvingKey::Bn254(_) => Curve::Bn254,
+			ProvingKey::Bls381(_) => Curve::Bls381,
+		}
+	}
+
+	/// Whether both handles share the same parsed key
+	pub fn ptr_eq(&self, other: &ProvingKey) -> bool {
+		match (self, other) {
+			(ProvingKey::Bn254(a), ProvingKey::Bn254(b)) => Arc::ptr_eq(a, b),
+			(ProvingKey::Bls381(a), ProvingKey::Bls381(b)) => Arc::ptr_eq(a, b),
+			_ => false,
+		}
+	}
+
+	pub(crate) fn bn254(&self) -> Result<&Groth16ProvingKey<Bn254>, OperationError> {
+		match self {
+			ProvingKey::Bn254(pk) => Ok(pk),
+			_ => Err(self.curve_mismatch(Curve::Bn254)),
+		}
+	}
+
+	pub(crate) fn bls381(&self) -> Result<&Groth16ProvingKey<Bls12_381>, OperationError> {
+		match self {
+			ProvingKey::Bls381(pk) => Ok(pk),
+			_ => Err(self.curve_mismatch(Curve::Bls381)),
+		}
+	}
+
+	/// Fails with `InvalidProvingKey` unless the key is of `curve`
+	pub(crate) fn check_curve(&self, curve: Curve) -> Result<(), OperationError> {
+		if self.curve() != curve {
+			return Err(self.curve_mismatch(curve));
+		}
+		Ok(())
+	}
+
+	fn curve_mismatch(&self, curve: Curve) -> OperationError {
+		let message = format!("The proving key is of the {} curve, not {}", self.curve(), curve);
+		OperationError::new_with_message(OpStatusCode::InvalidProvingKey, message)
+	}
+}
+
+fn parse_key<E: PairingEngine>(bytes: &[u8], checked: bool) -> Result<Arc<Groth16ProvingKey<E>>, OperationError> {
+	// Keys are written uncompressed by `setup_keys`
+	let pk = if checked {
+		Groth16ProvingKey::<E>::deserialize_uncompressed(bytes)
+	} else {
+		Groth16ProvingKey::<E>::deserialize_unchecked(bytes)
+	};
+	pk.map(Arc::new)
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidProvingKey, e.to_string()))
+}
+
+/// Groth16 proof of `circuit`, compressed like the proofs `arkworks-setups`
+/// makes out of key bytes
+pub(crate) fn prove<E, C>(circuit: C, pk: &Groth16ProvingKey<E>, rng: &mut OsRng) -> Result<Vec<u8>, OperationError>
+where
+	E: PairingEngine,
+	C: ConstraintSynthesizer<E::Fr>,
+{
+	let proof = create_random_proof(circuit, pk, rng)
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidProofParameters, e.to_string()))?;
+	let mut proof_bytes = Vec::new();
+	proof
+		.serialize(&mut proof_bytes)
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidProofParameters, e.to_string()))?;
+	Ok(proof_bytes)
+}
+
+// Keys are tens of MB, only their curve is worth printing
+impl fmt::Debug for ProvingKey {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "ProvingKey({})", self.curve())
+	}
+}
+
+/// A proving key parsed in the wasm memory, copied from JS once and passed by
+/// reference to `JsProofInputBuilder.setPk` and `generateProofs`
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct JsProvingKey {
+	#[wasm_bindgen(skip)]
+	pub inner: ProvingKey,
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl JsProvingKey {
+	/// The curve defaults to Bn254, with `checked` every point of the key is
+	/// validated
+	#[wasm_bindgen(constructor)]
+	pub fn new(pk: Uint8Array, curve: Option<WasmCurve>, checked: Option<bool>) -> Result<JsProvingKey, JsValue> {
+		let curve: Curve = match curve {
+			Some(curve) => JsValue::from(curve)
+				.as_string()
+				.ok_or(OpStatusCode::InvalidCurve)?
+				.parse()
+				.map_err(|_| OpStatusCode::InvalidCurve)?,
+			None => Curve::Bn254,
+		};
+		let bytes = pk.to_vec();
+		let inner = if checked.unwrap_or(false) {
+			ProvingKey::new_checked(curve, &bytes)?
+		} else {
+			ProvingKey::new(curve, &bytes)?
+		};
+		Ok(JsProvingKey { inner })
+	}
+
+	#[wasm_bindgen(getter)]
+	pub fn curve(&self) -> WasmCurve {
+		JsValue::from(self.inner.curve().to_string()).into()
+	}
+}
+
+#[cfg(feature = "js")]
+impl From<ProvingKey> for JsProvingKey {
+	fn from(inner: ProvingKey) -> Self {
+		Self { inner }
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::proof::setup_keys_with_height;
+	use crate::types::NoteProtocol;
+
+	#[test]
+	fn should_share_and_check_proving_keys() {
+		let (pk, _) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let key = ProvingKey::new_checked(Curve::Bn254, &pk).unwrap();
+		let shared = key.clone();
+		assert!(shared.ptr_eq(&key));
+		assert!(!ProvingKey::new(Curve::Bn254, &pk).unwrap().ptr_eq(&key));
+		assert_eq!(format!("{:?}", key), "ProvingKey(Bn254)");
+		assert_eq!(key.bls381().unwrap_err().code, OpStatusCode::InvalidProvingKey);
+
+		let mut corrupted = pk;
+		corrupted.truncate(corrupted.len() / 2);
+		assert_eq!(
+			ProvingKey::new_checked(Curve::Bn254, &corrupted).unwrap_err().code,
+			OpStatusCode::InvalidProvingKey
+		);
+		assert_eq!(
+			ProvingKey::new(Curve::Bn254, &corrupted).unwrap_err().code,
+			OpStatusCode::InvalidProvingKey
+		);
+	}
+}
@@ -1,20 +1,27 @@
+use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
-use ark_ff::{BigInteger, PrimeField};
 use arkworks_setups::common::{setup_keys_unchecked, verify_unchecked_raw};
 use arkworks_setups::Curve;
 use js_sys::{Array, JsString, Uint8Array};
 use rand::rngs::OsRng;
+use std::collections::BTreeMap;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
+use crate::amount::Amount;
+use crate::proof::ext_data::ExtData;
 use crate::proof::test_utils::{
 	generate_mixer_test_setup, generate_vanchor_test_setup_16_mixed_inputs,
-	generate_vanchor_test_setup_16_non_default_inputs, generate_vanchor_test_setup_2_inputs, generate_vanchor_utxo,
-	new_utxo_bn254_2_2, MixerTestSetup, VAnchorTestSetup, DECODED_SUBSTRATE_ADDRESS, MIXER_NOTE_V1_X5_5,
+	generate_vanchor_test_setup_16_non_default_inputs, generate_vanchor_test_setup_2_inputs,
+	generate_vanchor_test_setup_bls381_2_inputs, generate_vanchor_utxo, new_utxo_bn254_2_2, MixerTestSetup,
+	VAnchorTestSetup, DECODED_SUBSTRATE_ADDRESS, MIXER_NOTE_V1_X5_5,
 };
-use crate::proof::{generate_proof_js, truncate_and_pad, JsProofInputBuilder, LeavesMapInput, MTBn254X5};
-use crate::types::{Indices, Leaves};
-use crate::utxo::JsUtxo;
+use crate::proof::{
+	create_proof, generate_proof_js, truncate_and_pad, JsProofInputBuilder, JsProvingKey, LeavesMapInput, MTBn254X5,
+	ProofInputBuilder,
+};
+use crate::types::{Backend, Indices, Leaves, NoteProtocol, OpStatusCode};
+use crate::utxo::{JsUtxo, Utxo};
 use crate::{VAnchorR1CSProverBn254_30_2_2_2, DEFAULT_LEAF};
 
 const TREE_DEPTH: usize = 30;
@@ -96,7 +103,6 @@ fn should_generate_vanchor_proof_input() {
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -108,9 +114,9 @@ fn should_generate_vanchor_proof_input() {
 
 	proof_input_builder.set_input_utxos(input_utxos).unwrap();
 	proof_input_builder.set_output_utxos(output_1, output_2).unwrap();
-	let proof_builder = proof_input_builder.build_js().unwrap();
+	let proof_builder = proof_input_builder.build_without_pk().unwrap();
 	let vanchor_proof_input_payload = proof_builder.inner.vanchor_input().unwrap();
-	assert_eq!(vanchor_proof_input_payload.public_amount, 10);
+	assert_eq!(vanchor_proof_input_payload.public_amount, Amount::from(10i128));
 	assert_eq!(vanchor_proof_input_payload.chain_id, 0);
 	assert_eq!(vanchor_proof_input_payload.indices, [0u64, 0u64].to_vec());
 	assert_eq!(
@@ -122,7 +128,7 @@ fn should_generate_vanchor_proof_input() {
 	assert_eq!(vanchor_proof_input_payload.exponentiation, 5);
 	assert_eq!(vanchor_proof_input_payload.width, 5);
 	assert_eq!(vanchor_proof_input_payload.curve.to_string(), "Bn254");
-	assert_eq!(hex::encode(vanchor_proof_input_payload.pk), "0000");
+	assert!(vanchor_proof_input_payload.pk.is_none());
 }
 
 #[wasm_bindgen_test]
@@ -156,7 +162,6 @@ fn should_fail_to_generate_vanchor_proof_input_with_invalid_amounts() {
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -210,7 +215,6 @@ fn should_fail_to_generate_vanchor_proof_input_with_inconsistent_notes_chain_id(
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -266,7 +270,6 @@ fn should_fail_to_generate_vanchor_proof_input_with_inconsistent_notes_indices()
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -292,6 +295,119 @@ fn should_fail_to_generate_vanchor_proof_input_with_inconsistent_notes_indices()
 	assert_eq!(message, expected_error_message)
 }
 
+fn vanchor_builder_with_ext_data(in_amount: u128, out_amounts: [u128; 2], ext_data: ExtData) -> ProofInputBuilder {
+	let utxo = |amount, index| {
+		Utxo::new(
+			crate::types::Curve::Bn254,
+			Backend::Arkworks,
+			amount,
+			0,
+			None,
+			None,
+			None,
+			index,
+		)
+		.unwrap()
+	};
+	let input_utxos = vec![utxo(in_amount, Some(0)), utxo(0, Some(0))];
+	let output_utxos = [utxo(out_amounts[0], None), utxo(out_amounts[1], None)];
+	let mut leaves = BTreeMap::new();
+	leaves.insert(0, vec![input_utxos[0].get_commitment()]);
+
+	let mut builder = ProofInputBuilder::new(NoteProtocol::VAnchor);
+	builder.chain_id(0).unwrap();
+	builder.leaves_map(leaves).unwrap();
+	builder.roots(vec![vec![0u8; 32], vec![0u8; 32]]).unwrap();
+	builder.leaf_indices(vec![0, 0]).unwrap();
+	builder.set_input_utxos(input_utxos).unwrap();
+	builder.set_output_utxos(output_utxos).unwrap();
+	builder.ext_data(ext_data).unwrap();
+	builder
+}
+
+fn ext_data(ext_amount: i128, fee: u128) -> ExtData {
+	ExtData::new(
+		vec![1u8; 20],
+		vec![2u8; 20],
+		ext_amount,
+		fee,
+		0,
+		vec![3u8; 20],
+		vec![],
+		vec![],
+	)
+}
+
+#[wasm_bindgen_test]
+fn should_derive_ext_data_hash_and_public_amount_from_ext_data() {
+	// Withdraw 10 out of 30 and pay a fee of 2
+	let ext_data = ext_data(-10, 2);
+	let hash = ext_data.get_hash();
+	let payload = vanchor_builder_with_ext_data(30, [18, 0], ext_data)
+		.build_without_pk()
+		.unwrap()
+		.vanchor_input()
+		.unwrap();
+	assert_eq!(payload.ext_data_hash, hash);
+	assert_eq!(payload.public_amount, Amount::from(-12i128));
+}
+
+#[wasm_bindgen_test]
+fn should_fail_with_public_amount_not_matching_ext_data() {
+	let mut builder = vanchor_builder_with_ext_data(30, [18, 0], ext_data(-10, 2));
+	builder.public_amount(Amount::from(-10i128)).unwrap();
+	let error = builder.build_without_pk().unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidPublicAmount);
+	assert_eq!(
+		error.error_message,
+		"Public amount -10 doesn't match ext amount -10 minus fee 2"
+	);
+	let data: serde_json::Value = serde_json::from_str(&error.data.unwrap()).unwrap();
+	assert_eq!(
+		data,
+		serde_json::json!({ "publicAmount": "-10", "extAmount": "-10", "fee": "2" })
+	);
+
+	let mut builder = vanchor_builder_with_ext_data(30, [18, 0], ext_data(-10, 2));
+	builder.ext_data_hash(vec![0u8; 32]).unwrap();
+	let error = builder.build_without_pk().unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidExtDataHash);
+}
+
+#[wasm_bindgen_test]
+fn should_fail_with_uncovered_fee() {
+	let error = vanchor_builder_with_ext_data(5, [0, 0], ext_data(-3, 4))
+		.build_without_pk()
+		.unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidFee);
+	let data: serde_json::Value = serde_json::from_str(&error.data.unwrap()).unwrap();
+	assert_eq!(data, serde_json::json!({ "fee": "4", "available": "2" }));
+}
+
+#[wasm_bindgen_test]
+fn should_build_with_utxo_amounts_beyond_i128() {
+	// 18 decimals tokens with a large total supply
+	let amount = u128::MAX - 1;
+	let payload = vanchor_builder_with_ext_data(amount, [amount - 10, 8], ext_data(-2, 0))
+		.build_without_pk()
+		.unwrap()
+		.vanchor_input()
+		.unwrap();
+	assert_eq!(payload.public_amount, Amount::from(-2i128));
+
+	let error = vanchor_builder_with_ext_data(amount, [amount, 1], ext_data(0, 0))
+		.build_without_pk()
+		.unwrap_err();
+	assert_eq!(
+		error.error_message,
+		format!(
+			"Output amount and input amount don't match input({}) != output({})",
+			amount,
+			Amount::from(amount) + Amount::from(1u128)
+		)
+	);
+}
+
 #[wasm_bindgen_test]
 fn should_fail_to_proof_with_1_input() {
 	let utxo = generate_vanchor_utxo(30, 0, Some(0));
@@ -322,7 +438,6 @@ fn should_fail_to_proof_with_1_input() {
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -335,7 +450,7 @@ fn should_fail_to_proof_with_1_input() {
 	proof_input_builder.set_input_utxos(input_utxos).unwrap();
 	proof_input_builder.set_output_utxos(output_1, output_2).unwrap();
 
-	let proof_input = proof_input_builder.build_js().unwrap();
+	let proof_input = proof_input_builder.build_without_pk().unwrap();
 	let proof = generate_proof_js(proof_input);
 	let mut message = "".to_string();
 	if let Err(e) = proof {
@@ -380,7 +495,6 @@ fn should_fail_to_proof_with_3_inputs() {
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -399,7 +513,7 @@ fn should_fail_to_proof_with_3_inputs() {
 	proof_input_builder.set_input_utxos(input_utxos).unwrap();
 	proof_input_builder.set_output_utxos(output_1, output_2).unwrap();
 
-	let proof_builder = proof_input_builder.build_js().unwrap();
+	let proof_builder = proof_input_builder.build_without_pk().unwrap();
 	let proof = generate_proof_js(proof_builder);
 	let mut message = "".to_string();
 	if let Err(e) = proof {
@@ -410,6 +524,110 @@ fn should_fail_to_proof_with_3_inputs() {
 			.to_string();
 	assert_eq!(message, expected_error_message)
 }
+#[wasm_bindgen_test]
+fn should_pad_3_inputs_to_16_inputs() {
+	let utxos: Vec<_> = (1..=3).map(|index| generate_vanchor_utxo(10, 0, Some(index))).collect();
+	let protocol = JsValue::from("vanchor").into();
+	let mut proof_input_builder = JsProofInputBuilder::new(protocol).unwrap();
+
+	let leaves: Array = utxos.iter().map(|utxo| utxo.commitment()).collect();
+	let mut leaves_map = LeavesMapInput::new();
+	leaves_map
+		.set_chain_leaves(0, Leaves::from(JsValue::from(leaves)))
+		.unwrap();
+
+	let indices: Array = vec![JsValue::from("1"), JsValue::from("2"), JsValue::from("3")]
+		.into_iter()
+		.collect();
+	let roots: Array = vec![
+		Uint8Array::from([0u8; 32].to_vec().as_slice()),
+		Uint8Array::from([0u8; 32].to_vec().as_slice()),
+	]
+	.into_iter()
+	.collect();
+	proof_input_builder.set_leaves_map(leaves_map).unwrap();
+	proof_input_builder
+		.set_indices(Indices::from(JsValue::from(indices)))
+		.unwrap();
+	proof_input_builder
+		.set_roots(Leaves::from(JsValue::from(roots)))
+		.unwrap();
+
+	proof_input_builder.public_amount(JsString::from("10")).unwrap();
+	proof_input_builder.chain_id(JsString::from("0")).unwrap();
+	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
+
+	let input_utxos: Array = utxos.iter().cloned().map(JsValue::from).collect();
+	let output_1 = new_utxo_bn254_2_2(crate::types::Curve::Bn254, 20, 0);
+	let output_2 = new_utxo_bn254_2_2(crate::types::Curve::Bn254, 20, 0);
+
+	proof_input_builder.set_input_utxos(input_utxos).unwrap();
+	proof_input_builder.set_output_utxos(output_1, output_2).unwrap();
+	proof_input_builder.set_pad_inputs(true).unwrap();
+
+	let payload = proof_input_builder.build().unwrap().vanchor_input().unwrap();
+	assert_eq!(payload.input_utxos.len(), 16);
+	assert!(payload.input_utxos[3..]
+		.iter()
+		.all(|utxo| utxo.get_amount_raw().unwrap() == 0));
+	assert_eq!(payload.indices[..3], [1, 2, 3]);
+	assert!(payload.indices[3..].iter().all(|index| *index == 0));
+}
+
+#[wasm_bindgen_test]
+fn generate_vanchor_proof_with_1_padded_input() {
+	let utxo = generate_vanchor_utxo(10, 0, Some(0));
+	let protocol = JsValue::from("vanchor").into();
+	let mut proof_input_builder = JsProofInputBuilder::new(protocol).unwrap();
+
+	let leaves: Array = vec![utxo.commitment()].into_iter().collect();
+	let mut leaves_map = LeavesMapInput::new();
+	leaves_map
+		.set_chain_leaves(0, Leaves::from(JsValue::from(leaves.clone())))
+		.unwrap();
+	let tree = MTBn254X5::new(Leaves::from(JsValue::from(leaves)), JsString::from("0"), None).unwrap();
+
+	let indices: Array = vec![JsValue::from("0")].into_iter().collect();
+	let roots: Array = vec![
+		Uint8Array::from(tree.get_root().as_slice()),
+		Uint8Array::from([0u8; 32].to_vec().as_slice()),
+	]
+	.into_iter()
+	.collect();
+	proof_input_builder.set_leaves_map(leaves_map).unwrap();
+	proof_input_builder
+		.set_indices(Indices::from(JsValue::from(indices)))
+		.unwrap();
+	proof_input_builder
+		.set_roots(Leaves::from(JsValue::from(roots)))
+		.unwrap();
+
+	proof_input_builder.public_amount(JsString::from("10")).unwrap();
+	proof_input_builder.chain_id(JsString::from("0")).unwrap();
+	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
+
+	let input_utxos: Array = vec![JsValue::from(utxo)].into_iter().collect();
+	let output_1 = new_utxo_bn254_2_2(crate::types::Curve::Bn254, 10, 0);
+	let output_2 = new_utxo_bn254_2_2(crate::types::Curve::Bn254, 10, 0);
+
+	proof_input_builder.set_input_utxos(input_utxos).unwrap();
+	proof_input_builder.set_output_utxos(output_1, output_2).unwrap();
+	proof_input_builder.set_pad_inputs(true).unwrap();
+
+	let c = VAnchorR1CSProverBn254_30_2_2_2::setup_random_circuit(Curve::Bn254, DEFAULT_LEAF, &mut OsRng).unwrap();
+	let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).unwrap();
+	proof_input_builder
+		.set_pk(&JsProvingKey::new(Uint8Array::from(pk.as_slice()), None, None).unwrap())
+		.unwrap();
+
+	let proof_input = proof_input_builder.build_js().unwrap();
+	let proof = generate_proof_js(proof_input).unwrap().vanchor_proof().unwrap();
+	assert_eq!(proof.input_utxos.len(), 2);
+	let is_valid_proof = verify_unchecked_raw::<Bn254>(&proof.public_inputs, &vk, &proof.proof).unwrap();
+
+	assert!(is_valid_proof);
+}
+
 #[wasm_bindgen_test]
 fn generate_vanchor_proof_2_inputs() {
 	let VAnchorTestSetup {
@@ -426,6 +644,41 @@ fn generate_vanchor_proof_2_inputs() {
 	assert!(is_valid_proof);
 }
 
+#[wasm_bindgen_test]
+fn generate_vanchor_proof_bls381_2_inputs() {
+	let VAnchorTestSetup {
+		proof_input_builder,
+		roots_raw: _,
+		notes: _,
+		leaf_index: _,
+		vk,
+	} = generate_vanchor_test_setup_bls381_2_inputs();
+	let proof_input = proof_input_builder.build_js().unwrap();
+	let proof = generate_proof_js(proof_input).unwrap().vanchor_proof().unwrap();
+	let is_valid_proof = verify_unchecked_raw::<Bls12_381>(&proof.public_inputs, &vk, &proof.proof).unwrap();
+
+	assert!(is_valid_proof);
+}
+
+#[wasm_bindgen_test]
+fn should_fail_to_prove_bn254_utxos_with_bls381() {
+	let VAnchorTestSetup {
+		mut proof_input_builder,
+		..
+	} = generate_vanchor_test_setup_2_inputs();
+	proof_input_builder.inner.curve(crate::types::Curve::Bls381).unwrap();
+	let error = proof_input_builder.inner.clone().build().unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidProvingKey);
+
+	// Without the Bn254 key, the UTXOs are the ones of the wrong curve
+	if let ProofInputBuilder::VAnchor(input) = &mut proof_input_builder.inner {
+		input.pk = None;
+	}
+	let proof_input = proof_input_builder.inner.build_without_pk().unwrap();
+	let error = create_proof(proof_input, &mut OsRng).unwrap_err();
+	assert_eq!(error.code, OpStatusCode::InvalidCurve);
+}
+
 #[wasm_bindgen_test]
 fn generate_vanchor_proof_16_inputs() {
 	let VAnchorTestSetup {
@@ -480,10 +733,10 @@ fn should_generate_a_valid_proof_for_already_used_merkle_tree() {
 		.set_chain_leaves(0, Leaves::from(JsValue::from(leaves.clone())))
 		.unwrap();
 	// Create the tree
-	let tree = MTBn254X5::new(Leaves::from(JsValue::from(leaves.clone())), JsString::from("0")).unwrap();
+	let tree = MTBn254X5::new(Leaves::from(JsValue::from(leaves.clone())), JsString::from("0"), None).unwrap();
 	let indices: Array = vec![JsValue::from("16"), JsValue::from("0")].into_iter().collect();
 	let roots: Array = vec![
-		Uint8Array::from(tree.inner.root().into_repr().to_bytes_be().as_slice()),
+		Uint8Array::from(tree.get_root().as_slice()),
 		Uint8Array::from([0u8; 32].to_vec().as_slice()),
 	]
 	.into_iter()
@@ -497,7 +750,6 @@ fn should_generate_a_valid_proof_for_already_used_merkle_tree() {
 		.set_roots(Leaves::from(JsValue::from(roots)))
 		.unwrap();
 
-	proof_input_builder.set_pk(JsString::from("0000")).unwrap();
 	proof_input_builder.public_amount(JsString::from("10")).unwrap();
 	proof_input_builder.chain_id(JsString::from("0")).unwrap();
 	proof_input_builder.set_ext_data_hash(JsString::from("1111")).unwrap();
@@ -515,7 +767,9 @@ fn should_generate_a_valid_proof_for_already_used_merkle_tree() {
 	let c = VAnchorR1CSProverBn254_30_2_2_2::setup_random_circuit(Curve::Bn254, DEFAULT_LEAF, &mut OsRng).unwrap();
 	let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).unwrap();
 
-	proof_input_builder.set_pk(JsString::from(hex::encode(pk))).unwrap();
+	proof_input_builder
+		.set_pk(&JsProvingKey::new(Uint8Array::from(pk.as_slice()), None, None).unwrap())
+		.unwrap();
 
 	let proof_input = proof_input_builder.build_js().unwrap();
 
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use ark_bls12_381::Fr as Bls381Fr;
 use ark_bn254::{Bn254, Fr as Bn254Fr};
 use ark_ff::{BigInteger, PrimeField, Zero};
 use arkworks_native_gadgets::poseidon::Poseidon;
@@ -9,10 +10,13 @@ use js_sys::{Array, JsString, Uint8Array};
 use rand::rngs::OsRng;
 use wasm_bindgen::prelude::*;
 
+use crate::amount::Amount;
 use crate::note::JsNote;
-use crate::proof::{JsProofInputBuilder, LeavesMapInput, ProofInputBuilder, VAnchorProofInput};
-use crate::types::{Backend, Curve, Indices, Leaves, Protocol, WasmCurve, BE};
-use crate::utxo::JsUtxo;
+use crate::proof::{
+	setup_keys, JsProofInputBuilder, JsProvingKey, LeavesMapInput, ProofInputBuilder, ProvingKey, VAnchorProofInput,
+};
+use crate::types::{Backend, Curve, Indices, Leaves, NoteProtocol, Protocol, WasmCurve, BE};
+use crate::utxo::{JsUtxo, Utxo};
 use crate::{
 	MixerR1CSProverBn254_30, VAnchorR1CSProverBn254_30_2_16_2, VAnchorR1CSProverBn254_30_2_2_2, DEFAULT_LEAF,
 	TREE_HEIGHT,
@@ -62,7 +66,7 @@ pub fn generate_mixer_test_setup(
 	let (c, ..) = MixerR1CSProverBn254_30::setup_random_circuit(ArkCurve::Bn254, DEFAULT_LEAF, &mut OsRng).unwrap();
 	let (pk, vk) = setup_keys_unchecked::<Bn254, _, _>(c, &mut OsRng).unwrap();
 	let index = 0;
-	let note = JsNote::js_deserialize(JsString::from(note)).unwrap();
+	let note = JsNote::js_deserialize(JsString::from(note), None).unwrap();
 	let leaf = note.get_leaf_commitment().unwrap();
 	let leaf_bytes: Vec<u8> = leaf.to_vec();
 
@@ -81,7 +85,9 @@ pub fn generate_mixer_test_setup(
 		.set_recipient(JsString::from(recipient_decoded_ss58))
 		.unwrap();
 
-	js_builder.set_pk(JsString::from(hex::encode(&pk))).unwrap();
+	js_builder
+		.set_pk(&JsProvingKey::new(Uint8Array::from(pk.as_slice()), None, None).unwrap())
+		.unwrap();
 
 	js_builder.set_metadata_from_note(&note).unwrap();
 
@@ -188,7 +194,9 @@ pub fn generate_vanchor_test_js_setup() -> VAnchorTestSetup {
 
 	let mut js_builder = JsProofInputBuilder::new(JsValue::from("vanchor").into()).unwrap();
 
-	js_builder.set_pk(JsString::from(hex::encode(pk))).unwrap();
+	js_builder
+		.set_pk(&JsProvingKey::new(Uint8Array::from(pk.as_slice()), None, None).unwrap())
+		.unwrap();
 	js_builder.set_roots(Leaves::from(JsValue::from(roots_array))).unwrap();
 	// leaves
 	let mut leaves_map = LeavesMapInput::new();
@@ -231,7 +239,7 @@ pub fn generate_vanchor_test_setup_2_inputs() -> VAnchorTestSetup {
 
 	let params3 = setup_params::<Bn254Fr>(curve, 5, 3);
 	let tree_hasher = Poseidon::new(params3);
-	let public_amount = 10;
+	let public_amount = Amount::from(10i128);
 	let in_amount = 5;
 	let in_chain_id = 0;
 	let in_amount_fr = Bn254Fr::from(in_amount);
@@ -279,8 +287,8 @@ pub fn generate_vanchor_test_setup_2_inputs() -> VAnchorTestSetup {
 	proof_builder.leaves_map(leave_map).unwrap();
 	proof_builder
 		.set_input_utxos(vec![
-			JsUtxo::new_from_bn254_utxo(in_utxo1),
-			JsUtxo::new_from_bn254_utxo(in_utxo2),
+			Utxo::new_from_bn254_utxo(in_utxo1),
+			Utxo::new_from_bn254_utxo(in_utxo2),
 		])
 		.unwrap();
 
@@ -294,12 +302,62 @@ pub fn generate_vanchor_test_setup_2_inputs() -> VAnchorTestSetup {
 	proof_builder.curve(Curve::Bn254).unwrap();
 	proof_builder.roots(in_root_set).unwrap();
 	proof_builder
-		.set_output_utxos([
-			JsUtxo::new_from_bn254_utxo(output_1),
-			JsUtxo::new_from_bn254_utxo(output_2),
-		])
+		.set_output_utxos([Utxo::new_from_bn254_utxo(output_1), Utxo::new_from_bn254_utxo(output_2)])
 		.unwrap();
-	proof_builder.pk(pk).unwrap();
+	proof_builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
+
+	VAnchorTestSetup {
+		proof_input_builder: JsProofInputBuilder { inner: proof_builder },
+		notes: vec![],
+		roots_raw: vec![],
+		vk,
+		leaf_index: 0,
+	}
+}
+
+pub fn generate_vanchor_test_setup_bls381_2_inputs() -> VAnchorTestSetup {
+	let chain_id = 0;
+	let in_utxos = vec![
+		Utxo::new(Curve::Bls381, Backend::Arkworks, 5, chain_id, None, None, None, Some(0)).unwrap(),
+		Utxo::new(Curve::Bls381, Backend::Arkworks, 5, chain_id, None, None, None, Some(1)).unwrap(),
+	];
+	let output_1 = Utxo::new(Curve::Bls381, Backend::Arkworks, 10, chain_id, None, None, None, None).unwrap();
+	let output_2 = Utxo::new(Curve::Bls381, Backend::Arkworks, 10, chain_id, None, None, None, None).unwrap();
+
+	let params3 = setup_params::<Bls381Fr>(ArkCurve::Bls381, 5, 3);
+	let tree_hasher = Poseidon::new(params3);
+	let leaves: Vec<_> = in_utxos.iter().map(|utxo| utxo.get_commitment()).collect();
+	let leaves_fr: Vec<_> = leaves
+		.iter()
+		.map(|leaf| Bls381Fr::from_be_bytes_mod_order(leaf))
+		.collect();
+	let (tree, _) = setup_tree_and_create_path::<Bls381Fr, Poseidon<Bls381Fr>, TREE_HEIGHT>(
+		&tree_hasher,
+		&leaves_fr,
+		0,
+		&DEFAULT_LEAF,
+	)
+	.unwrap();
+	let root = tree.root().into_repr().to_bytes_be();
+
+	let mut leave_map: BTreeMap<u64, Vec<Vec<u8>>> = BTreeMap::new();
+	leave_map.insert(chain_id, leaves);
+	let (pk, vk) = setup_keys(NoteProtocol::VAnchor, Curve::Bls381, 2, 2, 2).unwrap();
+
+	let mut proof_builder = ProofInputBuilder::VAnchor(Box::new(VAnchorProofInput::default()));
+	proof_builder.public_amount(Amount::from(10i128)).unwrap();
+	proof_builder.ext_data_hash([1u8; 32].to_vec()).unwrap();
+	proof_builder.leaf_indices(vec![0, 1]).unwrap();
+	proof_builder.leaves_map(leave_map).unwrap();
+	proof_builder.set_input_utxos(in_utxos).unwrap();
+	proof_builder.exponentiation(5).unwrap();
+	proof_builder.width(5).unwrap();
+	proof_builder.chain_id(chain_id.into()).unwrap();
+	proof_builder.backend(Backend::Arkworks).unwrap();
+	proof_builder.curve(Curve::Bls381).unwrap();
+	proof_builder.roots(vec![root.clone(), root]).unwrap();
+	proof_builder.set_output_utxos([output_1, output_2]).unwrap();
+	proof_builder.pk(ProvingKey::new(Curve::Bls381, &pk).unwrap()).unwrap();
 
 	VAnchorTestSetup {
 		proof_input_builder: JsProofInputBuilder { inner: proof_builder },
@@ -317,7 +375,7 @@ pub fn generate_vanchor_test_setup_16_non_default_inputs() -> VAnchorTestSetup {
 
 	let params3 = setup_params::<Bn254Fr>(curve, 5, 3);
 	let tree_hasher = Poseidon::new(params3);
-	let public_amount = 10;
+	let public_amount = Amount::from(10i128);
 	let in_amount = 10u128;
 	let in_chain_id = 0;
 	let in_amount_fr = Bn254Fr::from(in_amount);
@@ -371,7 +429,7 @@ pub fn generate_vanchor_test_setup_16_non_default_inputs() -> VAnchorTestSetup {
 			inputs
 				.clone()
 				.into_iter()
-				.map(|u| JsUtxo::new_from_bn254_utxo(u))
+				.map(|u| Utxo::new_from_bn254_utxo(u))
 				.collect(),
 		)
 		.unwrap();
@@ -386,12 +444,9 @@ pub fn generate_vanchor_test_setup_16_non_default_inputs() -> VAnchorTestSetup {
 	proof_builder.curve(Curve::Bn254).unwrap();
 	proof_builder.roots(in_root_set).unwrap();
 	proof_builder
-		.set_output_utxos([
-			JsUtxo::new_from_bn254_utxo(output_1),
-			JsUtxo::new_from_bn254_utxo(output_2),
-		])
+		.set_output_utxos([Utxo::new_from_bn254_utxo(output_1), Utxo::new_from_bn254_utxo(output_2)])
 		.unwrap();
-	proof_builder.pk(pk).unwrap();
+	proof_builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
 
 	VAnchorTestSetup {
 		proof_input_builder: JsProofInputBuilder { inner: proof_builder },
@@ -409,7 +464,7 @@ pub fn generate_vanchor_test_setup_16_mixed_inputs() -> VAnchorTestSetup {
 
 	let params3 = setup_params::<Bn254Fr>(curve, 5, 3);
 	let tree_hasher = Poseidon::new(params3);
-	let public_amount = 10;
+	let public_amount = Amount::from(10i128);
 	let in_amount = 10u128;
 	let in_chain_id = 0;
 	let in_amount_fr = Bn254Fr::from(in_amount);
@@ -480,7 +535,7 @@ pub fn generate_vanchor_test_setup_16_mixed_inputs() -> VAnchorTestSetup {
 			inputs
 				.clone()
 				.into_iter()
-				.map(|u| JsUtxo::new_from_bn254_utxo(u))
+				.map(|u| Utxo::new_from_bn254_utxo(u))
 				.collect(),
 		)
 		.unwrap();
@@ -494,12 +549,9 @@ pub fn generate_vanchor_test_setup_16_mixed_inputs() -> VAnchorTestSetup {
 	proof_builder.backend(Backend::Arkworks).unwrap();
 	proof_builder.curve(Curve::Bn254).unwrap();
 	proof_builder.roots(in_root_set).unwrap();
-	proof_builder.pk(pk).unwrap();
+	proof_builder.pk(ProvingKey::new(Curve::Bn254, &pk).unwrap()).unwrap();
 	proof_builder
-		.set_output_utxos([
-			JsUtxo::new_from_bn254_utxo(output_1),
-			JsUtxo::new_from_bn254_utxo(output_2),
-		])
+		.set_output_utxos([Utxo::new_from_bn254_utxo(output_1), Utxo::new_from_bn254_utxo(output_2)])
 		.unwrap();
 
 	VAnchorTestSetup {
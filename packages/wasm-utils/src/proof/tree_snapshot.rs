@@ -0,0 +1,447 @@
+//! Snapshots of `MTBn254X5`, to persist a tree and sync only the leaves
+//! inserted since
+use std::collections::BTreeMap;
+
+use ark_bn254::Fr as Bn254Fr;
+use ark_ff::{BigInteger, PrimeField};
+use arkworks_native_gadgets::merkle_tree::SparseMerkleTree;
+use arkworks_native_gadgets::poseidon::{FieldHasher, Poseidon};
+use arkworks_setups::common::setup_params;
+use arkworks_setups::Curve as ArkCurve;
+#[cfg(feature = "js")]
+use js_sys::{Array, JsString, Uint8Array};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::proof::{MTBn254X5, MerklePath, MerkleTreeBn254};
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, hex_bytes_list, Serializable};
+#[cfg(feature = "js")]
+use crate::types::{Leaves, Uint8Arrayx32};
+use crate::types::{OpStatusCode, OperationError};
+use crate::{check_tree_height, DEFAULT_LEAF};
+
+type TreeBn254<const H: usize> = SparseMerkleTree<Bn254Fr, Poseidon<Bn254Fr>, H>;
+
+/// The leaves of a tree, the filled subtrees left of its last leaf and its
+/// root, as 32 bytes big-endian hashes
+///
+/// `filled_subtrees[level]` is the left sibling of the node above the last
+/// leaf at `level` when that node is a right child, the empty subtree
+/// otherwise. Restoring a snapshot only hashes the path of the last leaf, the
+/// nodes above the other leaves are hashed when a path needs them.
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MerkleTreeSnapshot {
+	pub(crate) height: usize,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) leaves: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) filled_subtrees: Vec<Vec<u8>>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) root: Vec<u8>,
+}
+
+impl Serializable for MerkleTreeSnapshot {}
+
+impl MerkleTreeSnapshot {
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	pub fn leaves(&self) -> &[Vec<u8>] {
+		&self.leaves
+	}
+
+	pub fn root(&self) -> &[u8] {
+		&self.root
+	}
+}
+
+fn poseidon() -> Poseidon<Bn254Fr> {
+	Poseidon::new(setup_params::<Bn254Fr>(ArkCurve::Bn254, 5, 3))
+}
+
+fn first_leaf<const H: usize>() -> u64 {
+	(1 << H) - 1
+}
+
+/// Level order index of the node at `position` of `level`, the leaves being
+/// the level `0`
+fn node_index<const H: usize>(level: usize, position: u64) -> u64 {
+	(1 << (H - level)) - 1 + position
+}
+
+fn hash_error(e: impl ToString) -> OperationError {
+	OperationError::new_with_message(OpStatusCode::InvalidLeaves, e.to_string())
+}
+
+fn empty_tree<const H: usize>() -> Result<TreeBn254<H>, OperationError> {
+	TreeBn254::<H>::new(&BTreeMap::new(), &poseidon(), &DEFAULT_LEAF).map_err(hash_error)
+}
+
+/// Hash of the empty subtree of every level, from the empty leaf up
+fn empty_hashes<const H: usize>() -> Result<Vec<Bn254Fr>, OperationError> {
+	// The siblings of the first leaf of an empty tree
+	let path = empty_tree::<H>()?.generate_membership_proof(0);
+	Ok(path.path.iter().map(|(node, _)| *node).collect())
+}
+
+/// The node at `position` of `level`, hashed from the leaves below it when
+/// the tree doesn't hold it
+fn node<const H: usize>(
+	tree: &TreeBn254<H>,
+	empty_hashes: &[Bn254Fr],
+	hasher: &Poseidon<Bn254Fr>,
+	level: usize,
+	position: u64,
+) -> Result<Bn254Fr, OperationError> {
+	if let Some(node) = tree.tree.get(&node_index::<H>(level, position)) {
+		return Ok(*node);
+	}
+	let first = first_leaf::<H>() + (position << level);
+	if level == 0 || tree.tree.range(first..first + (1 << level)).next().is_none() {
+		return Ok(empty_hashes[level]);
+	}
+	let left = node(tree, empty_hashes, hasher, level - 1, 2 * position)?;
+	let right = node(tree, empty_hashes, hasher, level - 1, 2 * position + 1)?;
+	hasher.hash_two(&left, &right).map_err(hash_error)
+}
+
+/// Path of the leaf `index`, `index` has to be in the tree
+pub(crate) fn path<const H: usize>(tree: &TreeBn254<H>, index: u64) -> Result<MerklePath, OperationError> {
+	let empty_hashes = empty_hashes::<H>()?;
+	let hasher = poseidon();
+	let mut path_elements = Vec::with_capacity(H);
+	let mut path_indices = Vec::with_capacity(H);
+	for level in 0..H {
+		let position = index >> level;
+		let sibling = node(tree, &empty_hashes, &hasher, level, position ^ 1)?;
+		path_elements.push(sibling.into_repr().to_bytes_be());
+		path_indices.push((position & 1) as u8);
+	}
+	MerklePath::new(path_elements, path_indices, tree.root().into_repr().to_bytes_be())
+}
+
+fn snapshot<const H: usize>(tree: &TreeBn254<H>) -> Result<MerkleTreeSnapshot, OperationError> {
+	let empty_hashes = empty_hashes::<H>()?;
+	let hasher = poseidon();
+	let leaf_count = leaf_count(tree);
+	let leaves = (0..leaf_count)
+		.map(|index| node(tree, &empty_hashes, &hasher, 0, index).map(|leaf| leaf.into_repr().to_bytes_be()))
+		.collect::<Result<_, _>>()?;
+	let mut filled_subtrees = Vec::with_capacity(H);
+	for level in 0..H {
+		let position = leaf_count.saturating_sub(1) >> level;
+		let filled = match position & 1 {
+			1 => node(tree, &empty_hashes, &hasher, level, position - 1)?,
+			_ => empty_hashes[level],
+		};
+		filled_subtrees.push(filled.into_repr().to_bytes_be());
+	}
+	Ok(MerkleTreeSnapshot {
+		height: H,
+		leaves,
+		filled_subtrees,
+		root: tree.root().into_repr().to_bytes_be(),
+	})
+}
+
+fn restore<const H: usize>(snapshot: &MerkleTreeSnapshot) -> Result<TreeBn254<H>, OperationError> {
+	let invalid = |message: String| OperationError::new_with_message(OpStatusCode::InvalidTreeSnapshot, message);
+	if snapshot.leaves.len() as u64 > 1 << H || snapshot.filled_subtrees.len() != H {
+		return Err(invalid(format!(
+			"{} leaves and {} filled subtrees don't fit a tree of height {}",
+			snapshot.leaves.len(),
+			snapshot.filled_subtrees.len(),
+			H
+		)));
+	}
+	if let Some(node) = snapshot
+		.leaves
+		.iter()
+		.chain(&snapshot.filled_subtrees)
+		.find(|node| node.len() != 32)
+	{
+		return Err(invalid(format!("Node {} isn't 32 bytes", hex::encode(node))));
+	}
+
+	let mut tree = empty_tree::<H>()?;
+	let empty_hashes = empty_hashes::<H>()?;
+	for (index, leaf) in (first_leaf::<H>()..).zip(&snapshot.leaves) {
+		tree.tree.insert(index, Bn254Fr::from_be_bytes_mod_order(leaf));
+	}
+	// Only the path of the last leaf is hashed, with the filled subtrees on its
+	// left, later leaves are inserted along the same path
+	if let Some(last) = (snapshot.leaves.len() as u64).checked_sub(1) {
+		let hasher = poseidon();
+		let mut node = tree.tree[&(first_leaf::<H>() + last)];
+		for level in 0..H {
+			let position = last >> level;
+			let (left, right) = match position & 1 {
+				1 => {
+					let filled = Bn254Fr::from_be_bytes_mod_order(&snapshot.filled_subtrees[level]);
+					tree.tree.insert(node_index::<H>(level, position - 1), filled);
+					(filled, node)
+				}
+				_ => (node, empty_hashes[level]),
+			};
+			node = hasher.hash_two(&left, &right).map_err(hash_error)?;
+			tree.tree.insert(node_index::<H>(level + 1, position >> 1), node);
+		}
+	}
+
+	let root = tree.root().into_repr().to_bytes_be();
+	if root != snapshot.root {
+		return Err(invalid(format!(
+			"The last leaf and the filled subtrees hash to the root {}, not the root {} of the snapshot",
+			hex::encode(&root),
+			hex::encode(&snapshot.root)
+		)));
+	}
+	Ok(tree)
+}
+
+fn leaf_count<const H: usize>(tree: &TreeBn254<H>) -> u64 {
+	tree.tree
+		.range(first_leaf::<H>()..)
+		.next_back()
+		.map_or(0, |(index, _)| index - first_leaf::<H>() + 1)
+}
+
+fn sync_from<const H: usize>(
+	tree: &mut TreeBn254<H>,
+	from_index: u64,
+	leaves: &[Vec<u8>],
+) -> Result<(), OperationError> {
+	let leaf_count = leaf_count(tree);
+	if from_index > leaf_count {
+		let message = format!(
+			"The tree has {} leaves, leaves from {} would leave a gap",
+			leaf_count, from_index
+		);
+		return Err(OperationError::new_with_message(OpStatusCode::InvalidIndices, message));
+	}
+	if from_index + leaves.len() as u64 > 1 << H {
+		let message = format!(
+			"{} leaves from {} don't fit a tree of height {}",
+			leaves.len(),
+			from_index,
+			H
+		);
+		return Err(OperationError::new_with_message(OpStatusCode::InvalidIndices, message));
+	}
+
+	let mut new_leaves = BTreeMap::new();
+	for (index, leaf) in (from_index..).zip(leaves) {
+		let leaf = Bn254Fr::from_be_bytes_mod_order(leaf);
+		if index >= leaf_count {
+			new_leaves.insert(index as u32, leaf);
+		} else if tree.tree.get(&(first_leaf::<H>() + index)) != Some(&leaf) {
+			// Leaves already synced have to match, the tree may be of another
+			// chain or from before a reorg
+			let message = format!("Leaf {} doesn't match the leaf of the tree", index);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidLeaves, message));
+		}
+	}
+	tree.insert_batch(&new_leaves, &poseidon())
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidLeaves, e.to_string()))
+}
+
+impl MTBn254X5 {
+	pub fn snapshot(&self) -> Result<MerkleTreeSnapshot, OperationError> {
+		match &self.inner {
+			MerkleTreeBn254::Height16(tree) => snapshot(tree),
+			MerkleTreeBn254::Height20(tree) => snapshot(tree),
+			MerkleTreeBn254::Height30(tree) => snapshot(tree),
+		}
+	}
+
+	pub fn from_snapshot(snapshot: &MerkleTreeSnapshot) -> Result<MTBn254X5, OperationError> {
+		let inner = match check_tree_height(snapshot.height)? {
+			16 => MerkleTreeBn254::Height16(restore(snapshot)?),
+			20 => MerkleTreeBn254::Height20(restore(snapshot)?),
+			_ => MerkleTreeBn254::Height30(restore(snapshot)?),
+		};
+		Ok(Self { inner })
+	}
+
+	/// Number of leaves up to the last non empty one
+	pub fn leaf_count(&self) -> u64 {
+		match &self.inner {
+			MerkleTreeBn254::Height16(tree) => leaf_count(tree),
+			MerkleTreeBn254::Height20(tree) => leaf_count(tree),
+			MerkleTreeBn254::Height30(tree) => leaf_count(tree),
+		}
+	}
+
+	/// Insert `leaves` from the leaf index `from_index`, only hashing the
+	/// paths of the new leaves
+	///
+	/// `from_index` can't be past `leaf_count()`. Leaves the tree already has
+	/// are checked against it instead of being inserted again, so an indexer
+	/// page can be applied twice.
+	pub fn sync_from(&mut self, from_index: u64, leaves: &[Vec<u8>]) -> Result<(), OperationError> {
+		match &mut self.inner {
+			MerkleTreeBn254::Height16(tree) => sync_from(tree, from_index, leaves),
+			MerkleTreeBn254::Height20(tree) => sync_from(tree, from_index, leaves),
+			MerkleTreeBn254::Height30(tree) => sync_from(tree, from_index, leaves),
+		}
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl MerkleTreeSnapshot {
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = height)]
+	pub fn js_height(&self) -> u32 {
+		self.height as u32
+	}
+}
+
+#[cfg(feature = "js")]
+impl_js_serialization!(MerkleTreeSnapshot);
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl MTBn254X5 {
+	#[wasm_bindgen(js_name = snapshot)]
+	pub fn js_snapshot(&self) -> Result<MerkleTreeSnapshot, JsValue> {
+		Ok(self.snapshot()?)
+	}
+
+	#[wasm_bindgen(js_name = fromSnapshot)]
+	pub fn js_from_snapshot(snapshot: &MerkleTreeSnapshot) -> Result<MTBn254X5, JsValue> {
+		Ok(Self::from_snapshot(snapshot)?)
+	}
+
+	#[wasm_bindgen(getter)]
+	#[wasm_bindgen(js_name = leafCount)]
+	pub fn js_leaf_count(&self) -> JsString {
+		JsString::from(self.leaf_count().to_string())
+	}
+
+	#[wasm_bindgen(js_name = syncFrom)]
+	pub fn js_sync_from(&mut self, from_index: JsString, leaves: Leaves) -> Result<(), JsValue> {
+		let from_index: String = from_index.into();
+		let from_index: u64 = from_index.parse().map_err(|_| OpStatusCode::InvalidIndices)?;
+		let leaves: Vec<_> = Array::from(&leaves)
+			.to_vec()
+			.into_iter()
+			.map(|v| Uint8Array::new(&v))
+			.map(Uint8Arrayx32::try_from)
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|_| OpStatusCode::InvalidLeaves)?
+			.into_iter()
+			.map(|v| v.0.to_vec())
+			.collect();
+
+		self.sync_from(from_index, &leaves)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_restore_snapshots_and_sync_new_leaves() {
+		let leaves: Vec<Vec<u8>> = (1u8..=6).map(|i| [i; 32].to_vec()).collect();
+		let full = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap();
+
+		let synced = MTBn254X5::from_leaves_with_height(&leaves[..4], 0, 16).unwrap();
+		let snapshot = synced.snapshot().unwrap();
+		assert_eq!(snapshot.leaves(), &leaves[..4]);
+		assert_eq!(snapshot.filled_subtrees.len(), 16);
+		let json = snapshot.to_json().unwrap();
+		let mut restored = MTBn254X5::from_snapshot(&MerkleTreeSnapshot::from_json(&json).unwrap()).unwrap();
+		assert_eq!(restored.get_root(), synced.get_root());
+		assert_eq!(restored.leaf_count(), 4);
+
+		restored.sync_from(3, &leaves[3..]).unwrap();
+		assert_eq!(restored.leaf_count(), 6);
+		assert_eq!(restored.get_root(), full.get_root());
+		// Paths of the leaves of the snapshot and of the synced ones
+		for index in [0, 2, 5] {
+			let path = restored.path(index).unwrap();
+			assert_eq!(path, full.path(index).unwrap());
+			path.check(&leaves[index as usize]).unwrap();
+		}
+		assert_eq!(restored.snapshot().unwrap(), full.snapshot().unwrap());
+
+		assert_eq!(
+			restored.sync_from(7, &leaves[..1]).unwrap_err().code,
+			OpStatusCode::InvalidIndices
+		);
+		assert_eq!(
+			restored.sync_from(0, &leaves[1..2]).unwrap_err().code,
+			OpStatusCode::InvalidLeaves
+		);
+
+		let mut truncated = full.snapshot().unwrap();
+		truncated.filled_subtrees.pop();
+		assert_eq!(
+			MTBn254X5::from_snapshot(&truncated).err().unwrap().code,
+			OpStatusCode::InvalidTreeSnapshot
+		);
+	}
+
+	#[test]
+	fn should_restore_empty_trees() {
+		let empty = MTBn254X5 {
+			inner: MerkleTreeBn254::Height16(empty_tree().unwrap()),
+		};
+		let snapshot = empty.snapshot().unwrap();
+		assert!(snapshot.leaves().is_empty());
+		let restored = MTBn254X5::from_snapshot(&snapshot).unwrap();
+		assert_eq!(restored.get_root(), empty.get_root());
+		assert_eq!(restored.leaf_count(), 0);
+	}
+
+	#[test]
+	fn should_check_the_root_of_snapshots_on_restore() {
+		let leaves: Vec<Vec<u8>> = (1u8..=5).map(|i| [i; 32].to_vec()).collect();
+		let snapshot = MTBn254X5::from_leaves_with_height(&leaves, 0, 16)
+			.unwrap()
+			.snapshot()
+			.unwrap();
+
+		let mut wrong_root = snapshot.clone();
+		wrong_root.root = vec![0u8; 32];
+		assert_eq!(
+			MTBn254X5::from_snapshot(&wrong_root).err().unwrap().code,
+			OpStatusCode::InvalidTreeSnapshot
+		);
+
+		// The last leaf is at index 4, the subtree of the leaves 0 to 3 is
+		// filled
+		let mut wrong_subtree = snapshot.clone();
+		wrong_subtree.filled_subtrees[2] = vec![0u8; 32];
+		assert_eq!(
+			MTBn254X5::from_snapshot(&wrong_subtree).err().unwrap().code,
+			OpStatusCode::InvalidTreeSnapshot
+		);
+
+		let mut wrong_leaf = snapshot.clone();
+		wrong_leaf.leaves[4] = vec![9u8; 32];
+		assert_eq!(
+			MTBn254X5::from_snapshot(&wrong_leaf).err().unwrap().code,
+			OpStatusCode::InvalidTreeSnapshot
+		);
+
+		// Older leaves aren't rehashed on restore, their paths don't lead to
+		// the root
+		let mut wrong_old_leaf = snapshot;
+		wrong_old_leaf.leaves[0] = vec![9u8; 32];
+		let restored = MTBn254X5::from_snapshot(&wrong_old_leaf).unwrap();
+		assert_eq!(
+			restored.path(1).unwrap().check(&leaves[1]).unwrap_err().code,
+			OpStatusCode::InvalidRoots
+		);
+	}
+}
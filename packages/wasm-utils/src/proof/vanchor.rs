@@ -1,37 +1,79 @@
 use core::convert::TryInto;
+#[cfg(feature = "js")]
 use js_sys::{Array, JsString, Uint8Array};
-use wasm_bindgen::__rt::std::collections::btree_map::BTreeMap;
-// https://github.com/rustwasm/wasm-bindgen/issues/2231#issuecomment-656293288
+use std::collections::BTreeMap;
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
 
-use ark_bn254::Fr as Bn254Fr;
-use ark_crypto_primitives::Error;
+use ark_bls12_381::{Bls12_381, Fr as Bls381Fr};
+use ark_bn254::{Bn254, Fr as Bn254Fr};
+use ark_ec::PairingEngine;
 use ark_ff::{BigInteger, PrimeField};
-use arkworks_setups::utxo::Utxo;
-use arkworks_setups::{Curve as ArkCurve, VAnchorProver};
+use ark_groth16::ProvingKey as Groth16ProvingKey;
+use arkworks_native_gadgets::poseidon::Poseidon;
+use arkworks_setups::common::{setup_params, setup_tree_and_create_path};
+use arkworks_setups::r1cs::vanchor::VAnchorR1CSProver;
+use arkworks_setups::utxo::Utxo as ArkUtxo;
+use arkworks_setups::Curve as ArkCurve;
 use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
 
+use crate::amount::{Amount, MAX_PUBLIC_AMOUNT, MIN_PUBLIC_AMOUNT};
+use crate::proof::ext_data::ExtData;
+use crate::proof::merkle_path::MerklePath;
+use crate::proof::planner::{input_arity, zero_utxo};
+use crate::proof::proving_key::{prove, ProvingKey};
+#[cfg(feature = "js")]
+use crate::serialization::impl_js_serialization;
+use crate::serialization::{hex_bytes, hex_bytes_list, Serializable};
 use crate::types::{Backend, Curve, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
 use crate::utxo::JsUtxo;
-use crate::{VAnchorR1CSProverBn254_30_2_16_2, VAnchorR1CSProverBn254_30_2_2_2, DEFAULT_LEAF};
+use crate::utxo::Utxo;
+use crate::{check_tree_height, with_tree_height, DEFAULT_LEAF, TREE_HEIGHT};
 
 const SUPPORTED_INPUT_COUNT: [usize; 2] = [2, 16];
 const SUPPORTED_VANCHOR_COUNT: [usize; 3] = [2, 8, 16];
 
-#[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VAnchorProof {
-	#[wasm_bindgen(skip)]
-	pub proof: Vec<u8>,
-	#[wasm_bindgen(skip)]
-	pub public_inputs: Vec<Vec<u8>>,
-	#[wasm_bindgen(skip)]
-	pub output_utxos: Vec<JsUtxo>,
-	#[wasm_bindgen(skip)]
-	pub input_utxos: Vec<JsUtxo>,
-	#[wasm_bindgen(skip)]
-	pub public_amount: [u8; 32],
+	#[serde(with = "hex_bytes")]
+	pub(crate) proof: Vec<u8>,
+	#[serde(with = "hex_bytes_list")]
+	pub(crate) public_inputs: Vec<Vec<u8>>,
+	pub(crate) output_utxos: Vec<Utxo>,
+	pub(crate) input_utxos: Vec<Utxo>,
+	#[serde(with = "hex_bytes")]
+	pub(crate) public_amount: [u8; 32],
+}
+
+impl Serializable for VAnchorProof {}
+
+impl VAnchorProof {
+	pub fn get_proof(&self) -> &[u8] {
+		&self.proof
+	}
+
+	pub fn get_public_inputs(&self) -> &[Vec<u8>] {
+		&self.public_inputs
+	}
+
+	pub fn get_output_utxos(&self) -> &[Utxo] {
+		&self.output_utxos
+	}
+
+	pub fn get_input_utxos(&self) -> &[Utxo] {
+		&self.input_utxos
+	}
+
+	pub fn get_public_amount(&self) -> [u8; 32] {
+		self.public_amount
+	}
 }
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl VAnchorProof {
 	#[wasm_bindgen(getter)]
@@ -48,14 +90,24 @@ impl VAnchorProof {
 	#[wasm_bindgen(getter)]
 	#[wasm_bindgen(js_name = outputUtxos)]
 	pub fn output_utxos(&self) -> Array {
-		let inputs: Array = self.output_utxos.clone().into_iter().map(JsValue::from).collect();
+		let inputs: Array = self
+			.output_utxos
+			.clone()
+			.into_iter()
+			.map(|utxo| JsValue::from(JsUtxo::from(utxo)))
+			.collect();
 		inputs
 	}
 
 	#[wasm_bindgen(getter)]
 	#[wasm_bindgen(js_name = inputUtxos)]
 	pub fn inputs_utxos(&self) -> Array {
-		let inputs: Array = self.input_utxos.clone().into_iter().map(JsValue::from).collect();
+		let inputs: Array = self
+			.input_utxos
+			.clone()
+			.into_iter()
+			.map(|utxo| JsValue::from(JsUtxo::from(utxo)))
+			.collect();
 		inputs
 	}
 
@@ -71,14 +123,22 @@ impl VAnchorProof {
 	}
 }
 
+#[cfg(feature = "js")]
+impl_js_serialization!(VAnchorProof);
+
 #[derive(Debug, Clone)]
 pub struct VAnchorProofPayload {
 	pub exponentiation: i8,
 	pub width: usize,
+	pub height: usize,
 	pub curve: Curve,
 	pub backend: Backend,
-	pub pk: Vec<u8>,
+	/// `None` when built with `build_without_pk`, until `create_proofs` sets
+	/// the key of the batch
+	pub pk: Option<ProvingKey>,
 	pub leaves: BTreeMap<u64, Vec<Vec<u8>>>,
+	/// Paths of the first inputs, used instead of the trees of `leaves`
+	pub merkle_paths: Vec<MerklePath>,
 	pub ext_data_hash: Vec<u8>,
 	/// get roots for linkable tree
 	/// Available set can be of length 2 , 16 , 32
@@ -86,57 +146,366 @@ pub struct VAnchorProofPayload {
 	// Input UTXOs - The UTXOs that will be spent in the proof.
 	// Input UTXOs should have a nullfier, as well as a keypair
 	// that contains a private key.
-	pub input_utxos: Vec<JsUtxo>,
+	pub input_utxos: Vec<Utxo>,
 	// Leaf indices
 	pub indices: Vec<u64>,
 	// Chain Id
 	pub chain_id: u64,
 	// Public amount
-	pub public_amount: i128,
+	pub public_amount: Amount,
 	// Utxos that are being created
-	pub output_utxos: [JsUtxo; 2],
+	pub output_utxos: [Utxo; 2],
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct VAnchorProofInput {
 	pub exponentiation: Option<i8>,
 	pub width: Option<usize>,
+	pub height: Option<usize>,
 	pub curve: Option<Curve>,
 	pub backend: Option<Backend>,
-	pub pk: Option<Vec<u8>>,
+	pub pk: Option<ProvingKey>,
 	pub leaves: Option<BTreeMap<u64, Vec<Vec<u8>>>>,
+	// Merkle paths of the inputs, in their order
+	pub merkle_paths: Option<Vec<MerklePath>>,
 	pub ext_data_hash: Option<Vec<u8>>,
 	/// get roots for linkable tree
 	/// Available set can be of length 2 , 16 , 32
 	pub roots: Option<Vec<Vec<u8>>>,
 	// Utxos that are being spent
-	pub input_utxos: Option<Vec<JsUtxo>>,
+	pub input_utxos: Option<Vec<Utxo>>,
 	// Leaf indices
 	pub indices: Option<Vec<u64>>,
 	// Chain Id
 	pub chain_id: Option<u128>,
 	// Public amount
-	pub public_amount: Option<i128>,
+	pub public_amount: Option<Amount>,
 	// Utxos that are being created
-	pub output_utxos: Option<[JsUtxo; 2]>,
+	pub output_utxos: Option<[Utxo; 2]>,
+	// Pad the inputs to the next supported circuit arity
+	pub pad_inputs: Option<bool>,
+	// External data, used to derive the ext data hash and the public amount
+	pub ext_data: Option<ExtData>,
+}
+
+/// Ext data hash and public amount of a transaction carrying `ext_data`
+///
+/// The public amount defaults to `ext_amount - fee` and has to match it in
+/// the field of `curve` when it is set, as does a manually set ext data hash.
+fn ext_data_payload(
+	curve: Curve,
+	ext_data: &ExtData,
+	ext_data_hash: Option<Vec<u8>>,
+	public_amount: Option<Amount>,
+) -> Result<(Vec<u8>, Amount), OperationError> {
+	let hash = ext_data.get_hash();
+	if let Some(ext_data_hash) = ext_data_hash {
+		if ext_data_hash != hash {
+			let message = format!(
+				"The ext data hash {} doesn't match the hash of the ext data {}",
+				hex::encode(ext_data_hash),
+				hex::encode(hash.as_slice())
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidExtDataHash,
+				message,
+			));
+		}
+	}
+
+	let expected_public_amount = Amount::from(ext_data.ext_amount) - Amount::from(ext_data.fee);
+	let public_amount = public_amount.unwrap_or(expected_public_amount);
+	if public_amount.to_field_bytes(curve) != expected_public_amount.to_field_bytes(curve) {
+		let message = format!(
+			"Public amount {} doesn't match ext amount {} minus fee {}",
+			public_amount, ext_data.ext_amount, ext_data.fee
+		);
+		let mut oe = OperationError::new_with_message(OpStatusCode::InvalidPublicAmount, message);
+		oe.data = Some(
+			serde_json::json!({
+				"publicAmount": public_amount.to_string(),
+				"extAmount": ext_data.ext_amount.to_string(),
+				"fee": ext_data.fee.to_string(),
+			})
+			.to_string(),
+		);
+		return Err(oe);
+	}
+	Ok((hash, public_amount))
+}
+
+/// Fill the inputs with zero amount UTXOs and default indices up to the
+/// smallest supported arity, input sets above the largest arity are left
+/// as they are
+fn pad_to_arity(
+	mut input_utxos: Vec<Utxo>,
+	mut indices: Vec<u64>,
+	curve: Curve,
+	chain_id: u64,
+) -> Result<(Vec<Utxo>, Vec<u64>), OperationError> {
+	let arity = match input_arity(input_utxos.len()) {
+		Some(arity) => arity,
+		None => return Ok((input_utxos, indices)),
+	};
+	let chain_id = input_utxos.first().map(Utxo::get_chain_id_raw).unwrap_or(chain_id);
+	while input_utxos.len() < arity {
+		input_utxos.push(zero_utxo(curve, chain_id)?);
+	}
+	if indices.len() < arity {
+		indices.resize(arity, 0);
+	}
+	Ok((input_utxos, indices))
+}
+
+/// Check the UTXOs against the proof curve, leaves and roots before proving
+///
+/// An input with a Merkle path has to be the leaf at the end of it, at its
+/// index. The tree of the chain of the other inputs is rebuilt from `leaves`:
+/// the input commitment has to be the leaf at its index. Either way the root
+/// has to be one of `roots`, in any order. Zero amount inputs are padding the
+/// circuit doesn't check and are skipped. The error names the first bad input
+/// and carries its position in `data`.
+///
+/// Gives the position in `roots` of the root of every input, `None` for the
+/// skipped ones.
+pub fn preflight(payload: &VAnchorProofPayload) -> Result<Vec<Option<usize>>, OperationError> {
+	// All the UTXOs have to be on the proving curve
+	if let Some(index) = payload
+		.input_utxos
+		.iter()
+		.chain(payload.output_utxos.iter())
+		.position(|utxo| utxo.curve() != payload.curve)
+	{
+		let message = format!(
+			"proof::vanchor: UTXO {} isn't on the {} curve of the proof",
+			index, payload.curve
+		);
+		return Err(OperationError::new_with_message(OpStatusCode::InvalidCurve, message));
+	}
+
+	let mut chain_roots: BTreeMap<u64, Vec<u8>> = BTreeMap::new();
+	let mut positions = Vec::with_capacity(payload.input_utxos.len());
+	for (input, (utxo, &index)) in payload.input_utxos.iter().zip(&payload.indices).enumerate() {
+		if utxo.get_amount_value().is_zero() {
+			positions.push(None);
+			continue;
+		}
+		let chain_id = utxo.get_chain_id_raw();
+		if let Some(utxo_index) = utxo.get_index().filter(|utxo_index| *utxo_index != index) {
+			let message = format!("its UTXO index {} isn't the declared index {}", utxo_index, index);
+			return Err(preflight_error(OpStatusCode::InvalidIndices, input, message));
+		}
+		let commitment = utxo.get_commitment();
+		let (root, source) = match payload.merkle_paths.get(input) {
+			Some(merkle_path) => (
+				path_root(payload, merkle_path, index, &commitment, input)?,
+				"Merkle path",
+			),
+			None => {
+				let leaves = chain_leaves(payload, chain_id, index, &commitment, input)?;
+				if !chain_roots.contains_key(&chain_id) {
+					let root = match payload.curve {
+						Curve::Bn254 => tree_root::<Bn254Fr>(ArkCurve::Bn254, leaves, payload.height)?,
+						Curve::Bls381 => tree_root::<Bls381Fr>(ArkCurve::Bls381, leaves, payload.height)?,
+					};
+					chain_roots.insert(chain_id, root);
+				}
+				(chain_roots[&chain_id].clone(), "leaves")
+			}
+		};
+		match payload.roots.iter().position(|candidate| *candidate == root) {
+			Some(position) => positions.push(Some(position)),
+			None => {
+				let message = format!(
+					"the root {} of the {} of chain {} isn't one of the roots",
+					hex::encode(&root),
+					source,
+					chain_id
+				);
+				return Err(preflight_error(OpStatusCode::InvalidRoots, input, message));
+			}
+		}
+	}
+	Ok(positions)
+}
+
+fn preflight_error(code: OpStatusCode, input: usize, reason: String) -> OperationError {
+	let message = format!("proof::vanchor: Input {} can't be proved, {}", input, reason);
+	let mut oe = OperationError::new_with_message(code, message);
+	oe.data = Some(serde_json::json!({ "input": input }).to_string());
+	oe
+}
+
+/// Root of the path of `commitment`, which has to be at `index`
+fn path_root(
+	payload: &VAnchorProofPayload,
+	merkle_path: &MerklePath,
+	index: u64,
+	commitment: &[u8],
+	input: usize,
+) -> Result<Vec<u8>, OperationError> {
+	if merkle_path.height() != payload.height {
+		let message = format!(
+			"its Merkle path has {} levels, the tree {}",
+			merkle_path.height(),
+			payload.height
+		);
+		return Err(preflight_error(OpStatusCode::InvalidTreeHeight, input, message));
+	}
+	if merkle_path.leaf_index() != index {
+		let message = format!(
+			"its Merkle path is the one of index {}, not {}",
+			merkle_path.leaf_index(),
+			index
+		);
+		return Err(preflight_error(OpStatusCode::InvalidIndices, input, message));
+	}
+	let root = match payload.curve {
+		Curve::Bn254 => merkle_path.compute_root_on::<Bn254Fr>(ArkCurve::Bn254, commitment)?,
+		Curve::Bls381 => merkle_path.compute_root_on::<Bls381Fr>(ArkCurve::Bls381, commitment)?,
+	};
+	if root != merkle_path.get_root() {
+		let message = format!(
+			"its Merkle path leads to the root {}, not {}",
+			hex::encode(&root),
+			hex::encode(merkle_path.get_root())
+		);
+		return Err(preflight_error(OpStatusCode::InvalidRoots, input, message));
+	}
+	Ok(root)
+}
+
+/// Leaves of `chain_id`, where `commitment` has to be the leaf at `index`
+fn chain_leaves<'a>(
+	payload: &'a VAnchorProofPayload,
+	chain_id: u64,
+	index: u64,
+	commitment: &[u8],
+	input: usize,
+) -> Result<&'a [Vec<u8>], OperationError> {
+	let leaves = payload.leaves.get(&chain_id).ok_or_else(|| {
+		let message = format!("there are no leaves for its chain {}", chain_id);
+		preflight_error(OpStatusCode::InvalidLeaves, input, message)
+	})?;
+	match leaves.get(index as usize) {
+		Some(leaf) if leaf == commitment => {}
+		Some(leaf) => {
+			let message = format!(
+				"the leaf {} at index {} of chain {} isn't its commitment {}",
+				hex::encode(leaf),
+				index,
+				chain_id,
+				hex::encode(commitment)
+			);
+			return Err(preflight_error(OpStatusCode::InvalidIndices, input, message));
+		}
+		None => {
+			let message = format!(
+				"index {} is outside of the {} leaves of chain {}",
+				index,
+				leaves.len(),
+				chain_id
+			);
+			return Err(preflight_error(OpStatusCode::InvalidIndices, input, message));
+		}
+	}
+	Ok(leaves)
+}
+
+fn tree_root<F: PrimeField>(curve: ArkCurve, leaves: &[Vec<u8>], height: usize) -> Result<Vec<u8>, OperationError> {
+	let leaves: Vec<F> = leaves.iter().map(|leaf| F::from_be_bytes_mod_order(leaf)).collect();
+	let hasher = Poseidon::new(setup_params::<F>(curve, 5, 3));
+	with_tree_height!(height, |H| setup_tree_and_create_path::<F, Poseidon<F>, H>(
+		&hasher,
+		&leaves,
+		0,
+		&DEFAULT_LEAF
+	))
+	.ok_or(OpStatusCode::InvalidTreeHeight)?
+	.map(|(tree, _)| tree.root().into_repr().to_bytes_be())
+	.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidLeaves, e.to_string()))
 }
 
 impl VAnchorProofInput {
 	pub fn build(self) -> Result<VAnchorProofPayload, OperationError> {
-		let pk = self.pk.ok_or(OpStatusCode::InvalidProvingKey)?;
+		let payload = self.build_without_pk()?;
+		if payload.pk.is_none() {
+			return Err(OpStatusCode::InvalidProvingKey.into());
+		}
+		Ok(payload)
+	}
+
+	/// `build` without requiring the proving key, for inputs proved by
+	/// `create_proofs` with the key of the batch
+	pub fn build_without_pk(self) -> Result<VAnchorProofPayload, OperationError> {
 		let input_utxos = self.input_utxos.ok_or(OpStatusCode::InvalidInputUtxoConfig)?;
-		let leaves = self.leaves.ok_or(OpStatusCode::InvalidLeaves)?;
-		let ext_data_hash = self.ext_data_hash.ok_or(OpStatusCode::InvalidExtDataHash)?;
+		// Merkle paths replace the leaves and give the indices of the inputs
+		let merkle_paths = self.merkle_paths.unwrap_or_default();
+		if merkle_paths.len() > input_utxos.len() {
+			let message = format!(
+				"There are {} Merkle paths for {} input UTXOs",
+				merkle_paths.len(),
+				input_utxos.len()
+			);
+			return Err(OperationError::new_with_message(OpStatusCode::InvalidLeaves, message));
+		}
+		let leaves = match self.leaves {
+			Some(leaves) => leaves,
+			None if !merkle_paths.is_empty() => BTreeMap::new(),
+			None => return Err(OpStatusCode::InvalidLeaves.into()),
+		};
 		let roots = self.roots.ok_or(OpStatusCode::InvalidRoots)?;
-		let chain_id = self.chain_id.ok_or(OpStatusCode::InvalidChainId)?;
-		let indices = self.indices.ok_or(OpStatusCode::InvalidIndices)?;
-		let public_amount = self.public_amount.ok_or(OpStatusCode::InvalidPublicAmount)?;
+		let chain_id: u64 = self
+			.chain_id
+			.ok_or(OpStatusCode::InvalidChainId)?
+			.try_into()
+			.map_err(|_| OpStatusCode::InvalidChainId)?;
+		let indices = match self.indices {
+			Some(indices) => indices,
+			None if !merkle_paths.is_empty() => {
+				let mut indices: Vec<u64> = merkle_paths.iter().map(MerklePath::leaf_index).collect();
+				indices.resize(input_utxos.len(), 0);
+				indices
+			}
+			None => return Err(OpStatusCode::InvalidIndices.into()),
+		};
+		let (ext_data_hash, public_amount) = match &self.ext_data {
+			Some(ext_data) => ext_data_payload(
+				self.curve.unwrap_or(Curve::Bn254),
+				ext_data,
+				self.ext_data_hash,
+				self.public_amount,
+			)?,
+			None => (
+				self.ext_data_hash.ok_or(OpStatusCode::InvalidExtDataHash)?,
+				self.public_amount.ok_or(OpStatusCode::InvalidPublicAmount)?,
+			),
+		};
+		if !public_amount.is_public_amount() {
+			let message = format!(
+				"Public amount {} is outside of the public amount range [{}, {}]",
+				public_amount, MIN_PUBLIC_AMOUNT, MAX_PUBLIC_AMOUNT
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidPublicAmount,
+				message,
+			));
+		}
 		let output_utxos = self.output_utxos.ok_or(OpStatusCode::InvalidOutputUtxoConfig)?;
+		let (input_utxos, indices) = if self.pad_inputs.unwrap_or(false) {
+			pad_to_arity(input_utxos, indices, self.curve.unwrap_or(Curve::Bn254), chain_id)?
+		} else {
+			(input_utxos, indices)
+		};
 
 		let exponentiation = self.exponentiation.unwrap_or(5);
 		let width = self.width.unwrap_or(3);
+		let height = check_tree_height(self.height.unwrap_or(TREE_HEIGHT))?;
 		let curve = self.curve.unwrap_or(Curve::Bn254);
 		let backend = self.backend.unwrap_or(Backend::Arkworks);
+		if let Some(pk) = &self.pk {
+			pk.check_curve(curve)?;
+		}
 
 		// Input UTXO should have the same chain_id
 		// For default UTXOS the amount and the index should be `0`
@@ -144,7 +513,7 @@ impl VAnchorProofInput {
 		// chain_id of the first item in the list
 		let mut invalid_utxo_chain_id_indices = vec![];
 		let mut invalid_utxo_dublicate_nullifiers = vec![];
-		let utxos_chain_id = input_utxos[0].clone().chain_id_raw();
+		let utxos_chain_id = input_utxos[0].get_chain_id_raw();
 		// validate the all inputs share the same chain_id
 		input_utxos.iter().enumerate().for_each(|(index, utxo)| {
 			if utxo.get_chain_id_raw() != utxos_chain_id {
@@ -156,7 +525,7 @@ impl VAnchorProofInput {
 			.enumerate()
 			.filter(|(_, utxo)| {
 				// filter for non-default utxos
-				utxo.get_amount_raw() != 0 && utxo.get_index().unwrap_or(0) != 0
+				!utxo.get_amount_value().is_zero() && utxo.get_index().unwrap_or(0) != 0
 			})
 			.collect::<Vec<_>>();
 		non_default_utxo.iter().for_each(|(index, utxo)| {
@@ -181,22 +550,30 @@ impl VAnchorProofInput {
 			return Err(op);
 		}
 
-		// validate amounts
-		let mut in_amount = public_amount;
-		input_utxos.iter().for_each(|utxo| {
-			let utxo_amount: i128 = utxo
-				.get_amount_raw()
-				.try_into()
-				.expect("Failed to convert utxo value to i128");
-			in_amount += utxo_amount
-		});
-		let mut out_amount = 0u128;
-		output_utxos
+		// validate amounts, the sums can exceed `i128`
+		let in_total = input_utxos
 			.iter()
-			.for_each(|output_config| out_amount += output_config.get_amount_raw());
-		let out_amount: i128 = out_amount
-			.try_into()
-			.expect("Failed to convert accumulated in amounts  value to i128");
+			.fold(Amount::default(), |sum, utxo| sum + utxo.get_amount_value());
+		let in_amount = in_total + public_amount;
+		// The fee is paid out of the spent inputs and the deposited amount
+		if let Some(ext_data) = &self.ext_data {
+			let available = in_total + Amount::from(ext_data.ext_amount);
+			if available < Amount::from(ext_data.fee) {
+				let message = format!(
+					"The fee {} isn't covered by the inputs and the ext amount, only {} is available",
+					ext_data.fee, available
+				);
+				let mut oe = OperationError::new_with_message(OpStatusCode::InvalidFee, message);
+				oe.data = Some(
+					serde_json::json!({ "fee": ext_data.fee.to_string(), "available": available.to_string() })
+						.to_string(),
+				);
+				return Err(oe);
+			}
+		}
+		let out_amount = output_utxos
+			.iter()
+			.fold(Amount::default(), |sum, utxo| sum + utxo.get_amount_value());
 		if out_amount != in_amount {
 			let message = format!(
 				"Output amount and input amount don't match input({}) != output({})",
@@ -212,15 +589,17 @@ impl VAnchorProofInput {
 		Ok(VAnchorProofPayload {
 			exponentiation,
 			width,
+			height,
 			curve,
 			backend,
-			pk,
+			pk: self.pk,
 			leaves,
+			merkle_paths,
 			ext_data_hash,
 			roots,
 			input_utxos,
 			indices,
-			chain_id: chain_id.try_into().unwrap(),
+			chain_id,
 			public_amount,
 			output_utxos,
 		})
@@ -228,33 +607,15 @@ impl VAnchorProofInput {
 }
 
 pub fn create_proof(vanchor_proof_input: VAnchorProofPayload, rng: &mut OsRng) -> Result<VAnchorProof, OperationError> {
-	let VAnchorProofPayload {
-		public_amount,
-		backend,
-		curve,
-		width,
-		input_utxos,
-		indices,
-		leaves,
-		exponentiation,
-		roots,
-		pk,
-		chain_id,
-		output_utxos,
-		ext_data_hash,
-	} = vanchor_proof_input;
-	let public_amount_bytes = Bn254Fr::from(public_amount)
-		.into_repr()
-		.to_bytes_be()
-		.try_into()
-		.expect("proof::vanchor: Failed to wrap public amount to bytes");
+	preflight(&vanchor_proof_input)?;
+	let payload = &vanchor_proof_input;
+	let pk = payload.pk.as_ref().ok_or(OpStatusCode::InvalidProvingKey)?;
+	let public_amount_bytes = payload.public_amount.to_field_bytes(payload.curve);
 	// Insure UTXO set has the required/supported input count
-	let in_utxos: Vec<JsUtxo> = if SUPPORTED_INPUT_COUNT.contains(&input_utxos.len()) {
-		input_utxos
-	} else {
+	if !SUPPORTED_INPUT_COUNT.contains(&payload.input_utxos.len()) {
 		let message = format!(
 			"proof::vanchor: Input set has {} UTXOs while the supported set length should be one of {:?}",
-			&input_utxos.len(),
+			&payload.input_utxos.len(),
 			&SUPPORTED_INPUT_COUNT,
 		);
 		return Err(OperationError::new_with_message(
@@ -263,11 +624,11 @@ pub fn create_proof(vanchor_proof_input: VAnchorProofPayload, rng: &mut OsRng) -
 		));
 	};
 	// Insure the length of the indices
-	if indices.len() != in_utxos.len() {
+	if payload.indices.len() != payload.input_utxos.len() {
 		let message = format!(
       "proof::vanchor: Indices Array don't match with the Input size , supplied {} indices while there are {} utxos in the input ",
-      indices.len(),
-      in_utxos.len(),
+      payload.indices.len(),
+      payload.input_utxos.len(),
     );
 		return Err(OperationError::new_with_message(
 			OpStatusCode::InvalidProofParameters,
@@ -275,10 +636,10 @@ pub fn create_proof(vanchor_proof_input: VAnchorProofPayload, rng: &mut OsRng) -
 		));
 	}
 	// Insure the Anchor count is supported
-	if !SUPPORTED_VANCHOR_COUNT.contains(&roots.len()) {
+	if !SUPPORTED_VANCHOR_COUNT.contains(&payload.roots.len()) {
 		let message = format!(
 			"proof::vanchor: Input set has {} roots while the supported set length should be one of {:?}",
-			&roots.len(),
+			&payload.roots.len(),
 			&SUPPORTED_VANCHOR_COUNT,
 		);
 		return Err(OperationError::new_with_message(
@@ -286,91 +647,266 @@ pub fn create_proof(vanchor_proof_input: VAnchorProofPayload, rng: &mut OsRng) -
 			message,
 		));
 	};
-	// Initialize the output notes vec
-	let utxos_out = output_utxos
-		.iter()
-		.map(|js_utx| js_utx.get_bn254_utxo())
-		.collect::<Result<Vec<_>, OpStatusCode>>()?
-		.try_into()
-		.map_err(|_| OpStatusCode::InvalidProofParameters)?;
-	let proof = match (backend, curve, roots.len()) {
-		(Backend::Arkworks, Curve::Bn254, 2) => match (exponentiation, width, in_utxos.len()) {
-			(5, 5, 2) => {
-				let utxos_in: [Utxo<Bn254Fr>; 2] = [in_utxos[0].get_bn254_utxo()?, in_utxos[1].get_bn254_utxo()?];
-				let indices = indices.try_into().map_err(|_| OpStatusCode::InvalidIndices)?;
-				let roots = roots.try_into().map_err(|_| OpStatusCode::InvalidRoots)?;
-				VAnchorR1CSProverBn254_30_2_2_2::create_proof(
-					ArkCurve::Bn254,
-					chain_id,
-					public_amount,
-					ext_data_hash,
-					roots,
-					indices,
-					leaves,
-					utxos_in,
-					utxos_out,
-					pk,
-					DEFAULT_LEAF,
-					rng,
-				)
-			}
-			(5, 5, 16) => {
-				let in_utxos = in_utxos
-					.iter()
-					.map(|utxo| utxo.get_bn254_utxo())
-					.collect::<Result<Vec<_>, _>>()?;
-				let utxos_slice = in_utxos.try_into().map_err(|_| OpStatusCode::InvalidNoteSecrets)?;
-				let indices = indices.try_into().map_err(|_| OpStatusCode::InvalidIndices)?;
-				let roots = roots.try_into().map_err(|_| OpStatusCode::InvalidRoots)?;
-
-				VAnchorR1CSProverBn254_30_2_16_2::create_proof(
-					ArkCurve::Bn254,
-					chain_id,
-					public_amount,
-					ext_data_hash,
-					roots,
-					indices,
-					leaves,
-					utxos_slice,
-					utxos_out,
-					pk,
-					DEFAULT_LEAF,
-					rng,
-				)
-			}
-			_ => {
-				let message = format!(
-						"proof::vanchor: The proofing setup for backend {} curve {} width {} exp {} input size {} isn't implemented!",
-						backend,
-						curve,
-						width,
-						exponentiation,
-						&in_utxos.len(),
-					);
-				Err(Error::from(message))
-			}
-		},
+	let (proof, public_inputs) = match (
+		payload.backend,
+		payload.curve,
+		payload.roots.len(),
+		payload.exponentiation,
+		payload.width,
+		payload.input_utxos.len(),
+	) {
+		(Backend::Arkworks, Curve::Bn254, 2, 5, 5, 2) => with_tree_height!(payload.height, |H| {
+			prove_vanchor::<Bn254, H, 2>(
+				ArkCurve::Bn254,
+				pk.bn254()?,
+				payload,
+				utxo_array(&payload.input_utxos, Utxo::get_bn254_utxo)?,
+				utxo_array(&payload.output_utxos, Utxo::get_bn254_utxo)?,
+				rng,
+			)
+		}),
+		(Backend::Arkworks, Curve::Bn254, 2, 5, 5, 16) => with_tree_height!(payload.height, |H| {
+			prove_vanchor::<Bn254, H, 16>(
+				ArkCurve::Bn254,
+				pk.bn254()?,
+				payload,
+				utxo_array(&payload.input_utxos, Utxo::get_bn254_utxo)?,
+				utxo_array(&payload.output_utxos, Utxo::get_bn254_utxo)?,
+				rng,
+			)
+		}),
+		(Backend::Arkworks, Curve::Bls381, 2, 5, 5, 2) => with_tree_height!(payload.height, |H| {
+			prove_vanchor::<Bls12_381, H, 2>(
+				ArkCurve::Bls381,
+				pk.bls381()?,
+				payload,
+				utxo_array(&payload.input_utxos, Utxo::get_bls381_utxo)?,
+				utxo_array(&payload.output_utxos, Utxo::get_bls381_utxo)?,
+				rng,
+			)
+		}),
+		(Backend::Arkworks, Curve::Bls381, 2, 5, 5, 16) => with_tree_height!(payload.height, |H| {
+			prove_vanchor::<Bls12_381, H, 16>(
+				ArkCurve::Bls381,
+				pk.bls381()?,
+				payload,
+				utxo_array(&payload.input_utxos, Utxo::get_bls381_utxo)?,
+				utxo_array(&payload.output_utxos, Utxo::get_bls381_utxo)?,
+				rng,
+			)
+		}),
 		_ => {
 			let message = format!(
 				"proof::vanchor: The proofing setup for backend {} curve {} width {} exp {} input size {} isn't implemented!",
-				backend,
-				curve,
-				width,
-				exponentiation,
-				&in_utxos.len(),
+				payload.backend,
+				payload.curve,
+				payload.width,
+				payload.exponentiation,
+				&payload.input_utxos.len(),
 			);
-			Err(Error::from(message))
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidProofParameters,
+				message,
+			));
 		}
 	}
-	.map_err(|e| {
-		let message = format!("proof::vanchor:  {}", e);
-		OperationError::new_with_message(OpStatusCode::InvalidProofParameters, message)
-	})?;
+	.ok_or(OpStatusCode::InvalidTreeHeight)??;
 	Ok(VAnchorProof {
-		proof: proof.proof,
-		public_inputs: proof.public_inputs_raw,
-		output_utxos: output_utxos.to_vec(),
-		input_utxos: in_utxos,
+		proof,
+		public_inputs,
+		output_utxos: vanchor_proof_input.output_utxos.to_vec(),
+		input_utxos: vanchor_proof_input.input_utxos,
 		public_amount: public_amount_bytes,
 	})
 }
+
+fn proof_error(e: impl ToString) -> OperationError {
+	let message = format!("proof::vanchor:  {}", e.to_string());
+	OperationError::new_with_message(OpStatusCode::InvalidProofParameters, message)
+}
+
+fn utxo_array<F: PrimeField, const N: usize>(
+	utxos: &[Utxo],
+	ark_utxo: impl Fn(&Utxo) -> Result<ArkUtxo<F>, OpStatusCode>,
+) -> Result<[ArkUtxo<F>; N], OperationError> {
+	utxos
+		.iter()
+		.map(ark_utxo)
+		.collect::<Result<Vec<_>, _>>()?
+		.try_into()
+		.map_err(|_| OpStatusCode::InvalidProofParameters.into())
+}
+
+/// Prove with the parsed key `pk` the transaction of the payload, from the
+/// circuit `arkworks-setups` builds out of the Merkle paths of the inputs
+///
+/// Returns the proof and the public inputs.
+fn prove_vanchor<E: PairingEngine, const H: usize, const INS: usize>(
+	curve: ArkCurve,
+	pk: &Groth16ProvingKey<E>,
+	payload: &VAnchorProofPayload,
+	in_utxos: [ArkUtxo<E::Fr>; INS],
+	out_utxos: [ArkUtxo<E::Fr>; 2],
+	rng: &mut OsRng,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), OperationError> {
+	let keypair_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 2));
+	let tree_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 3));
+	let nullifier_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 4));
+	let leaf_hasher = Poseidon::<E::Fr>::new(setup_params(curve, 5, 5));
+
+	let chain_id = E::Fr::from(payload.chain_id);
+	let public_amount = payload.public_amount.to_field::<E::Fr>();
+	let ext_data_hash = E::Fr::from_be_bytes_mod_order(&payload.ext_data_hash);
+	let roots: [E::Fr; 2] = payload
+		.roots
+		.iter()
+		.map(|root| E::Fr::from_be_bytes_mod_order(root))
+		.collect::<Vec<_>>()
+		.try_into()
+		.map_err(|_| OpStatusCode::InvalidRoots)?;
+	let indices: [E::Fr; INS] = payload
+		.indices
+		.iter()
+		.map(|index| E::Fr::from(*index))
+		.collect::<Vec<_>>()
+		.try_into()
+		.map_err(|_| OpStatusCode::InvalidIndices)?;
+
+	// Zero amount inputs aren't checked by the circuit, a chain without
+	// leaves gets the path of an empty tree
+	let mut paths = Vec::with_capacity(INS);
+	for (input, (utxo, index)) in in_utxos.iter().zip(&payload.indices).enumerate() {
+		if let Some(merkle_path) = payload.merkle_paths.get(input) {
+			let commitment = utxo.commitment.into_repr().to_bytes_be();
+			paths.push(merkle_path.to_path::<E::Fr, H>(curve, &commitment)?);
+			continue;
+		}
+		let leaves: Vec<E::Fr> = payload
+			.leaves
+			.get(&utxo.chain_id_raw)
+			.map(|leaves| leaves.iter().map(|leaf| E::Fr::from_be_bytes_mod_order(leaf)).collect())
+			.unwrap_or_default();
+		let (_, path) =
+			setup_tree_and_create_path::<E::Fr, Poseidon<E::Fr>, H>(&tree_hasher, &leaves, *index, &DEFAULT_LEAF)
+				.map_err(proof_error)?;
+		paths.push(path);
+	}
+
+	let nullifiers = in_utxos
+		.iter()
+		.map(|utxo| utxo.calculate_nullifier(&nullifier_hasher))
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(proof_error)?;
+	let commitments = out_utxos.iter().map(|utxo| utxo.commitment).collect();
+	let circuit = VAnchorR1CSProver::<E, H, 2, INS, 2>::create_circuit(
+		public_amount,
+		ext_data_hash,
+		in_utxos,
+		indices,
+		paths,
+		roots,
+		out_utxos,
+		keypair_hasher,
+		tree_hasher,
+		nullifier_hasher,
+		leaf_hasher,
+	);
+	let public_inputs = VAnchorR1CSProver::<E, H, 2, INS, 2>::construct_public_inputs(
+		chain_id,
+		public_amount,
+		roots.to_vec(),
+		nullifiers,
+		commitments,
+		ext_data_hash,
+	);
+
+	Ok((
+		prove(circuit, pk, rng)?,
+		public_inputs
+			.iter()
+			.map(|input| input.into_repr().to_bytes_be())
+			.collect(),
+	))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::proof::MTBn254X5;
+
+	fn utxo(amount: u128, index: Option<u64>) -> Utxo {
+		Utxo::new(Curve::Bn254, Backend::Arkworks, amount, 0, None, None, None, index).unwrap()
+	}
+
+	fn payload() -> VAnchorProofPayload {
+		let spent = utxo(10, Some(1));
+		let leaves = vec![utxo(5, Some(0)).get_commitment(), spent.get_commitment()];
+		let root = MTBn254X5::from_leaves_with_height(&leaves, 0, 16).unwrap().get_root();
+		VAnchorProofPayload {
+			exponentiation: 5,
+			width: 5,
+			height: 16,
+			curve: Curve::Bn254,
+			backend: Backend::Arkworks,
+			pk: None,
+			leaves: BTreeMap::from([(0, leaves)]),
+			merkle_paths: Vec::new(),
+			ext_data_hash: vec![1u8; 32],
+			roots: vec![root, vec![0u8; 32]],
+			input_utxos: vec![spent, utxo(0, None)],
+			indices: vec![1, 0],
+			chain_id: 0,
+			public_amount: Amount::default(),
+			output_utxos: [utxo(10, None), utxo(0, None)],
+		}
+	}
+
+	#[test]
+	fn should_name_the_input_failing_preflight() {
+		assert_eq!(preflight(&payload()).unwrap(), [Some(0), None]);
+
+		let mut wrong_index = payload();
+		wrong_index.indices[0] = 0;
+		let error = preflight(&wrong_index).unwrap_err();
+		assert_eq!(error.code, OpStatusCode::InvalidIndices);
+		assert_eq!(error.data.unwrap(), r#"{"input":0}"#);
+
+		let mut wrong_leaf = payload();
+		wrong_leaf.leaves.get_mut(&0).unwrap().swap(0, 1);
+		assert_eq!(preflight(&wrong_leaf).unwrap_err().code, OpStatusCode::InvalidIndices);
+
+		let mut wrong_root = payload();
+		wrong_root.roots[0] = vec![0u8; 32];
+		assert_eq!(preflight(&wrong_root).unwrap_err().code, OpStatusCode::InvalidRoots);
+
+		// Roots can come in any order, like the edges of the anchor
+		let mut swapped_roots = payload();
+		swapped_roots.roots.swap(0, 1);
+		assert_eq!(preflight(&swapped_roots).unwrap(), [Some(1), None]);
+
+		let mut wrong_chain = payload();
+		wrong_chain.leaves = BTreeMap::from([(1, vec![])]);
+		assert_eq!(preflight(&wrong_chain).unwrap_err().code, OpStatusCode::InvalidLeaves);
+	}
+
+	#[test]
+	fn should_check_inputs_against_their_merkle_paths() {
+		let tree = MTBn254X5::from_leaves_with_height(&payload().leaves[&0], 0, 16).unwrap();
+		let mut with_path = payload();
+		with_path.leaves = BTreeMap::new();
+		with_path.merkle_paths = vec![tree.path(1).unwrap()];
+		preflight(&with_path).unwrap();
+
+		let mut wrong_path = with_path.clone();
+		wrong_path.merkle_paths = vec![tree.path(0).unwrap()];
+		assert_eq!(preflight(&wrong_path).unwrap_err().code, OpStatusCode::InvalidIndices);
+
+		let mut wrong_leaf = with_path.clone();
+		wrong_leaf.input_utxos[0] = utxo(11, Some(1));
+		assert_eq!(preflight(&wrong_leaf).unwrap_err().code, OpStatusCode::InvalidRoots);
+
+		let mut wrong_root = with_path;
+		wrong_root.roots[0] = vec![0u8; 32];
+		assert_eq!(preflight(&wrong_root).unwrap_err().code, OpStatusCode::InvalidRoots);
+	}
+}
@@ -0,0 +1,145 @@
+//! Solidity Groth16 verifiers rendered from verifying keys
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalDeserialize;
+#[cfg(feature = "js")]
+use js_sys::{JsString, Uint8Array};
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::proof::format::{g1_coordinates, g2_coordinates, to_decimal, G1Coordinates, G2Coordinates};
+use crate::types::{OpStatusCode, OperationError};
+
+const VERIFIER_TEMPLATE: &str = include_str!("verifier.sol");
+
+/// Render the Solidity verifier contract `name` of a Bn254 verifying key, as
+/// written by `setup_keys`
+///
+/// The contract has the snarkjs `verifyProof(a, b, c, input)` the VAnchor
+/// verifier selector calls, with `input` a fixed size array of the circuit
+/// public inputs, and a `verifyProof(proof, input)` overload taking the
+/// `solidity` proof format. The pairing library is `<name>Pairing` so the
+/// verifiers of several circuits can be compiled together.
+pub fn render_solidity_verifier(vk: &[u8], name: &str) -> Result<String, OperationError> {
+	check_identifier(name)?;
+	let vk = VerifyingKey::<Bn254>::deserialize_uncompressed(vk)
+		.map_err(|e| OperationError::new_with_message(OpStatusCode::InvalidVerifyingKey, e.to_string()))?;
+	// `IC[0]` is the constant term, the other points are one per input
+	if vk.gamma_abc_g1.is_empty() {
+		let message = "The verifying key has no IC points".to_string();
+		return Err(OperationError::new_with_message(
+			OpStatusCode::InvalidVerifyingKey,
+			message,
+		));
+	}
+
+	let mut lines = vec![
+		format!("\t\tvk.alfa1 = {};", g1_point(name, &g1_coordinates(&vk.alpha_g1))),
+		format!("\t\tvk.beta2 = {};", g2_point(name, &g2_coordinates(&vk.beta_g2))),
+		format!("\t\tvk.gamma2 = {};", g2_point(name, &g2_coordinates(&vk.gamma_g2))),
+		format!("\t\tvk.delta2 = {};", g2_point(name, &g2_coordinates(&vk.delta_g2))),
+	];
+	for (i, point) in vk.gamma_abc_g1.iter().enumerate() {
+		lines.push(format!(
+			"\t\tvk.IC[{}] = {};",
+			i,
+			g1_point(name, &g1_coordinates(point))
+		));
+	}
+
+	Ok(VERIFIER_TEMPLATE
+		.replace("{{verifying_key}}", &lines.join("\n"))
+		.replace("{{ic_length}}", &vk.gamma_abc_g1.len().to_string())
+		.replace("{{input_count}}", &(vk.gamma_abc_g1.len() - 1).to_string())
+		.replace("{{name}}", name))
+}
+
+// Contract names end up in the source, anything but an identifier could
+// inject code
+fn check_identifier(name: &str) -> Result<(), OperationError> {
+	let mut chars = name.chars();
+	let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+	if !valid {
+		let message = format!("{:?} isn't a Solidity identifier", name);
+		return Err(OperationError::new_with_message(
+			OpStatusCode::InvalidProofParameters,
+			message,
+		));
+	}
+	Ok(())
+}
+
+fn g1_point(name: &str, coordinates: &Option<G1Coordinates>) -> String {
+	let [x, y] = match coordinates {
+		Some([x, y]) => [to_decimal(x), to_decimal(y)],
+		None => ["0".to_string(), "0".to_string()],
+	};
+	format!("{}Pairing.G1Point({}, {})", name, x, y)
+}
+
+// The precompile takes the imaginary part first
+fn g2_point(name: &str, coordinates: &Option<G2Coordinates>) -> String {
+	let [[x0, x1], [y0, y1]] = match coordinates {
+		Some([[x0, x1], [y0, y1]]) => [[to_decimal(x0), to_decimal(x1)], [to_decimal(y0), to_decimal(y1)]],
+		None => [["0".to_string(), "0".to_string()], ["0".to_string(), "0".to_string()]],
+	};
+	format!("{}Pairing.G2Point([{}, {}], [{}, {}])", name, x1, x0, y1, y0)
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen(js_name = renderSolidityVerifier)]
+pub fn render_solidity_verifier_js(vk: Uint8Array, name: JsString) -> Result<JsString, JsValue> {
+	let name: String = name.into();
+	let source = render_solidity_verifier(&vk.to_vec(), &name)?;
+	Ok(source.into())
+}
+
+#[cfg(test)]
+mod test {
+	use ark_serialize::CanonicalSerialize;
+
+	use super::*;
+	use crate::proof::setup_keys_with_height;
+	use crate::types::{Curve, NoteProtocol};
+
+	#[test]
+	fn should_render_a_verifier_of_the_circuit_inputs() {
+		let (_, vk) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let inputs = VerifyingKey::<Bn254>::deserialize_uncompressed(vk.as_slice())
+			.unwrap()
+			.gamma_abc_g1
+			.len()
+			- 1;
+
+		let source = render_solidity_verifier(&vk, "MixerVerifier16").unwrap();
+		assert!(source.contains("contract MixerVerifier16 {"));
+		assert!(source.contains("library MixerVerifier16Pairing {"));
+		assert!(source.contains(&format!("uint256[{}] memory input", inputs)));
+		assert!(source.contains(&format!("vk.IC[{}] = ", inputs)));
+		assert!(!source.contains("{{"));
+
+		assert_eq!(
+			render_solidity_verifier(&vk, "Verifier {}").unwrap_err().code,
+			OpStatusCode::InvalidProofParameters
+		);
+		assert_eq!(
+			render_solidity_verifier(&vk[1..], "Verifier").unwrap_err().code,
+			OpStatusCode::InvalidVerifyingKey
+		);
+	}
+
+	#[test]
+	fn should_reject_a_key_without_ic_points() {
+		let (_, vk) = setup_keys_with_height(NoteProtocol::Mixer, Curve::Bn254, 16, 2, 2, 2).unwrap();
+		let mut vk = VerifyingKey::<Bn254>::deserialize_uncompressed(vk.as_slice()).unwrap();
+		vk.gamma_abc_g1.clear();
+		let mut bytes = Vec::new();
+		vk.serialize_uncompressed(&mut bytes).unwrap();
+
+		assert_eq!(
+			render_solidity_verifier(&bytes, "Verifier").unwrap_err().code,
+			OpStatusCode::InvalidVerifyingKey
+		);
+	}
+}
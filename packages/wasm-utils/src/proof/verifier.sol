@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: GPL-3.0
+// Groth16 verifier rendered by wasm-utils from a Bn254 verifying key
+pragma solidity ^0.8.0;
+
+library {{name}}Pairing {
+	struct G1Point {
+		uint256 X;
+		uint256 Y;
+	}
+
+	// Coordinates of G2 points are [imaginary, real]
+	struct G2Point {
+		uint256[2] X;
+		uint256[2] Y;
+	}
+
+	uint256 constant PRIME_Q = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+	function negate(G1Point memory p) internal pure returns (G1Point memory) {
+		if (p.X == 0 && p.Y == 0) {
+			return G1Point(0, 0);
+		}
+		return G1Point(p.X, PRIME_Q - (p.Y % PRIME_Q));
+	}
+
+	function addition(G1Point memory p1, G1Point memory p2) internal view returns (G1Point memory r) {
+		uint256[4] memory input = [p1.X, p1.Y, p2.X, p2.Y];
+		bool success;
+		assembly {
+			success := staticcall(sub(gas(), 2000), 6, input, 0x80, r, 0x40)
+		}
+		require(success, "pairing-add-failed");
+	}
+
+	function scalarMul(G1Point memory p, uint256 s) internal view returns (G1Point memory r) {
+		uint256[3] memory input = [p.X, p.Y, s];
+		bool success;
+		assembly {
+			success := staticcall(sub(gas(), 2000), 7, input, 0x60, r, 0x40)
+		}
+		require(success, "pairing-mul-failed");
+	}
+
+	function pairingProd4(
+		G1Point memory a1,
+		G2Point memory a2,
+		G1Point memory b1,
+		G2Point memory b2,
+		G1Point memory c1,
+		G2Point memory c2,
+		G1Point memory d1,
+		G2Point memory d2
+	) internal view returns (bool) {
+		G1Point[4] memory p1 = [a1, b1, c1, d1];
+		G2Point[4] memory p2 = [a2, b2, c2, d2];
+		uint256[24] memory input;
+		for (uint256 i = 0; i < 4; i++) {
+			uint256 j = i * 6;
+			input[j + 0] = p1[i].X;
+			input[j + 1] = p1[i].Y;
+			input[j + 2] = p2[i].X[0];
+			input[j + 3] = p2[i].X[1];
+			input[j + 4] = p2[i].Y[0];
+			input[j + 5] = p2[i].Y[1];
+		}
+		uint256[1] memory out;
+		bool success;
+		assembly {
+			success := staticcall(sub(gas(), 2000), 8, input, 0x300, out, 0x20)
+		}
+		require(success, "pairing-opcode-failed");
+		return out[0] != 0;
+	}
+}
+
+contract {{name}} {
+	uint256 constant SNARK_SCALAR_FIELD = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+
+	struct VerifyingKey {
+		{{name}}Pairing.G1Point alfa1;
+		{{name}}Pairing.G2Point beta2;
+		{{name}}Pairing.G2Point gamma2;
+		{{name}}Pairing.G2Point delta2;
+		{{name}}Pairing.G1Point[{{ic_length}}] IC;
+	}
+
+	function verifyingKey() internal pure returns (VerifyingKey memory vk) {
+{{verifying_key}}
+	}
+
+	/// Verify a proof with `a`, `b` and `c` as exported by snarkjs, the
+	/// signature the VAnchor verifier selector calls
+	function verifyProof(
+		uint256[2] memory a,
+		uint256[2][2] memory b,
+		uint256[2] memory c,
+		uint256[{{input_count}}] memory input
+	) public view returns (bool r) {
+		VerifyingKey memory vk = verifyingKey();
+		{{name}}Pairing.G1Point memory vk_x = {{name}}Pairing.G1Point(0, 0);
+		for (uint256 i = 0; i < input.length; i++) {
+			require(input[i] < SNARK_SCALAR_FIELD, "verifier-gte-snark-scalar-field");
+			vk_x = {{name}}Pairing.addition(vk_x, {{name}}Pairing.scalarMul(vk.IC[i + 1], input[i]));
+		}
+		vk_x = {{name}}Pairing.addition(vk_x, vk.IC[0]);
+		return
+			{{name}}Pairing.pairingProd4(
+				{{name}}Pairing.negate({{name}}Pairing.G1Point(a[0], a[1])),
+				{{name}}Pairing.G2Point(b[0], b[1]),
+				vk.alfa1,
+				vk.beta2,
+				vk_x,
+				vk.gamma2,
+				{{name}}Pairing.G1Point(c[0], c[1]),
+				vk.delta2
+			);
+	}
+
+	/// Verify a proof packed as `uint256[8]`, the `solidity` proof format of
+	/// wasm-utils
+	function verifyProof(bytes memory proof, uint256[{{input_count}}] memory input) public view returns (bool r) {
+		uint256[8] memory p = abi.decode(proof, (uint256[8]));
+		return verifyProof([p[0], p[1]], [[p[2], p[3]], [p[4], p[5]]], [p[6], p[7]], input);
+	}
+}
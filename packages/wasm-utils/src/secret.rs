@@ -0,0 +1,104 @@
+//! Secret values that don't show up in logs
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Holds a secret: `Debug` prints `[REDACTED]` and the value is zeroized on
+/// drop
+///
+/// It derefs to the value and serializes as the value, encodings like the
+/// note string still hold the secret.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+	pub fn new(value: T) -> Self {
+		Self(value)
+	}
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+	fn from(value: T) -> Self {
+		Self(value)
+	}
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+
+impl<T: Zeroize> DerefMut for Secret<T> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.0
+	}
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("[REDACTED]")
+	}
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+	fn drop(&mut self) {
+		self.0.zeroize();
+	}
+}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.0.serialize(serializer)
+	}
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		T::deserialize(deserializer).map(Self)
+	}
+}
+
+/// `:` separated hex encoding of `secrets`, the parts and the string are
+/// wiped on drop
+pub(crate) fn encode_secrets(secrets: &[Vec<u8>]) -> Zeroizing<String> {
+	let parts = Zeroizing::new(secrets.iter().map(hex::encode).collect::<Vec<String>>());
+	Zeroizing::new(parts.join(":"))
+}
+
+#[cfg(test)]
+mod test {
+	use std::cell::Cell;
+	use std::rc::Rc;
+
+	use super::*;
+
+	struct Probe(Rc<Cell<bool>>);
+
+	impl Zeroize for Probe {
+		fn zeroize(&mut self) {
+			self.0.set(true);
+		}
+	}
+
+	#[test]
+	fn should_zeroize_secrets_on_drop() {
+		let zeroized = Rc::new(Cell::new(false));
+		let secret = Secret::new(Probe(zeroized.clone()));
+		assert!(!zeroized.get());
+		drop(secret);
+		assert!(zeroized.get());
+	}
+
+	#[test]
+	fn should_redact_secrets_in_debug_output() {
+		let secret = Secret::new(vec![0xabu8; 32]);
+		assert_eq!(format!("{:?}", secret), "[REDACTED]");
+		assert_eq!(format!("{:?}", Some(secret.clone())), "Some([REDACTED])");
+		assert_eq!(secret.len(), 32);
+	}
+}
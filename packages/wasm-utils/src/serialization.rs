@@ -0,0 +1,191 @@
+//! Serde based encodings for notes, UTXOs, proofs and ext data.
+//!
+//! Byte fields are encoded as hex strings and 128 bits numbers as decimal
+//! strings, so the JSON output is readable from JS without precision loss.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::types::{OpStatusCode, OperationError};
+
+/// JSON (and CBOR with the `cbor` feature) encoding of a type
+pub trait Serializable: Serialize + DeserializeOwned {
+	fn to_json(&self) -> Result<String, OperationError> {
+		serde_json::to_string(self)
+			.map_err(|e| OperationError::new_with_message(OpStatusCode::SerializationFailed, e.to_string()))
+	}
+
+	fn from_json(json: &str) -> Result<Self, OperationError> {
+		serde_json::from_str(json)
+			.map_err(|e| OperationError::new_with_message(OpStatusCode::DeserializationFailed, e.to_string()))
+	}
+
+	#[cfg(feature = "cbor")]
+	fn to_cbor(&self) -> Result<Vec<u8>, OperationError> {
+		serde_cbor::to_vec(self)
+			.map_err(|e| OperationError::new_with_message(OpStatusCode::SerializationFailed, e.to_string()))
+	}
+
+	#[cfg(feature = "cbor")]
+	fn from_cbor(bytes: &[u8]) -> Result<Self, OperationError> {
+		serde_cbor::from_slice(bytes)
+			.map_err(|e| OperationError::new_with_message(OpStatusCode::DeserializationFailed, e.to_string()))
+	}
+}
+
+/// Implements `Serialize`/`Deserialize` through the `Display`/`FromStr`
+/// implementations of the type
+macro_rules! impl_serde_as_str {
+	($($ty:ty),*) => {
+		$(
+			impl serde::Serialize for $ty {
+				fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+					serializer.collect_str(self)
+				}
+			}
+
+			impl<'de> serde::Deserialize<'de> for $ty {
+				fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+					let value = String::deserialize(deserializer)?;
+					value
+						.parse()
+						.map_err(|_| serde::de::Error::custom(format!("unexpected value {}", value)))
+				}
+			}
+		)*
+	};
+}
+pub(crate) use impl_serde_as_str;
+
+/// Bytes as a hex string, usable for `Vec<u8>` and fixed size arrays
+pub(crate) mod hex_bytes {
+	use core::convert::TryFrom;
+	use serde::{de, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<T: AsRef<[u8]>, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&hex::encode(value))
+	}
+
+	pub fn deserialize<'de, T: TryFrom<Vec<u8>>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		let bytes = hex::decode(&value).map_err(de::Error::custom)?;
+		T::try_from(bytes).map_err(|_| de::Error::custom(format!("unexpected length of {}", value)))
+	}
+}
+
+/// A list of byte arrays as a list of hex strings
+pub(crate) mod hex_bytes_list {
+	use serde::{de, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(value.iter().map(hex::encode))
+	}
+
+	pub fn deserialize<'de, T: From<Vec<Vec<u8>>>, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+		let values = Vec::<String>::deserialize(deserializer)?;
+		values
+			.iter()
+			.map(|value| hex::decode(value).map_err(de::Error::custom))
+			.collect::<Result<Vec<_>, _>>()
+			.map(T::from)
+	}
+}
+
+/// Optional bytes as a hex string or `null`
+pub(crate) mod optional_hex_bytes {
+	use serde::{de, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(value: &Option<Vec<u8>>, serializer: S) -> Result<S::Ok, S::Error> {
+		match value {
+			Some(value) => serializer.serialize_str(&hex::encode(value)),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error> {
+		Option::<String>::deserialize(deserializer)?
+			.map(|value| hex::decode(value).map_err(de::Error::custom))
+			.transpose()
+	}
+}
+
+/// Numbers as decimal strings
+pub(crate) mod string {
+	use core::fmt::Display;
+	use core::str::FromStr;
+	use serde::{de, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<T: Display, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(value)
+	}
+
+	pub fn deserialize<'de, T: FromStr, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+		let value = String::deserialize(deserializer)?;
+		value
+			.parse()
+			.map_err(|_| de::Error::custom(format!("invalid number {}", value)))
+	}
+}
+
+/// Converts the JSON encoding into a plain JS object
+#[cfg(feature = "js")]
+pub fn to_js_object<T: Serializable>(value: &T) -> Result<wasm_bindgen::JsValue, OperationError> {
+	let json = value.to_json()?;
+	js_sys::JSON::parse(&json).map_err(|_| OpStatusCode::SerializationFailed.into())
+}
+
+/// Reads a value from either a JS object or its JSON string
+#[cfg(feature = "js")]
+pub fn from_js_value<T: Serializable>(value: &wasm_bindgen::JsValue) -> Result<T, OperationError> {
+	let json: String = match value.as_string() {
+		Some(json) => json,
+		None => js_sys::JSON::stringify(value)
+			.map_err(|_| OpStatusCode::DeserializationFailed)?
+			.into(),
+	};
+	T::from_json(&json)
+}
+
+/// Exposes `toJSON`/`fromJSON` (and `toCBOR`/`fromCBOR`) on a wasm type,
+/// `$inner` is the `Serializable` type the wasm type derefs to.
+#[cfg(feature = "js")]
+macro_rules! impl_js_serialization {
+	($js:ident => $inner:ty) => {
+		#[wasm_bindgen::prelude::wasm_bindgen]
+		impl $js {
+			/// Called by `JSON.stringify`
+			#[wasm_bindgen(js_name = toJSON)]
+			pub fn js_to_json(&self) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+				let value: &$inner = self;
+				Ok($crate::serialization::to_js_object(value)?)
+			}
+
+			#[wasm_bindgen(js_name = fromJSON)]
+			pub fn js_from_json(value: wasm_bindgen::JsValue) -> Result<$js, wasm_bindgen::JsValue> {
+				let value: $inner = $crate::serialization::from_js_value(&value)?;
+				Ok(value.into())
+			}
+		}
+
+		#[cfg(feature = "cbor")]
+		#[wasm_bindgen::prelude::wasm_bindgen]
+		impl $js {
+			#[wasm_bindgen(js_name = toCBOR)]
+			pub fn js_to_cbor(&self) -> Result<js_sys::Uint8Array, wasm_bindgen::JsValue> {
+				let value: &$inner = self;
+				let bytes = $crate::serialization::Serializable::to_cbor(value)?;
+				Ok(js_sys::Uint8Array::from(bytes.as_slice()))
+			}
+
+			#[wasm_bindgen(js_name = fromCBOR)]
+			pub fn js_from_cbor(bytes: js_sys::Uint8Array) -> Result<$js, wasm_bindgen::JsValue> {
+				let value: $inner = $crate::serialization::Serializable::from_cbor(&bytes.to_vec())?;
+				Ok(value.into())
+			}
+		}
+	};
+	($js:ident) => {
+		impl_js_serialization!($js => $js);
+	};
+}
+#[cfg(feature = "js")]
+pub(crate) use impl_js_serialization;
@@ -0,0 +1,236 @@
+//! Registry of the tokens notes and UTXOs are denominated in
+#[cfg(feature = "js")]
+use js_sys::JsString;
+#[cfg(feature = "js")]
+use wasm_bindgen::prelude::*;
+
+use crate::amount::{format_units, parse_units};
+#[cfg(feature = "js")]
+use crate::note::JsNote;
+use crate::note::Note;
+use crate::types::{OpStatusCode, OperationError};
+
+/// A token deployed on a chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenMetadata {
+	pub symbol: String,
+	pub decimals: u8,
+	pub chain_id: u64,
+}
+
+/// Token metadata wallets pass in, to parse and format amounts and to
+/// validate notes against
+#[cfg_attr(feature = "js", wasm_bindgen)]
+#[derive(Debug, Clone, Default)]
+pub struct TokenRegistry {
+	tokens: Vec<TokenMetadata>,
+}
+
+impl TokenRegistry {
+	/// Add a token, replacing the token of the same symbol on the same chain
+	pub fn register(&mut self, token: TokenMetadata) {
+		self.tokens
+			.retain(|t| t.symbol != token.symbol || t.chain_id != token.chain_id);
+		self.tokens.push(token);
+	}
+
+	pub fn get(&self, symbol: &str, chain_id: u64) -> Result<&TokenMetadata, OperationError> {
+		self.tokens
+			.iter()
+			.find(|t| t.symbol == symbol && t.chain_id == chain_id)
+			.ok_or_else(|| {
+				let message = format!("Token {} isn't registered on chain {}", symbol, chain_id);
+				OperationError::new_with_message(OpStatusCode::InvalidTokenSymbol, message)
+			})
+	}
+
+	/// Base units of the decimal `amount` of a token
+	pub fn parse_amount(&self, symbol: &str, chain_id: u64, amount: &str) -> Result<u128, OperationError> {
+		let token = self.get(symbol, chain_id)?;
+		parse_units(amount, token.decimals)
+	}
+
+	/// Decimal amount of the base units `amount` of a token
+	pub fn format_amount(&self, symbol: &str, chain_id: u64, amount: u128) -> Result<String, OperationError> {
+		let token = self.get(symbol, chain_id)?;
+		Ok(format_units(amount, token.decimals))
+	}
+
+	/// Check the note token is registered on its target chain, with the
+	/// note's denomination as decimals, and that its amount is valid and
+	/// matches the UTXO of VAnchor notes
+	pub fn check_note(&self, note: &Note) -> Result<(), OperationError> {
+		let symbol = note.token_symbol.as_deref().ok_or(OpStatusCode::InvalidTokenSymbol)?;
+		let chain_id: u64 = note
+			.target_chain_id
+			.parse()
+			.map_err(|_| OpStatusCode::InvalidTargetChain)?;
+		let token = self.get(symbol, chain_id)?;
+		if note.denomination != Some(token.decimals) {
+			let message = format!(
+				"Note denomination {:?} doesn't match the {} decimals of {}",
+				note.denomination, token.decimals, symbol
+			);
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidDenomination,
+				message,
+			));
+		}
+		note.check_utxo_amount()
+	}
+}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+impl TokenRegistry {
+	#[wasm_bindgen(constructor)]
+	pub fn new() -> TokenRegistry {
+		Self::default()
+	}
+
+	#[wasm_bindgen(js_name = register)]
+	pub fn register_js(&mut self, symbol: JsString, decimals: u8, chain_id: JsString) -> Result<(), JsValue> {
+		let chain_id: String = chain_id.into();
+		let chain_id = chain_id.parse().map_err(|_| OpStatusCode::InvalidChainId)?;
+		self.register(TokenMetadata {
+			symbol: symbol.into(),
+			decimals,
+			chain_id,
+		});
+		Ok(())
+	}
+
+	/// Decimals of a registered token
+	pub fn decimals(&self, symbol: JsString, chain_id: JsString) -> Result<u8, JsValue> {
+		let symbol: String = symbol.into();
+		let chain_id: String = chain_id.into();
+		let chain_id = chain_id.parse().map_err(|_| OpStatusCode::InvalidChainId)?;
+		Ok(self.get(&symbol, chain_id)?.decimals)
+	}
+
+	#[wasm_bindgen(js_name = parseAmount)]
+	pub fn parse_amount_js(&self, symbol: JsString, chain_id: JsString, amount: JsString) -> Result<JsString, JsValue> {
+		let symbol: String = symbol.into();
+		let chain_id: String = chain_id.into();
+		let chain_id = chain_id.parse().map_err(|_| OpStatusCode::InvalidChainId)?;
+		let amount: String = amount.into();
+		let amount = self.parse_amount(&symbol, chain_id, &amount)?;
+		Ok(JsString::from(amount.to_string()))
+	}
+
+	#[wasm_bindgen(js_name = formatAmount)]
+	pub fn format_amount_js(
+		&self,
+		symbol: JsString,
+		chain_id: JsString,
+		amount: JsString,
+	) -> Result<JsString, JsValue> {
+		let symbol: String = symbol.into();
+		let chain_id: String = chain_id.into();
+		let chain_id = chain_id.parse().map_err(|_| OpStatusCode::InvalidChainId)?;
+		let amount: String = amount.into();
+		let amount = parse_units(&amount, 0)?;
+		Ok(JsString::from(self.format_amount(&symbol, chain_id, amount)?))
+	}
+
+	#[wasm_bindgen(js_name = checkNote)]
+	pub fn check_note_js(&self, note: &JsNote) -> Result<(), JsValue> {
+		self.check_note(note)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::note::NoteBuilder;
+	use crate::types::{Curve, HashFunction, NoteProtocol, NoteVersion};
+
+	fn registry() -> TokenRegistry {
+		let mut registry = TokenRegistry::default();
+		registry.register(TokenMetadata {
+			symbol: "EDG".to_string(),
+			decimals: 18,
+			chain_id: 2,
+		});
+		registry.register(TokenMetadata {
+			symbol: "USDC".to_string(),
+			decimals: 6,
+			chain_id: 2,
+		});
+		registry
+	}
+
+	#[test]
+	fn should_parse_and_format_registered_token_amounts() {
+		let registry = registry();
+		assert_eq!(registry.parse_amount("USDC", 2, "1.5").unwrap(), 1_500_000);
+		assert_eq!(registry.format_amount("EDG", 2, 10u128.pow(18)).unwrap(), "1");
+		assert_eq!(
+			registry.parse_amount("USDC", 3, "1").unwrap_err().code,
+			OpStatusCode::InvalidTokenSymbol
+		);
+		assert_eq!(
+			registry.parse_amount("USDC", 2, "0.0000001").unwrap_err().code,
+			OpStatusCode::InvalidAmount
+		);
+	}
+
+	#[test]
+	fn should_check_notes_against_the_registry() {
+		let registry = registry();
+		let mixer_note: Note = "webb://v1:mixer/2:2/2:2/fd717cfe463b3ffec71ee6b7606bbd0179170510abf41c9f16c1d20ca9923f0e:18b6b080e6a43262f00f6fb3da0d2409c4871b8f26d89d5c8836358e1af5a41c/?curve=Bn254&width=3&exp=5&hf=Poseidon&backend=Arkworks&token=EDG&denom=18&amount=10&index=10"
+			.parse()
+			.unwrap();
+		registry.check_note(&mixer_note).unwrap();
+
+		let mut no_amount = mixer_note.clone();
+		no_amount.amount = None;
+		registry.check_note(&no_amount).unwrap();
+
+		let mut wrong_denomination = mixer_note.clone();
+		wrong_denomination.denomination = Some(6);
+		assert_eq!(
+			registry.check_note(&wrong_denomination).unwrap_err().code,
+			OpStatusCode::InvalidDenomination
+		);
+
+		let vanchor_note = NoteBuilder {
+			protocol: Some(NoteProtocol::VAnchor),
+			version: Some(NoteVersion::V1),
+			source_chain_id: Some("2".to_string()),
+			target_chain_id: Some("3".to_string()),
+			source_identifying_data: Some("2".to_string()),
+			target_identifying_data: Some("3".to_string()),
+			amount: Some("10".to_string()),
+			denomination: Some(18),
+			token_symbol: Some("EDG".to_string()),
+			curve: Some(Curve::Bn254),
+			hash_function: Some(HashFunction::Poseidon),
+			exponentiation: Some(5),
+			width: Some(5),
+			index: Some(10),
+			..Default::default()
+		}
+		.build()
+		.unwrap();
+		assert_eq!(
+			registry.check_note(&vanchor_note).unwrap_err().code,
+			OpStatusCode::InvalidTokenSymbol
+		);
+
+		let mut registry = registry;
+		registry.register(TokenMetadata {
+			symbol: "EDG".to_string(),
+			decimals: 18,
+			chain_id: 3,
+		});
+		registry.check_note(&vanchor_note).unwrap();
+		let mut wrong_amount = vanchor_note;
+		wrong_amount.amount = Some("11".to_string());
+		assert_eq!(
+			registry.check_note(&wrong_amount).unwrap_err().code,
+			OpStatusCode::InvalidAmount
+		);
+	}
+}
@@ -1,16 +1,20 @@
 #![allow(clippy::unused_unit)]
+#[cfg(feature = "js")]
 use core::convert::{TryFrom, TryInto};
 use core::fmt;
 use core::ops::Deref;
 use core::str::FromStr;
 
 use arkworks_setups::Curve as ArkCurve;
+#[cfg(feature = "js")]
 use js_sys::{JsString, Uint8Array};
-use wasm_bindgen::__rt::core::fmt::Formatter;
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
 
+use crate::serialization::impl_serde_as_str;
+
 /// Final Operation Error
-#[cfg(not(test))]
+#[cfg(all(feature = "js", not(test)))]
 #[wasm_bindgen]
 #[derive(PartialEq, Eq, Debug)]
 pub struct OperationError {
@@ -23,7 +27,7 @@ pub struct OperationError {
 }
 
 #[allow(clippy::unused_unit)]
-#[cfg(not(test))]
+#[cfg(all(feature = "js", not(test)))]
 #[wasm_bindgen]
 impl OperationError {
 	#[wasm_bindgen(js_name = code)]
@@ -54,15 +58,16 @@ impl OperationError {
 		}
 	}
 }
-/// For tests this will have a custom JsValue conversion
-#[cfg(test)]
+/// For tests this will have a custom JsValue conversion, native builds use it
+/// as a plain Rust error
+#[cfg(any(not(feature = "js"), test))]
 #[derive(PartialEq, Eq, Debug)]
 pub struct OperationError {
 	pub code: OpStatusCode,
 	pub error_message: String,
 	pub data: Option<String>,
 }
-#[cfg(test)]
+#[cfg(all(feature = "js", test))]
 impl OperationError {
 	pub fn code(&self) -> JsValue {
 		JsValue::from(self.code.clone() as u32)
@@ -80,7 +85,7 @@ impl OperationError {
 	}
 }
 
-#[cfg(test)]
+#[cfg(all(feature = "js", test))]
 impl From<OperationError> for JsValue {
 	fn from(e: OperationError) -> Self {
 		JsValue::from(e.to_string())
@@ -88,17 +93,19 @@ impl From<OperationError> for JsValue {
 }
 
 impl fmt::Display for OperationError {
-	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		write!(
 			f,
 			"Code {}, message {}, data {}",
 			self.code.clone() as u32,
-			self.error_message.clone(),
-			self.data()
+			self.error_message,
+			self.data.as_deref().unwrap_or("{}")
 		)
 	}
 }
 
+impl std::error::Error for OperationError {}
+
 impl OperationError {
 	pub fn new_with_message(code: OpStatusCode, message: String) -> Self {
 		let mut oe: Self = code.into();
@@ -141,9 +148,12 @@ pub enum HashFunction {
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum NoteProtocol {
 	Mixer,
+	Anchor,
 	VAnchor,
 }
 
+impl_serde_as_str!(NoteVersion, Backend, Curve, HashFunction, NoteProtocol);
+
 impl fmt::Display for NoteVersion {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
@@ -220,6 +230,7 @@ impl FromStr for NoteProtocol {
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		match s {
 			"mixer" => Ok(NoteProtocol::Mixer),
+			"anchor" => Ok(NoteProtocol::Anchor),
 			"vanchor" => Ok(NoteProtocol::VAnchor),
 			_ => Err(OpStatusCode::InvalidNoteProtocol),
 		}
@@ -230,6 +241,7 @@ impl fmt::Display for NoteProtocol {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			NoteProtocol::Mixer => write!(f, "mixer"),
+			NoteProtocol::Anchor => write!(f, "anchor"),
 			NoteProtocol::VAnchor => write!(f, "vanchor"),
 		}
 	}
@@ -367,8 +379,37 @@ pub enum OpStatusCode {
 	InvalidOutputUtxoConfig = 55,
 	InvalidExtDataHash = 56,
 	InvalidInputUtxoConfig = 57,
-}
-
+	/// Unsupported version prefix of a serialized UTXO
+	InvalidUtxoVersion = 58,
+	/// Invalid number of UTXO parts when decoding
+	InvalidUtxoLength = 59,
+	/// Checksum of a versioned UTXO doesn't match its content
+	InvalidUtxoChecksum = 60,
+	/// Invalid UTXO blinding
+	InvalidBlinding = 61,
+	/// Invalid UTXO public key
+	InvalidPublicKey = 62,
+	/// Invalid UTXO secret key
+	InvalidSecretKey = 63,
+	/// Spendable UTXOs don't cover the requested amount
+	InsufficientFunds = 64,
+	/// Merkle tree height the provers aren't compiled for
+	InvalidTreeHeight = 65,
+	/// Note metadata that doesn't match the note secrets
+	InvalidNoteIntegrity = 66,
+	/// The global thread pool is already running or its threads can't start
+	InvalidThreadPool = 67,
+	/// Proof bytes that don't decode in the given proof format
+	InvalidProofFormat = 68,
+	/// Verifying key bytes that don't decode
+	InvalidVerifyingKey = 69,
+	/// Merkle tree snapshot that doesn't fit its height
+	InvalidTreeSnapshot = 70,
+	/// Circuit or key generation failed
+	KeySetupFailed = 71,
+}
+
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 extern "C" {
 	// Use `js_namespace` here to bind `console.log(..)` instead of just
@@ -399,29 +440,43 @@ extern "C" {
 	#[wasm_bindgen(typescript_type = "Indices")]
 	pub type Indices;
 
+	#[wasm_bindgen(typescript_type = "ProofFormat")]
+	pub type WasmProofFormat;
+
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
-const NOTE_PROTOCOL: &str = "type NoteProtocol = 'mixer' | 'vanchor' ";
+const NOTE_PROTOCOL: &str = "type NoteProtocol = 'mixer' | 'anchor' | 'vanchor' ";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const LEAVES: &str = "type Leaves = Array<Uint8Array>;";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const INDICES: &str = "type Indices = Array<number>;";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const HF: &str = "type HashFunction = 'Poseidon' | 'MiMC'";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const CURVE: &str = "type Curve = 'Bls381' | 'Bn254'";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const VERSION: &str = "type Version = 'v1'";
 
+#[cfg(feature = "js")]
 #[wasm_bindgen(typescript_custom_section)]
 const BE: &str = "type Backend = 'Arkworks' | 'Circom'";
 
+#[cfg(feature = "js")]
+#[wasm_bindgen(typescript_custom_section)]
+const PROOF_FORMAT: &str = "type ProofFormat = 'compressed' | 'uncompressed' | 'solidity' | 'snarkjs'";
+
 pub struct Uint8Arrayx32(pub [u8; 32]);
 
 impl Deref for Uint8Arrayx32 {
@@ -432,6 +487,7 @@ impl Deref for Uint8Arrayx32 {
 	}
 }
 
+#[cfg(feature = "js")]
 impl TryFrom<Uint8Array> for Uint8Arrayx32 {
 	type Error = OpStatusCode;
 
@@ -506,6 +562,20 @@ impl From<OpStatusCode> for String {
 			OpStatusCode::InvalidOutputUtxoConfig => "Invalid output UTXO config",
 			OpStatusCode::InvalidExtDataHash => "Invalid external data hash",
 			OpStatusCode::InvalidInputUtxoConfig => "Invalid input UTXO config",
+			OpStatusCode::InvalidUtxoVersion => "Unsupported UTXO version",
+			OpStatusCode::InvalidUtxoLength => "Invalid UTXO length",
+			OpStatusCode::InvalidUtxoChecksum => "Invalid UTXO checksum",
+			OpStatusCode::InvalidBlinding => "Invalid blinding",
+			OpStatusCode::InvalidPublicKey => "Invalid public key",
+			OpStatusCode::InvalidSecretKey => "Invalid secret key",
+			OpStatusCode::InsufficientFunds => "Insufficient funds",
+			OpStatusCode::InvalidTreeHeight => "Unsupported tree height",
+			OpStatusCode::InvalidNoteIntegrity => "Note metadata doesn't match its secrets",
+			OpStatusCode::InvalidThreadPool => "Thread pool can't be started",
+			OpStatusCode::InvalidProofFormat => "Invalid proof format",
+			OpStatusCode::InvalidVerifyingKey => "Invalid verifying key",
+			OpStatusCode::InvalidTreeSnapshot => "Invalid Merkle tree snapshot",
+			OpStatusCode::KeySetupFailed => "Failed to generate keys",
 		}
 		.to_string()
 	}
@@ -520,6 +590,7 @@ impl From<OpStatusCode> for OperationError {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<OpStatusCode> for JsValue {
 	fn from(e: OpStatusCode) -> Self {
 		let op: OperationError = e.into();
@@ -527,36 +598,42 @@ impl From<OpStatusCode> for JsValue {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<Backend> for JsString {
 	fn from(e: Backend) -> Self {
 		JsString::from(e.to_string())
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<Curve> for JsString {
 	fn from(e: Curve) -> Self {
 		JsString::from(e.to_string())
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<HashFunction> for JsString {
 	fn from(e: HashFunction) -> Self {
 		JsString::from(e.to_string())
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<NoteVersion> for JsString {
 	fn from(e: NoteVersion) -> Self {
 		JsString::from(e.to_string())
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<NoteProtocol> for JsString {
 	fn from(e: NoteProtocol) -> Self {
 		JsString::from(e.to_string())
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<Curve> for WasmCurve {
 	fn from(curve: Curve) -> Self {
 		let js_str = curve.to_string();
@@ -564,6 +641,7 @@ impl From<Curve> for WasmCurve {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<HashFunction> for HF {
 	fn from(hash_function: HashFunction) -> Self {
 		let js_str = hash_function.to_string();
@@ -571,6 +649,7 @@ impl From<HashFunction> for HF {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<NoteVersion> for Version {
 	fn from(version: NoteVersion) -> Self {
 		let js_str = version.to_string();
@@ -578,6 +657,7 @@ impl From<NoteVersion> for Version {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<Backend> for BE {
 	fn from(backend: Backend) -> Self {
 		let js_str = backend.to_string();
@@ -585,6 +665,7 @@ impl From<Backend> for BE {
 	}
 }
 
+#[cfg(feature = "js")]
 impl From<NoteProtocol> for Protocol {
 	fn from(proto: NoteProtocol) -> Self {
 		let js_str = proto.to_string();
@@ -592,7 +673,7 @@ impl From<NoteProtocol> for Protocol {
 	}
 }
 
-#[cfg(not(test))]
+#[cfg(all(feature = "js", not(test)))]
 #[allow(clippy::unused_unit)]
 #[wasm_bindgen(start)]
 pub fn main() {
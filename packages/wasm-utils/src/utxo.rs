@@ -1,53 +1,69 @@
 use core::fmt;
 use core::fmt::Formatter;
+#[cfg(feature = "js")]
+use core::ops::Deref;
 use core::str::FromStr;
 
+use ark_bls12_381::Fr as Bls381Fr;
 use ark_bn254::Fr as Bn254Fr;
 use ark_ff::{BigInteger, PrimeField};
 use ark_std::UniformRand;
 use arkworks_native_gadgets::poseidon::Poseidon;
 use arkworks_setups::common::setup_params;
-use arkworks_setups::utxo::Utxo;
+use arkworks_setups::utxo::Utxo as ArkUtxo;
 use arkworks_setups::{Curve as ArkCurve, VAnchorProver};
+use ethabi::Uint as U256;
+#[cfg(feature = "js")]
 use js_sys::{JsString, Uint8Array};
 use rand::rngs::OsRng;
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+use tiny_keccak::{Hasher, Keccak};
+#[cfg(feature = "js")]
 use wasm_bindgen::prelude::*;
 
-use crate::types::{Backend, Curve, OpStatusCode, OperationError, WasmCurve, BE};
-use crate::VAnchorR1CSProverBn254_30_2_2_2;
-
+#[cfg(feature = "js")]
+use crate::amount::format_units;
+use crate::amount::Amount;
+use crate::secret::{encode_secrets, Secret};
+use crate::serialization::{hex_bytes, optional_hex_bytes, string, Serializable};
+#[cfg(feature = "js")]
+use crate::serialization::{impl_js_serialization, to_js_object};
+use crate::types::{Backend, Curve, OpStatusCode, OperationError};
+#[cfg(feature = "js")]
+use crate::types::{WasmCurve, BE};
+use crate::{VAnchorR1CSProverBls381_30_2_2_2, VAnchorR1CSProverBn254_30_2_2_2};
+
+/// A VAnchor UTXO over one of the supported curves
 #[derive(Clone)]
-pub enum JsUtxoInner {
-	Bn254(Utxo<Bn254Fr>),
+pub enum Utxo {
+	Bn254(ArkUtxo<Bn254Fr>),
+	Bls381(ArkUtxo<Bls381Fr>),
 }
-impl fmt::Debug for JsUtxoInner {
+/// Only prints the public fields, see `Utxo::redacted`
+impl fmt::Debug for Utxo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "JsUtxoInner")
+		f.debug_tuple("Utxo").field(&self.redacted()).finish()
 	}
 }
 
-impl JsUtxoInner {
-	// When the index is set on the wasm-utils object, recalculate the nullifier for
-	// the utxo.
-	pub fn set_index(&mut self, val: u64) {
-		match self {
-			JsUtxoInner::Bn254(utxo) => {
-				utxo.set_index(val);
-			}
-		}
-	}
+/// Public view of a `Utxo`, see `Utxo::redacted`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactedUtxo {
+	pub curve: Curve,
+	pub chain_id: u64,
+	#[serde(with = "string")]
+	pub amount: Amount,
+	pub index: Option<u64>,
+	#[serde(with = "hex_bytes")]
+	pub commitment: Vec<u8>,
 }
 
-#[wasm_bindgen]
-#[derive(Clone, Debug)]
-pub struct JsUtxo {
-	#[wasm_bindgen(skip)]
-	pub inner: JsUtxoInner,
-}
+impl Serializable for RedactedUtxo {}
 
-impl JsUtxo {
+impl Utxo {
 	#[allow(clippy::too_many_arguments)]
-	pub(crate) fn new(
+	pub fn new(
 		curve: Curve,
 		backend: Backend,
 		amount: u128,
@@ -56,7 +72,7 @@ impl JsUtxo {
 		public_key: Option<Vec<u8>>,
 		private_key: Option<Vec<u8>>,
 		index: Option<u64>,
-	) -> Result<JsUtxo, OperationError> {
+	) -> Result<Utxo, OperationError> {
 		let mut rng = OsRng;
 		let utxo = match (curve, backend) {
 			(Curve::Bn254, Backend::Arkworks) => {
@@ -99,88 +115,163 @@ impl JsUtxo {
 				}
 			}
 			.map_err(|_| OpStatusCode::InvalidOutputUtxoConfig)
-			.map(JsUtxo::new_from_bn254_utxo),
+			.map(Utxo::new_from_bn254_utxo),
+			(Curve::Bls381, Backend::Arkworks) => {
+				let blinding = blinding.unwrap_or_else(|| Bls381Fr::rand(&mut rng).into_repr().to_bytes_be());
+
+				match (private_key, public_key) {
+					(Some(priv_key), _) => VAnchorR1CSProverBls381_30_2_2_2::create_utxo(
+						ArkCurve::Bls381,
+						chain_id,
+						amount,
+						index,
+						priv_key,
+						blinding,
+					),
+					(None, Some(pub_key)) => VAnchorR1CSProverBls381_30_2_2_2::create_public_utxo(
+						ArkCurve::Bls381,
+						chain_id,
+						amount,
+						blinding,
+						pub_key,
+						index,
+					),
+					(None, None) => VAnchorR1CSProverBls381_30_2_2_2::create_random_utxo(
+						ArkCurve::Bls381,
+						chain_id,
+						amount,
+						index,
+						&mut rng,
+					),
+				}
+			}
+			.map_err(|_| OpStatusCode::InvalidOutputUtxoConfig)
+			.map(Utxo::new_from_bls381_utxo),
 			_ => Err(OpStatusCode::InvalidNoteProtocol),
 		}?;
 
 		Ok(utxo)
 	}
 
-	pub fn get_amount_raw(&self) -> u128 {
-		let amount_bytes = self.get_amount();
-		let mut amount_slice = [0u8; 16];
-		amount_slice.copy_from_slice(amount_bytes[16..32].to_vec().as_slice());
-		u128::from_be_bytes(amount_slice)
+	/// The commitment, chain, amount and index of the UTXO, safe to log or
+	/// attach to error reports
+	///
+	/// The `Display` output is the UTXO string and holds the secret key and
+	/// blinding.
+	pub fn redacted(&self) -> RedactedUtxo {
+		RedactedUtxo {
+			curve: self.curve(),
+			chain_id: self.get_chain_id_raw(),
+			amount: self.get_amount_value(),
+			index: self.get_index(),
+			commitment: self.get_commitment(),
+		}
+	}
+
+	pub fn curve(&self) -> Curve {
+		match self {
+			Utxo::Bn254(_) => Curve::Bn254,
+			Utxo::Bls381(_) => Curve::Bls381,
+		}
+	}
+
+	/// The amount, fails with `InvalidAmount` when it doesn't fit in the
+	/// 128 bits UTXOs are created with
+	pub fn get_amount_raw(&self) -> Result<u128, OperationError> {
+		let amount = self.get_amount_value();
+		amount.to_u128().ok_or_else(|| {
+			let message = format!("The UTXO amount {} doesn't fit in 128 bits", amount);
+			OperationError::new_with_message(OpStatusCode::InvalidAmount, message)
+		})
+	}
+
+	/// All the 256 bits of the amount field element
+	pub fn get_amount_value(&self) -> Amount {
+		Amount::from(U256::from_big_endian(&self.get_amount()))
 	}
 
 	pub fn default_bn254_utxo() -> Self {
 		let utxo =
 			VAnchorR1CSProverBn254_30_2_2_2::create_random_utxo(ArkCurve::Bn254, 0, 0, None, &mut OsRng).unwrap();
-		Self {
-			inner: JsUtxoInner::Bn254(utxo),
-		}
+		Utxo::Bn254(utxo)
 	}
 
-	/// Create `JsUtxo` from a Utxo with bn254 Fr
-	pub fn new_from_bn254_utxo(utxo: Utxo<Bn254Fr>) -> Self {
-		Self {
-			inner: JsUtxoInner::Bn254(utxo),
+	/// Create `Utxo` from a Utxo with bn254 Fr
+	pub fn new_from_bn254_utxo(utxo: ArkUtxo<Bn254Fr>) -> Self {
+		Utxo::Bn254(utxo)
+	}
+
+	/// Create `Utxo` from a Utxo with bls381 Fr
+	pub fn new_from_bls381_utxo(utxo: ArkUtxo<Bls381Fr>) -> Self {
+		Utxo::Bls381(utxo)
+	}
+
+	// When the index is set, recalculate the nullifier for the utxo.
+	pub fn set_index(&mut self, val: u64) {
+		match self {
+			Utxo::Bn254(utxo) => {
+				utxo.set_index(val);
+			}
+			Utxo::Bls381(utxo) => {
+				utxo.set_index(val);
+			}
 		}
 	}
 
 	/// Getters for inner enum
 	pub fn get_chain_id_raw(&self) -> u64 {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.chain_id_raw,
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.chain_id_raw,
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.chain_id_raw,
 		}
 	}
 
 	pub fn get_chain_id_bytes(&self) -> Vec<u8> {
-		self.chain_id_raw().to_be_bytes().to_vec()
+		self.get_chain_id_raw().to_be_bytes().to_vec()
 	}
 
 	pub fn get_amount(&self) -> Vec<u8> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.amount.into_repr().to_bytes_be(),
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.amount.into_repr().to_bytes_be(),
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.amount.into_repr().to_bytes_be(),
 		}
 	}
 
 	pub fn get_blinding(&self) -> Vec<u8> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.blinding.into_repr().to_bytes_be(),
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.blinding.into_repr().to_bytes_be(),
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.blinding.into_repr().to_bytes_be(),
 		}
 	}
 
 	pub fn get_public_key(&self) -> Vec<u8> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.keypair.public_key.into_repr().to_bytes_be(),
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.keypair.public_key.into_repr().to_bytes_be(),
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.keypair.public_key.into_repr().to_bytes_be(),
 		}
 	}
 
 	pub fn get_secret_key(&self) -> Option<Vec<u8>> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.keypair.secret_key.map(|key| key.into_repr().to_bytes_be()),
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.keypair.secret_key.map(|key| key.into_repr().to_bytes_be()),
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.keypair.secret_key.map(|key| key.into_repr().to_bytes_be()),
 		}
 	}
 
 	pub fn get_index(&self) -> Option<u64> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.index,
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.index,
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.index,
 		}
 	}
 
 	pub fn get_index_bytes(&self) -> Vec<u8> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => match bn254_utxo.index {
-				None => 0u64.to_be_bytes().to_vec(),
-				Some(index) => index.to_be_bytes().to_vec(),
-			},
-		}
+		self.get_index().unwrap_or(0).to_be_bytes().to_vec()
 	}
 
 	pub fn get_nullifier(&self) -> Result<Vec<u8>, OpStatusCode> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => {
+		match self {
+			Utxo::Bn254(bn254_utxo) => {
 				let params4 = setup_params::<Bn254Fr>(ArkCurve::Bn254, 5, 4);
 				let hasher4 = Poseidon::<Bn254Fr>::new(params4);
 				let nullifier = bn254_utxo.calculate_nullifier(&hasher4);
@@ -189,30 +280,278 @@ impl JsUtxo {
 					Err(_) => Err(OpStatusCode::InvalidNullifer),
 				}
 			}
+			Utxo::Bls381(bls381_utxo) => {
+				let params4 = setup_params::<Bls381Fr>(ArkCurve::Bls381, 5, 4);
+				let hasher4 = Poseidon::<Bls381Fr>::new(params4);
+				let nullifier = bls381_utxo.calculate_nullifier(&hasher4);
+				match nullifier {
+					Ok(val) => Ok(val.into_repr().to_bytes_be()),
+					Err(_) => Err(OpStatusCode::InvalidNullifer),
+				}
+			}
 		}
 	}
 
 	pub fn get_commitment(&self) -> Vec<u8> {
-		match &self.inner {
-			JsUtxoInner::Bn254(bn254_utxo) => bn254_utxo.commitment.into_repr().to_bytes_be(),
+		match self {
+			Utxo::Bn254(bn254_utxo) => bn254_utxo.commitment.into_repr().to_bytes_be(),
+			Utxo::Bls381(bls381_utxo) => bls381_utxo.commitment.into_repr().to_bytes_be(),
+		}
+	}
+
+	pub fn get_bn254_utxo(&self) -> Result<ArkUtxo<Bn254Fr>, OpStatusCode> {
+		match self.clone() {
+			Utxo::Bn254(utxo) => Ok(utxo),
+			_ => Err(OpStatusCode::InvalidCurve),
+		}
+	}
+
+	pub fn get_bls381_utxo(&self) -> Result<ArkUtxo<Bls381Fr>, OpStatusCode> {
+		match self.clone() {
+			Utxo::Bls381(utxo) => Ok(utxo),
+			_ => Err(OpStatusCode::InvalidCurve),
+		}
+	}
+
+	pub fn deserialize(utxo_str: &str) -> Result<Utxo, OperationError> {
+		utxo_str.parse()
+	}
+}
+
+/// Prefix of the versioned UTXO encoding
+pub const UTXO_V1_PREFIX: &str = "v1:";
+/// Number of `&` separated parts of a serialized UTXO
+const UTXO_PARTS: usize = 8;
+/// Number of keccak bytes kept as checksum
+const UTXO_CHECKSUM_LENGTH: usize = 4;
+
+/// Checksum of a versioned UTXO, the first bytes of `keccak256(prefix ++ body)`
+fn utxo_checksum(body: &str) -> String {
+	let mut keccak = Keccak::v256();
+	keccak.update(UTXO_V1_PREFIX.as_bytes());
+	keccak.update(body.as_bytes());
+	let mut output = [0u8; 32];
+	keccak.finalize(&mut output);
+	hex::encode(&output[..UTXO_CHECKSUM_LENGTH])
+}
+
+/// Decode a 32 bytes hex field, failing with `code`
+fn decode_utxo_field(value: &str, code: OpStatusCode) -> Result<Vec<u8>, OperationError> {
+	let bytes = hex::decode(value).map_err(|e| OperationError::new_with_message(code.clone(), e.to_string()))?;
+	if bytes.len() != 32 {
+		return Err(OperationError::new_with_message(
+			code,
+			format!("Expected 32 bytes, got {}", bytes.len()),
+		));
+	}
+	Ok(bytes)
+}
+
+impl Utxo {
+	/// The `&` separated parts of the UTXO, without version or checksum
+	///
+	/// `curve&backend&amount&chain_id&blinding&public_key&secret_key&index`
+	pub fn serialize_unversioned(&self) -> String {
+		let curve = self.curve().to_string();
+		let backend = Backend::Arkworks.to_string();
+		let amount = self.get_amount_value().to_string();
+		let chain_id = self.get_chain_id_raw().to_string();
+		let index = self.get_index().map(|v| v.to_string()).unwrap_or_default();
+		let blinding = encode_secrets(&Secret::new(vec![self.get_blinding()]));
+		let public_key = hex::encode(self.get_public_key());
+		let private_key = encode_secrets(&Secret::new(vec![self.get_secret_key().unwrap_or_default()]));
+
+		[
+			curve.as_str(),
+			backend.as_str(),
+			amount.as_str(),
+			chain_id.as_str(),
+			blinding.as_str(),
+			public_key.as_str(),
+			private_key.as_str(),
+			index.as_str(),
+		]
+		.join("&")
+	}
+
+	/// Parse the unversioned parts, this is also the legacy encoding
+	fn deserialize_unversioned(s: &str) -> Result<Utxo, OperationError> {
+		let parts: Vec<_> = s.split('&').collect();
+		if parts.len() != UTXO_PARTS {
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidUtxoLength,
+				format!("Expected {} parts, got {}", UTXO_PARTS, parts.len()),
+			));
+		}
+		let curve: Curve = parts[0].parse().map_err(|_| OpStatusCode::InvalidCurve)?;
+		let backend: Backend = parts[1].parse().map_err(|_| OpStatusCode::InvalidBackend)?;
+		let amount = parts[2].parse().map_err(|_| OpStatusCode::InvalidAmount)?;
+		let chain_id = parts[3].parse().map_err(|_| OpStatusCode::InvalidChainId)?;
+		let blinding = decode_utxo_field(parts[4], OpStatusCode::InvalidBlinding)?;
+		let public_key = decode_utxo_field(parts[5], OpStatusCode::InvalidPublicKey)?;
+		let private_key = match parts[6] {
+			"" => None,
+			v => Some(decode_utxo_field(v, OpStatusCode::InvalidSecretKey)?),
+		};
+		let index = match parts[7] {
+			"" => None,
+			v => {
+				let index: u64 = v.parse().map_err(|_| OpStatusCode::InvalidUTXOIndex)?;
+				Some(index)
+			}
+		};
+
+		let utxo = Utxo::new(
+			curve,
+			backend,
+			amount,
+			chain_id,
+			Some(blinding),
+			Some(public_key.clone()),
+			private_key,
+			index,
+		)?;
+		// With a secret key the public key is derived, it has to match the stored one
+		if utxo.get_public_key() != public_key {
+			return Err(OperationError::new_with_message(
+				OpStatusCode::InvalidPublicKey,
+				"Public key doesn't match the secret key".to_string(),
+			));
+		}
+
+		Ok(utxo)
+	}
+}
+
+/// Versioned encoding `v1:<parts>:<checksum>`
+impl fmt::Display for Utxo {
+	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+		let body = self.serialize_unversioned();
+		write!(f, "{}{}:{}", UTXO_V1_PREFIX, body, utxo_checksum(&body))
+	}
+}
+
+/// Parses the versioned encoding and the legacy unversioned one
+impl FromStr for Utxo {
+	type Err = OperationError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if let Some(versioned) = s.strip_prefix(UTXO_V1_PREFIX) {
+			let (body, checksum) = versioned.rsplit_once(':').ok_or(OpStatusCode::InvalidUtxoChecksum)?;
+			if checksum != utxo_checksum(body) {
+				return Err(OpStatusCode::InvalidUtxoChecksum.into());
+			}
+			return Utxo::deserialize_unversioned(body);
+		}
+
+		// Legacy strings start with the curve, anything like `v2:` is a newer version
+		match s.split_once(':') {
+			Some((version, _)) if version.starts_with('v') => Err(OperationError::new_with_message(
+				OpStatusCode::InvalidUtxoVersion,
+				format!("Unsupported UTXO version {}", version),
+			)),
+			_ => Utxo::deserialize_unversioned(s),
 		}
 	}
+}
+
+/// Serde representation of a `Utxo`, the secret key is left out for
+/// public UTXOs
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UtxoData {
+	curve: Curve,
+	backend: Backend,
+	#[serde(with = "string")]
+	amount: u128,
+	chain_id: u64,
+	#[serde(with = "hex_bytes")]
+	blinding: Vec<u8>,
+	#[serde(with = "hex_bytes")]
+	public_key: Vec<u8>,
+	#[serde(default, skip_serializing_if = "Option::is_none", with = "optional_hex_bytes")]
+	secret_key: Option<Vec<u8>>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	index: Option<u64>,
+}
 
-	pub fn get_bn254_utxo(&self) -> Result<Utxo<Bn254Fr>, OpStatusCode> {
-		match self.inner.clone() {
-			JsUtxoInner::Bn254(utxo) => Ok(utxo),
+impl Serialize for Utxo {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		UtxoData {
+			curve: self.curve(),
+			backend: Backend::Arkworks,
+			amount: self.get_amount_raw().map_err(ser::Error::custom)?,
+			chain_id: self.get_chain_id_raw(),
+			blinding: self.get_blinding(),
+			public_key: self.get_public_key(),
+			secret_key: self.get_secret_key(),
+			index: self.get_index(),
 		}
+		.serialize(serializer)
 	}
+}
+
+impl<'de> Deserialize<'de> for Utxo {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let data = UtxoData::deserialize(deserializer)?;
+		Utxo::new(
+			data.curve,
+			data.backend,
+			data.amount,
+			data.chain_id,
+			Some(data.blinding),
+			Some(data.public_key),
+			data.secret_key,
+			data.index,
+		)
+		.map_err(|e| serde::de::Error::custom(e.error_message))
+	}
+}
+
+impl Serializable for Utxo {}
+
+#[cfg(feature = "js")]
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct JsUtxo {
+	#[wasm_bindgen(skip)]
+	pub inner: Utxo,
+}
+
+#[cfg(feature = "js")]
+impl Deref for JsUtxo {
+	type Target = Utxo;
 
+	fn deref(&self) -> &Self::Target {
+		&self.inner
+	}
+}
+
+#[cfg(feature = "js")]
+impl From<Utxo> for JsUtxo {
+	fn from(inner: Utxo) -> Self {
+		Self { inner }
+	}
+}
+
+/// Copies a secret passed from JS, the copy is wiped on drop
+#[cfg(feature = "js")]
+fn secret_bytes(value: Option<Uint8Array>) -> Option<Secret<Vec<u8>>> {
+	value.map(|value| value.to_vec().into())
+}
+
+#[cfg(feature = "js")]
+impl JsUtxo {
 	pub fn deserialize(utxo_str: &str) -> Result<JsUtxo, OperationError> {
-		utxo_str.parse().map_err(Into::into)
+		Utxo::deserialize(utxo_str).map(Into::into)
 	}
 }
 
+#[cfg(feature = "js")]
 #[wasm_bindgen]
 impl JsUtxo {
 	pub fn serialize(&self) -> JsString {
-		JsString::from(self.to_string())
+		JsString::from(self.inner.to_string())
 	}
 
 	#[wasm_bindgen(js_name = deserialize)]
@@ -260,19 +599,20 @@ impl JsUtxo {
 			}
 		};
 		let utxo = match (curve, backend) {
-			(Curve::Bn254, Backend::Arkworks) => {
-				let blinding_vec: Option<Vec<u8>> = blinding.map(|val| val.to_vec());
+			(Curve::Bn254 | Curve::Bls381, Backend::Arkworks) => {
+				let blinding = secret_bytes(blinding);
 				let public_key_vec: Option<Vec<u8>> = public_key.map(|val| val.to_vec());
-				let private_key_vec: Option<Vec<u8>> = private_key.map(|val| val.to_vec());
+				let private_key = secret_bytes(private_key);
 
-				JsUtxo::new(
-					Curve::Bn254,
+				// Cloned for the prover, which doesn't wipe them
+				Utxo::new(
+					curve,
 					Backend::Arkworks,
 					amount,
 					chain_id,
-					blinding_vec,
+					blinding.as_deref().cloned(),
 					public_key_vec,
-					private_key_vec,
+					private_key.as_deref().cloned(),
 					index,
 				)
 			}
@@ -280,7 +620,7 @@ impl JsUtxo {
 			_ => Err(OpStatusCode::InvalidNoteProtocol),
 		}?;
 
-		Ok(utxo)
+		Ok(utxo.into())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -302,10 +642,16 @@ impl JsUtxo {
 		hex::encode(amount).into()
 	}
 
+	/// The amount as a decimal for a token with `decimals`
+	#[wasm_bindgen(js_name = formatAmount)]
+	pub fn format_amount(&self, decimals: u8) -> Result<JsString, JsValue> {
+		Ok(format_units(self.get_amount_raw()?, decimals).into())
+	}
+
 	#[wasm_bindgen(getter)]
 	pub fn blinding(&self) -> JsString {
-		let blinding = self.get_blinding();
-		hex::encode(blinding).into()
+		let blinding = encode_secrets(&Secret::new(vec![self.get_blinding()]));
+		JsString::from(blinding.as_str())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -316,8 +662,8 @@ impl JsUtxo {
 
 	#[wasm_bindgen(getter)]
 	pub fn secret_key(&self) -> JsString {
-		let secret_key = self.get_secret_key().unwrap_or_default();
-		hex::encode(secret_key).into()
+		let secret_key = encode_secrets(&Secret::new(vec![self.get_secret_key().unwrap_or_default()]));
+		JsString::from(secret_key.as_str())
 	}
 
 	#[wasm_bindgen(getter)]
@@ -344,79 +690,166 @@ impl JsUtxo {
 
 		Uint8Array::from(commitment.as_slice())
 	}
-}
-
-impl fmt::Display for JsUtxo {
-	fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-		let curve = match self.inner {
-			JsUtxoInner::Bn254(_) => Curve::Bn254.to_string(),
-		};
-		let backend = Backend::Arkworks.to_string();
-		let amount = self.get_amount_raw().to_string();
-		let chain_id = self.get_chain_id_raw().to_string();
-		let index = self.get_index().map(|v| v.to_string()).unwrap_or_default();
-		let blinding = hex::encode(self.get_blinding());
-		let public_key = hex::encode(self.get_public_key());
-		let private_key = hex::encode(self.get_secret_key().unwrap_or_default());
 
-		let sec = [
-			curve,
-			backend,
-			amount,
-			chain_id,
-			blinding,
-			public_key,
-			private_key,
-			index,
-		]
-		.join("&");
+	/// The UTXO without its secret key and blinding, as a plain object
+	#[wasm_bindgen(js_name = redacted)]
+	pub fn redacted_js(&self) -> Result<JsValue, JsValue> {
+		Ok(to_js_object(&self.inner.redacted())?)
+	}
+}
 
-		write!(f, "{}", sec)
+#[cfg(feature = "js")]
+impl_js_serialization!(JsUtxo => Utxo);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_serialize_and_deserialize_utxo() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		let utxo_str = utxo.to_string();
+		let parsed = Utxo::deserialize(&utxo_str).unwrap();
+
+		assert_eq!(parsed.get_amount_raw().unwrap(), 10);
+		assert_eq!(parsed.get_chain_id_raw(), 1);
+		assert_eq!(parsed.get_index(), Some(3));
+		assert_eq!(parsed.get_commitment(), utxo.get_commitment());
+		assert_eq!(parsed.get_nullifier(), utxo.get_nullifier());
+		assert_eq!(parsed.to_string(), utxo_str);
+	}
+
+	#[test]
+	fn should_create_bls381_utxos() {
+		let utxo = Utxo::new(Curve::Bls381, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		assert_eq!(utxo.curve(), Curve::Bls381);
+		assert!(utxo.get_bn254_utxo().is_err());
+		assert!(utxo.get_nullifier().is_ok());
+
+		let parsed = Utxo::deserialize(&utxo.to_string()).unwrap();
+		assert_eq!(parsed.curve(), Curve::Bls381);
+		assert_eq!(parsed.get_amount_raw().unwrap(), 10);
+		assert_eq!(parsed.get_commitment(), utxo.get_commitment());
+		assert_eq!(parsed.get_nullifier(), utxo.get_nullifier());
+
+		// The same secrets give another commitment on the other curve
+		let bn254 = Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			10,
+			1,
+			Some(utxo.get_blinding()),
+			None,
+			utxo.get_secret_key(),
+			Some(3),
+		)
+		.unwrap();
+		assert_ne!(bn254.get_commitment(), utxo.get_commitment());
+	}
+
+	#[test]
+	fn should_create_public_utxo_without_secret_key() {
+		let owned = Utxo::new(Curve::Bn254, Backend::Arkworks, 5, 1, None, None, None, None).unwrap();
+		let public = Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			5,
+			1,
+			Some(owned.get_blinding()),
+			Some(owned.get_public_key()),
+			None,
+			None,
+		)
+		.unwrap();
+
+		assert!(public.get_secret_key().is_none());
+		assert_eq!(public.get_commitment(), owned.get_commitment());
+	}
+
+	#[test]
+	fn should_encode_utxo_as_json() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		let decoded = Utxo::from_json(&utxo.to_json().unwrap()).unwrap();
+		assert_eq!(decoded.to_string(), utxo.to_string());
+
+		let public = Utxo::new(
+			Curve::Bn254,
+			Backend::Arkworks,
+			5,
+			1,
+			None,
+			Some(utxo.get_public_key()),
+			None,
+			None,
+		)
+		.unwrap();
+		let json = public.to_json().unwrap();
+		assert!(!json.contains("secretKey"));
+		let decoded = Utxo::from_json(&json).unwrap();
+		assert!(decoded.get_secret_key().is_none());
+		assert_eq!(decoded.get_commitment(), public.get_commitment());
+	}
+
+	#[test]
+	fn should_parse_versioned_and_legacy_utxos() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		let versioned = utxo.to_string();
+		assert!(versioned.starts_with(UTXO_V1_PREFIX));
+
+		let legacy = utxo.serialize_unversioned();
+		let parsed = Utxo::deserialize(&legacy).unwrap();
+		assert_eq!(parsed.get_commitment(), utxo.get_commitment());
+		assert_eq!(parsed.to_string(), versioned);
+	}
+
+	#[test]
+	fn should_reject_malformed_utxos() {
+		let utxo = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, Some(3)).unwrap();
+		let code = |s: &str| Utxo::deserialize(s).unwrap_err().code;
+		let legacy = utxo.serialize_unversioned();
+		let parts: Vec<_> = legacy.split('&').collect();
+
+		assert_eq!(code(&parts[..7].join("&")), OpStatusCode::InvalidUtxoLength);
+		assert_eq!(code(&format!("v2:{}", legacy)), OpStatusCode::InvalidUtxoVersion);
+
+		let mut tampered = utxo.to_string();
+		tampered.replace_range(5..6, if &tampered[5..6] == "0" { "1" } else { "0" });
+		assert_eq!(code(&tampered), OpStatusCode::InvalidUtxoChecksum);
+		assert_eq!(code(&format!("v1:{}", legacy)), OpStatusCode::InvalidUtxoChecksum);
+
+		let mut invalid = parts.clone();
+		invalid[4] = "zz";
+		assert_eq!(code(&invalid.join("&")), OpStatusCode::InvalidBlinding);
+
+		let other = Utxo::new(Curve::Bn254, Backend::Arkworks, 10, 1, None, None, None, None).unwrap();
+		let other_public_key = hex::encode(other.get_public_key());
+		let mut mismatched = parts.clone();
+		mismatched[5] = &other_public_key;
+		assert_eq!(code(&mismatched.join("&")), OpStatusCode::InvalidPublicKey);
+
+		let mut short_key = parts;
+		short_key[6] = "00";
+		assert_eq!(code(&short_key.join("&")), OpStatusCode::InvalidSecretKey);
 	}
 }
 
-impl FromStr for JsUtxo {
-	type Err = OperationError;
+#[cfg(all(test, feature = "js"))]
+mod js_test {
+	use wasm_bindgen_test::*;
+	use zeroize::Zeroize;
 
-	fn from_str(s: &str) -> Result<Self, Self::Err> {
-		let parts: Vec<_> = s.split('&').collect();
-		let curve: Curve = parts[0].parse().map_err(|_| OpStatusCode::InvalidCurve)?;
-		let backend: Backend = parts[1].parse().map_err(|_| OpStatusCode::InvalidBackend)?;
-		let amount = parts[2].parse().map_err(|_| OpStatusCode::InvalidAmount)?;
-		let chain_id = parts[3].parse().map_err(|_| OpStatusCode::InvalidChainId)?;
-		let public_key = hex::decode(parts[5]).map_err(|_| OpStatusCode::Unknown)?;
-		let index = match parts[7] {
-			"" => None,
-			v => {
-				let index: u64 = v.parse().map_err(|_| OpStatusCode::InvalidUTXOIndex)?;
-				Some(index)
-			}
-		};
-		let blinding = hex::decode(parts[4]).map_err(|_| OpStatusCode::Unknown)?;
-		let private_key = hex::decode(parts[6]).map_err(|_| OpStatusCode::Unknown)?;
-
-		if private_key.len() == 32 {
-			JsUtxo::new(
-				curve,
-				backend,
-				amount,
-				chain_id,
-				Some(blinding),
-				Some(public_key),
-				Some(private_key),
-				index,
-			)
-		} else {
-			JsUtxo::new(
-				curve,
-				backend,
-				amount,
-				chain_id,
-				Some(blinding),
-				Some(public_key),
-				None,
-				index,
-			)
-		}
+	use super::*;
+
+	#[wasm_bindgen_test]
+	fn should_wipe_the_copied_keys() {
+		let key = Uint8Array::from(&[7u8; 32][..]);
+		let mut copy = secret_bytes(Some(key.clone())).unwrap();
+		assert_eq!(copy.as_slice(), &[7u8; 32]);
+
+		// What the drop of `Secret` runs
+		copy.zeroize();
+		assert!(copy.is_empty());
+		assert_eq!(key.to_vec(), [7u8; 32]);
+		assert!(secret_bytes(None).is_none());
 	}
 }